@@ -0,0 +1,140 @@
+use std::{borrow::Cow, collections::HashMap, fs, sync::OnceLock};
+
+use crate::{locale::Locale, AppResult, ApplicationError};
+
+use super::{sort_by_score, AssociationModel};
+
+const ENGLISH: &str = include_str!("../../data/associations/en.txt");
+const GERMAN: &str = include_str!("../../data/associations/de.txt");
+const TURKISH: &str = include_str!("../../data/associations/tr.txt");
+
+/// A word and the words it was listed with.
+const LISTED_WEIGHT: f32 = 1.0;
+/// Two words listed together under a third.
+const SIBLING_WEIGHT: f32 = 0.25;
+
+/// An association model built from a co-occurrence table.
+///
+/// Each line of the table reads `word related related ...`, and `#` starts a
+/// comment. Relations are symmetric, and a pair gains weight every time it
+/// appears together, so words listed under each other score highest. The
+/// table is only parsed the first time the model is queried.
+///
+/// Words are looked up after the locale's normalization, the same way
+/// guesses are matched, and come back spelled as first listed.
+pub struct CooccurrenceModel {
+    locale: Locale,
+    source: Cow<'static, str>,
+    table: OnceLock<Table>,
+}
+
+struct Table {
+    // Normalized word -> normalized related word -> weight
+    relations: HashMap<String, HashMap<String, f32>>,
+    // Normalized word -> the word as first listed
+    spellings: HashMap<String, String>,
+}
+
+impl Table {
+    fn spelling<'a>(&'a self, word: &'a str) -> &'a str {
+        self.spellings.get(word).map_or(word, |w| w.as_str())
+    }
+}
+
+impl CooccurrenceModel {
+    pub fn new(locale: Locale, source: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            locale,
+            source: source.into(),
+            table: OnceLock::new(),
+        }
+    }
+
+    /// The table shipped with the game for a locale, borrowed rather than
+    /// copied.
+    pub fn bundled(locale: Locale) -> Self {
+        let source = match locale {
+            Locale::English => ENGLISH,
+            Locale::German => GERMAN,
+            Locale::Turkish => TURKISH,
+        };
+        Self::new(locale, source)
+    }
+
+    pub fn from_file(locale: Locale, path: &str) -> AppResult<Self> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| ApplicationError::config(&format!("Could not read {}", path)).caused_by(e))?;
+        Ok(Self::new(locale, source))
+    }
+
+    fn table(&self) -> &Table {
+        self.table.get_or_init(|| parse(self.locale, &self.source))
+    }
+}
+
+fn parse(locale: Locale, source: &str) -> Table {
+    let mut relations: HashMap<String, HashMap<String, f32>> = HashMap::new();
+    let mut spellings = HashMap::new();
+    let mut add = |a: &str, b: &str, weight: f32| {
+        if a == b {
+            return;
+        }
+        *relations.entry(a.into()).or_default().entry(b.into()).or_default() += weight;
+        *relations.entry(b.into()).or_default().entry(a.into()).or_default() += weight;
+    };
+
+    for line in source.lines().map(|l| l.split('#').next().unwrap_or_default()) {
+        let words: Vec<_> = line
+            .split_whitespace()
+            .map(|listed| {
+                let word = locale.normalize(listed);
+                spellings.entry(word.clone()).or_insert_with(|| listed.to_string());
+                word
+            })
+            .collect();
+        let (word, listed) = match words.split_first() {
+            Some(split) => split,
+            None => continue,
+        };
+        for (i, other) in listed.iter().enumerate() {
+            add(word, other, LISTED_WEIGHT);
+            for sibling in &listed[i + 1..] {
+                add(other, sibling, SIBLING_WEIGHT);
+            }
+        }
+    }
+    Table { relations, spellings }
+}
+
+impl AssociationModel for CooccurrenceModel {
+    fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    fn vocabulary(&self) -> Vec<&str> {
+        let table = self.table();
+        let mut words: Vec<_> = table.relations.keys().map(|w| table.spelling(w)).collect();
+        words.sort_unstable();
+        words
+    }
+
+    fn similarity(&self, a: &str, b: &str) -> f32 {
+        self.table()
+            .relations
+            .get(&self.locale.normalize(a))
+            .and_then(|related| related.get(&self.locale.normalize(b)))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    fn related(&self, word: &str) -> Vec<(&str, f32)> {
+        let table = self.table();
+        let mut related: Vec<_> = table
+            .relations
+            .get(&self.locale.normalize(word))
+            .map(|related| related.iter().map(|(w, score)| (table.spelling(w), *score)).collect())
+            .unwrap_or_default();
+        sort_by_score(&mut related);
+        related
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use crate::{locale::Locale, trait_enum, AppResult};
+
+use self::cooccurrence::CooccurrenceModel;
+
+pub mod cooccurrence;
+
+pub enum AssociationModelType {
+    /// The co-occurrence table shipped with the game for a locale.
+    Bundled(Locale),
+    /// A co-occurrence table in the same format, read from disk.
+    File { locale: Locale, path: String },
+}
+
+/// Scores how strongly words relate to each other.
+///
+/// Scores are only comparable within one model; zero means unrelated.
+pub trait AssociationModel {
+    /// The language of the words the model knows.
+    fn locale(&self) -> Locale;
+    fn vocabulary(&self) -> Vec<&str>;
+    fn similarity(&self, a: &str, b: &str) -> f32;
+    /// Words related to `word`, strongest first.
+    fn related(&self, word: &str) -> Vec<(&str, f32)>;
+
+    /// Words related to both `a` and `b`, strongest first.
+    fn related_to_both(&self, a: &str, b: &str) -> Vec<(&str, f32)> {
+        let to_b: HashMap<_, _> = self.related(b).into_iter().collect();
+        let mut both: Vec<_> = self
+            .related(a)
+            .into_iter()
+            .filter_map(|(word, score)| to_b.get(word).map(|other| (word, score.min(*other))))
+            .collect();
+        sort_by_score(&mut both);
+        both
+    }
+
+    /// Orders `candidates` by their total similarity to the `context` words.
+    fn rank<'a>(&self, candidates: &[&'a str], context: &[&str]) -> Vec<(&'a str, f32)> {
+        let mut ranked: Vec<_> = candidates
+            .iter()
+            .map(|candidate| {
+                let score = context.iter().map(|word| self.similarity(candidate, word)).sum();
+                (*candidate, score)
+            })
+            .collect();
+        sort_by_score(&mut ranked);
+        ranked
+    }
+}
+
+trait_enum!(AssociationModel, AssociationModelEnum, CooccurrenceModel);
+
+pub struct AssociationModelFactory;
+
+impl AssociationModelFactory {
+    pub fn create(model_type: AssociationModelType) -> AppResult<AssociationModelEnum> {
+        Ok(match model_type {
+            AssociationModelType::Bundled(locale) => {
+                AssociationModelEnum::CooccurrenceModel(CooccurrenceModel::bundled(locale))
+            }
+            AssociationModelType::File { locale, path } => {
+                AssociationModelEnum::CooccurrenceModel(CooccurrenceModel::from_file(locale, &path)?)
+            }
+        })
+    }
+}
+
+/// Sorts strongest first, breaking ties alphabetically so results are stable.
+pub(crate) fn sort_by_score(scored: &mut [(&str, f32)]) {
+    scored.sort_by(|(a, a_score), (b, b_score)| b_score.total_cmp(a_score).then_with(|| a.cmp(b)));
+}
@@ -0,0 +1,35 @@
+use rand::seq::SliceRandom;
+
+use crate::association::AssociationModelEnum;
+
+use super::{unused, BotStrategy, History};
+
+/// Follows the partner: picks any word related to their last guess.
+pub struct EasyStrategy {
+    model: AssociationModelEnum,
+}
+
+impl EasyStrategy {
+    pub fn new(model: AssociationModelEnum) -> Self {
+        Self { model }
+    }
+}
+
+impl BotStrategy for EasyStrategy {
+    fn guess(&mut self, history: &History) -> Option<String> {
+        let mut rng = rand::thread_rng();
+        let candidates = match history.last() {
+            Some((partner, _)) => {
+                let related = self.model.related(partner).into_iter().map(|(w, _)| w).collect();
+                unused(related, history, self.model.locale())
+            }
+            None => vec![],
+        };
+        let candidates = if candidates.is_empty() {
+            unused(self.model.vocabulary(), history, self.model.locale())
+        } else {
+            candidates
+        };
+        candidates.choose(&mut rng).map(|w| w.to_string())
+    }
+}
@@ -0,0 +1,46 @@
+use rand::seq::SliceRandom;
+
+use crate::association::AssociationModelEnum;
+
+use super::{unused, BotStrategy, History};
+
+/// Converges: picks the word most related to both of the last two guesses.
+pub struct HardStrategy {
+    model: AssociationModelEnum,
+}
+
+impl HardStrategy {
+    pub fn new(model: AssociationModelEnum) -> Self {
+        Self { model }
+    }
+}
+
+impl BotStrategy for HardStrategy {
+    fn guess(&mut self, history: &History) -> Option<String> {
+        let mut rng = rand::thread_rng();
+        let (partner, own) = match history.last() {
+            Some(last) => last,
+            None => {
+                let words = self.model.vocabulary();
+                return words.choose(&mut rng).map(|w| w.to_string());
+            }
+        };
+
+        // Prefer a bridge between the two words; when there is none, lean
+        // towards the partner, who is probably doing the same.
+        let both: Vec<_> = self.model.related_to_both(partner, own).into_iter().map(|(w, _)| w).collect();
+        let mut candidates = unused(both, history, self.model.locale());
+        if candidates.is_empty() {
+            let related = self.model.related(partner).into_iter().map(|(w, _)| w).collect();
+            candidates = unused(related, history, self.model.locale());
+        }
+        if candidates.is_empty() {
+            candidates = unused(self.model.vocabulary(), history, self.model.locale());
+        }
+
+        let ranked = self.model.rank(&candidates, &[partner, partner, own]);
+        let best = ranked.first().map_or(0.0, |(_, score)| *score);
+        let top: Vec<_> = ranked.into_iter().filter(|(_, score)| *score >= best).map(|(w, _)| w).collect();
+        top.choose(&mut rng).map(|w| w.to_string())
+    }
+}
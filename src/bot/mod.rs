@@ -0,0 +1,107 @@
+use rand::seq::SliceRandom;
+
+use crate::{
+    association::{AssociationModelEnum, AssociationModelFactory, AssociationModelType},
+    dictionary::{DictionaryConfig, DictionaryEnum, DictionaryFactory, DictionaryType},
+    game::Game,
+    locale::Locale,
+    trait_enum, AppResult,
+};
+
+use self::{easy::EasyStrategy, hard::HardStrategy};
+
+pub mod easy;
+pub mod hard;
+
+/// Completed rounds as `(partner's guess, bot's guess)`.
+pub type History = [(String, String)];
+
+pub enum Difficulty {
+    Easy,
+    Hard,
+}
+
+pub trait BotStrategy {
+    /// The next guess, or `None` if the model has no word left to offer.
+    fn guess(&mut self, history: &History) -> Option<String>;
+}
+
+trait_enum!(BotStrategy, BotStrategyEnum, EasyStrategy, HardStrategy);
+
+pub struct BotStrategyFactory;
+
+impl BotStrategyFactory {
+    pub fn create(difficulty: Difficulty, model: AssociationModelEnum) -> BotStrategyEnum {
+        match difficulty {
+            Difficulty::Easy => BotStrategyEnum::EasyStrategy(EasyStrategy::new(model)),
+            Difficulty::Hard => BotStrategyEnum::HardStrategy(HardStrategy::new(model)),
+        }
+    }
+}
+
+/// A computer opponent playing one side of a game.
+///
+/// It only guesses words its dictionary knows. When the strategy comes up
+/// empty or with an unknown word, it picks a random word from the
+/// dictionary instead.
+pub struct BotPlayer {
+    username: String,
+    strategy: BotStrategyEnum,
+    dictionary: DictionaryEnum,
+}
+
+impl BotPlayer {
+    /// A bot backed by the bundled association table and dictionary for
+    /// `locale`.
+    pub fn new(username: &str, difficulty: Difficulty, locale: Locale) -> AppResult<Self> {
+        let model = AssociationModelFactory::create(AssociationModelType::Bundled(locale))?;
+        let dictionary = DictionaryFactory::create(DictionaryType::Bundled(locale), DictionaryConfig::default())?;
+        Ok(Self::with_model(username, difficulty, model, dictionary))
+    }
+
+    pub fn with_model(username: &str, difficulty: Difficulty, model: AssociationModelEnum, dictionary: DictionaryEnum) -> Self {
+        Self {
+            username: username.into(),
+            strategy: BotStrategyFactory::create(difficulty, model),
+            dictionary,
+        }
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Picks a guess for the current round from the revealed rounds.
+    pub fn guess(&mut self, game: &Game) -> String {
+        let is_player_1 = game.player_1_username() == Some(self.username.as_str());
+        let history: Vec<_> = game.guesses()[..game.current_round()]
+            .iter()
+            .map(|(g1, g2)| {
+                let g1 = g1.clone().unwrap_or_default();
+                let g2 = g2.clone().unwrap_or_default();
+                if is_player_1 {
+                    (g2, g1)
+                } else {
+                    (g1, g2)
+                }
+            })
+            .collect();
+        match self.strategy.guess(&history) {
+            Some(guess) if self.dictionary.contains(&guess) => guess,
+            _ => {
+                let words = unused(self.dictionary.words(), &history, self.dictionary.locale());
+                words.choose(&mut rand::thread_rng()).map(|w| w.to_string()).unwrap_or_default()
+            }
+        }
+    }
+}
+
+/// Drops words that were already guessed by either player, comparing them
+/// the way the game's `locale` matches guesses.
+fn unused<'a>(words: Vec<&'a str>, history: &History, locale: Locale) -> Vec<&'a str> {
+    let used: Vec<_> = history
+        .iter()
+        .flat_map(|(a, b)| [locale.normalize(a), locale.normalize(b)])
+        .collect();
+    words.into_iter().filter(|w| !used.contains(&locale.normalize(w))).collect()
+}
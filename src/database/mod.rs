@@ -17,6 +17,10 @@ pub trait Database {
     fn save_game(&mut self, game: Game) -> AppResult<()>;
     fn get_games(&self) -> AppResult<Vec<Game>>;
     fn get_game(&self, game_id: &str) -> AppResult<Option<Game>>;
+    /// Writes any buffered state to durable storage.
+    fn flush(&mut self) -> AppResult<()> {
+        Ok(())
+    }
 }
 
 trait_enum!(Database, DatabaseEnum, InMemoryDatabase);
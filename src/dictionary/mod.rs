@@ -0,0 +1,137 @@
+use std::fmt;
+
+use crate::{
+    locale::{messages, Locale},
+    trait_enum, AppResult,
+};
+
+use self::word_list::WordListDictionary;
+
+pub mod word_list;
+
+/// Why a dictionary turned a guess away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidGuess {
+    Empty,
+    NotASingleWord,
+    NotAWord { word: String, suggestions: Vec<String> },
+}
+
+impl InvalidGuess {
+    pub fn text(&self, locale: Locale) -> String {
+        match self {
+            InvalidGuess::Empty => messages::text(locale, "guess.empty", &[]),
+            InvalidGuess::NotASingleWord => messages::text(locale, "guess.not_single_word", &[]),
+            InvalidGuess::NotAWord { word, suggestions } if suggestions.is_empty() => {
+                messages::text(locale, "guess.not_a_word", &[("word", word)])
+            }
+            InvalidGuess::NotAWord { word, suggestions } => {
+                let suggestions = suggestions.join(", ");
+                messages::text(locale, "guess.did_you_mean", &[("word", word), ("suggestions", &suggestions)])
+            }
+        }
+    }
+}
+
+impl fmt::Display for InvalidGuess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidGuess::Empty => write!(f, "empty guess"),
+            InvalidGuess::NotASingleWord => write!(f, "not a single word"),
+            InvalidGuess::NotAWord { word, .. } => write!(f, "\"{}\" is not a word", word),
+        }
+    }
+}
+
+pub enum DictionaryType {
+    /// The word list shipped with the game for a locale.
+    Bundled(Locale),
+    /// A word list read from disk, one word per line.
+    File { locale: Locale, path: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct DictionaryConfig {
+    /// Accept guesses of several words, as long as each one is known.
+    pub allow_phrases: bool,
+    /// How many corrections to offer for an unknown word.
+    pub max_suggestions: usize,
+}
+
+impl Default for DictionaryConfig {
+    fn default() -> Self {
+        Self {
+            allow_phrases: false,
+            max_suggestions: 3,
+        }
+    }
+}
+
+pub trait Dictionary {
+    fn locale(&self) -> Locale;
+    fn config(&self) -> &DictionaryConfig;
+    fn contains(&self, word: &str) -> bool;
+    /// Every known word, as listed.
+    fn words(&self) -> Vec<&str>;
+    /// Known words close to `word`, closest first.
+    fn suggest(&self, word: &str) -> Vec<String>;
+
+    /// Checks a guess, returning it trimmed.
+    fn validate(&self, guess: &str) -> AppResult<String> {
+        let guess = guess.trim();
+        let words: Vec<_> = guess.split_whitespace().collect();
+        if words.is_empty() {
+            return Err(InvalidGuess::Empty.into());
+        }
+        if words.len() > 1 && !self.config().allow_phrases {
+            return Err(InvalidGuess::NotASingleWord.into());
+        }
+
+        match words.into_iter().find(|word| !self.contains(word)) {
+            Some(word) => Err(InvalidGuess::NotAWord {
+                word: word.into(),
+                suggestions: self.suggest(word),
+            }
+            .into()),
+            None => Ok(guess.into()),
+        }
+    }
+}
+
+trait_enum!(Dictionary, DictionaryEnum, WordListDictionary);
+
+pub struct DictionaryFactory;
+
+impl DictionaryFactory {
+    pub fn create(dictionary_type: DictionaryType, config: DictionaryConfig) -> AppResult<DictionaryEnum> {
+        let dictionary = match dictionary_type {
+            DictionaryType::Bundled(locale) => WordListDictionary::bundled(locale, config),
+            DictionaryType::File { locale, path } => WordListDictionary::from_file(locale, &path, config)?,
+        };
+        Ok(DictionaryEnum::WordListDictionary(dictionary))
+    }
+}
+
+/// The optimal string alignment distance: insertions, deletions,
+/// substitutions and swaps of neighbouring letters each cost one.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    rows[0] = (0..=b.len()).collect();
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut best = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
@@ -0,0 +1,86 @@
+use std::{collections::HashMap, fs};
+
+use crate::{locale::Locale, AppResult, ApplicationError};
+
+use super::{edit_distance, Dictionary, DictionaryConfig};
+
+const ENGLISH: &str = include_str!("../../data/dictionaries/en.txt");
+const GERMAN: &str = include_str!("../../data/dictionaries/de.txt");
+const TURKISH: &str = include_str!("../../data/dictionaries/tr.txt");
+
+/// A dictionary backed by a plain word list, one word per line, with `#`
+/// starting a comment. Words are compared after the locale's normalization.
+pub struct WordListDictionary {
+    locale: Locale,
+    config: DictionaryConfig,
+    // Normalized word -> the word as listed
+    words: HashMap<String, String>,
+}
+
+impl WordListDictionary {
+    pub fn new(locale: Locale, word_list: &str, config: DictionaryConfig) -> Self {
+        let words = word_list
+            .lines()
+            .map(|l| l.split('#').next().unwrap_or_default().trim())
+            .filter(|w| !w.is_empty())
+            .map(|w| (locale.normalize(w), w.to_string()))
+            .collect();
+        Self { locale, config, words }
+    }
+
+    pub fn bundled(locale: Locale, config: DictionaryConfig) -> Self {
+        let word_list = match locale {
+            Locale::English => ENGLISH,
+            Locale::German => GERMAN,
+            Locale::Turkish => TURKISH,
+        };
+        Self::new(locale, word_list, config)
+    }
+
+    pub fn from_file(locale: Locale, path: &str, config: DictionaryConfig) -> AppResult<Self> {
+        let word_list = fs::read_to_string(path)
+            .map_err(|e| ApplicationError::config(&format!("Could not read {}", path)).caused_by(e))?;
+        Ok(Self::new(locale, &word_list, config))
+    }
+}
+
+impl Dictionary for WordListDictionary {
+    fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    fn config(&self) -> &DictionaryConfig {
+        &self.config
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.words.contains_key(&self.locale.normalize(word))
+    }
+
+    fn words(&self) -> Vec<&str> {
+        let mut words: Vec<_> = self.words.values().map(|w| w.as_str()).collect();
+        words.sort_unstable();
+        words
+    }
+
+    fn suggest(&self, word: &str) -> Vec<String> {
+        let word = self.locale.normalize(word);
+        let length = word.chars().count();
+        // Short words only get one typo, or everything looks like a match.
+        let max_distance = if length <= 4 { 1 } else { 2 };
+
+        let mut close: Vec<_> = self
+            .words
+            .iter()
+            .filter(|(w, _)| w.chars().count().abs_diff(length) <= max_distance)
+            .map(|(w, listed)| (edit_distance(&word, w), listed))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+        close.sort();
+        close
+            .into_iter()
+            .take(self.config.max_suggestions)
+            .map(|(_, w)| w.clone())
+            .collect()
+    }
+}
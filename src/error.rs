@@ -0,0 +1,229 @@
+use std::{error::Error, fmt, io};
+
+use crate::{
+    dictionary::InvalidGuess,
+    locale::{messages, Locale},
+};
+
+type Source = Box<dyn Error + Send + Sync>;
+
+/// What a lookup was looking for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entity {
+    Player,
+    Game,
+}
+
+impl fmt::Display for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entity::Player => write!(f, "player"),
+            Entity::Game => write!(f, "game"),
+        }
+    }
+}
+
+/// Everything that can go wrong in the game or a runner.
+///
+/// Game rule violations carry what a player needs to be told about them;
+/// `user_message` turns them into catalog text. Failures of the world
+/// around the game (storage, transport, configuration, IO) keep their
+/// underlying error as the `source`, and players only see a generic apology.
+#[derive(Debug)]
+pub enum ApplicationError {
+    NotFound { entity: Entity, id: String },
+    AlreadyExists { entity: Entity, id: String },
+    GameFull,
+    GameComplete,
+    /// The player already holds a seat in this game.
+    AlreadyJoined,
+    /// The player is still seated in the unfinished game `game_id`.
+    InAnotherGame { game_id: String },
+    AlreadyGuessed,
+    NotInGame,
+    /// The secret given doesn't belong to the player it claims to.
+    Unauthorized,
+    NoCurrentGame,
+    /// A correction for a round the player hasn't guessed in yet.
+    NoGuess { round: usize },
+    LanguageMismatch(Locale),
+    InvalidGuess(InvalidGuess),
+    NoHint,
+    UnknownLanguage(String),
+    InvalidDuration(String),
+    Storage { message: String, source: Option<Source> },
+    Transport { message: String, source: Option<Source> },
+    Config { message: String, source: Option<Source> },
+    Io(io::Error),
+}
+
+impl ApplicationError {
+    pub fn not_found(entity: Entity, id: &str) -> Self {
+        ApplicationError::NotFound { entity, id: id.into() }
+    }
+
+    pub fn already_exists(entity: Entity, id: &str) -> Self {
+        ApplicationError::AlreadyExists { entity, id: id.into() }
+    }
+
+    pub fn storage(message: &str) -> Self {
+        ApplicationError::Storage { message: message.into(), source: None }
+    }
+
+    pub fn transport(message: &str) -> Self {
+        ApplicationError::Transport { message: message.into(), source: None }
+    }
+
+    pub fn config(message: &str) -> Self {
+        ApplicationError::Config { message: message.into(), source: None }
+    }
+
+    /// Attaches the error that caused this one. Only storage, transport and
+    /// config errors keep a source; other variants are returned unchanged.
+    pub fn caused_by<E: Error + Send + Sync + 'static>(mut self, cause: E) -> Self {
+        match &mut self {
+            ApplicationError::Storage { source, .. }
+            | ApplicationError::Transport { source, .. }
+            | ApplicationError::Config { source, .. } => *source = Some(Box::new(cause)),
+            _ => {}
+        }
+        self
+    }
+
+    /// A short, stable name for the error, for machine-readable replies.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApplicationError::NotFound { .. } => "not_found",
+            ApplicationError::AlreadyExists { .. } => "already_exists",
+            ApplicationError::GameFull => "game_full",
+            ApplicationError::GameComplete => "game_complete",
+            ApplicationError::AlreadyJoined => "already_joined",
+            ApplicationError::InAnotherGame { .. } => "in_another_game",
+            ApplicationError::AlreadyGuessed => "already_guessed",
+            ApplicationError::NotInGame => "not_in_game",
+            ApplicationError::Unauthorized => "unauthorized",
+            ApplicationError::NoCurrentGame => "no_current_game",
+            ApplicationError::NoGuess { .. } => "no_guess",
+            ApplicationError::LanguageMismatch(_) => "language_mismatch",
+            ApplicationError::InvalidGuess(_) => "invalid_guess",
+            ApplicationError::NoHint => "no_hint",
+            ApplicationError::UnknownLanguage(_) => "unknown_language",
+            ApplicationError::InvalidDuration(_) => "invalid_duration",
+            ApplicationError::Storage { .. } => "storage",
+            ApplicationError::Transport { .. } => "transport",
+            ApplicationError::Config { .. } => "config",
+            ApplicationError::Io(_) => "io",
+        }
+    }
+
+    /// The text to reply to a player with, in their language.
+    pub fn user_message(&self, locale: Locale) -> String {
+        let text = |key: &str| messages::text(locale, key, &[]);
+        match self {
+            ApplicationError::NotFound { entity: Entity::Player, .. } => text("error.player_not_found"),
+            ApplicationError::NotFound { entity: Entity::Game, .. } => text("error.game_not_found"),
+            ApplicationError::AlreadyExists { entity: Entity::Player, .. } => text("error.player_exists"),
+            ApplicationError::AlreadyExists { entity: Entity::Game, .. } => text("error.game_exists"),
+            ApplicationError::GameFull => text("error.game_full"),
+            ApplicationError::GameComplete => text("error.game_complete"),
+            ApplicationError::AlreadyJoined => text("error.already_joined"),
+            ApplicationError::InAnotherGame { game_id } => {
+                messages::text(locale, "error.in_another_game", &[("code", game_id)])
+            }
+            ApplicationError::AlreadyGuessed => text("error.already_guessed"),
+            ApplicationError::NotInGame => text("error.not_in_game"),
+            ApplicationError::Unauthorized => text("error.unauthorized"),
+            ApplicationError::NoCurrentGame => text("error.no_current_game"),
+            ApplicationError::NoGuess { round } => messages::text(locale, "error.no_guess", &[("round", round)]),
+            ApplicationError::LanguageMismatch(language) => {
+                messages::text(locale, "error.language_mismatch", &[("language", language)])
+            }
+            ApplicationError::InvalidGuess(invalid) => invalid.text(locale),
+            ApplicationError::NoHint => text("hint.none"),
+            ApplicationError::UnknownLanguage(_) => {
+                let codes: Vec<_> = Locale::ALL.iter().map(|l| l.code()).collect();
+                messages::text(locale, "language.unknown", &[("codes", &codes.join(", "))])
+            }
+            ApplicationError::InvalidDuration(duration) => {
+                messages::text(locale, "error.invalid_duration", &[("duration", duration)])
+            }
+            ApplicationError::Storage { .. }
+            | ApplicationError::Transport { .. }
+            | ApplicationError::Config { .. }
+            | ApplicationError::Io(_) => text("error.generic"),
+        }
+    }
+}
+
+impl fmt::Display for ApplicationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplicationError::NotFound { entity, id } => write!(f, "Could not find {} {}", entity, id),
+            ApplicationError::AlreadyExists { entity, id } => write!(f, "There already is a {} {}", entity, id),
+            ApplicationError::GameFull => write!(f, "Game is full"),
+            ApplicationError::GameComplete => write!(f, "Game is already complete"),
+            ApplicationError::AlreadyJoined => write!(f, "Player already joined this game"),
+            ApplicationError::InAnotherGame { game_id } => write!(f, "Player is still in game {}", game_id),
+            ApplicationError::AlreadyGuessed => write!(f, "Player already guessed for this round"),
+            ApplicationError::NotInGame => write!(f, "Player is not in this game"),
+            ApplicationError::Unauthorized => write!(f, "Wrong or missing player secret"),
+            ApplicationError::NoCurrentGame => write!(f, "Player does not have a current game"),
+            ApplicationError::NoGuess { round } => write!(f, "Player has not guessed in round {}", round),
+            ApplicationError::LanguageMismatch(locale) => write!(f, "Game is played in {}", locale),
+            ApplicationError::InvalidGuess(invalid) => write!(f, "Invalid guess: {}", invalid),
+            ApplicationError::NoHint => write!(f, "No hint is available for this round"),
+            ApplicationError::UnknownLanguage(language) => write!(f, "\"{}\" is not a supported language", language),
+            ApplicationError::InvalidDuration(duration) => write!(f, "\"{}\" is not a valid duration", duration),
+            ApplicationError::Storage { message, .. } => write!(f, "Storage error: {}", message),
+            ApplicationError::Transport { message, .. } => write!(f, "Transport error: {}", message),
+            ApplicationError::Config { message, .. } => write!(f, "Configuration error: {}", message),
+            ApplicationError::Io(e) => write!(f, "IO error: {}", e),
+        }
+    }
+}
+
+impl Error for ApplicationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ApplicationError::Storage { source, .. }
+            | ApplicationError::Transport { source, .. }
+            | ApplicationError::Config { source, .. } => source.as_deref().map(|e| e as &(dyn Error + 'static)),
+            ApplicationError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ApplicationError {
+    fn from(e: io::Error) -> Self {
+        ApplicationError::Io(e)
+    }
+}
+
+// `tokio_xmpp::Error` doesn't implement `Error` itself, so only the IO
+// errors it wraps are kept as a source.
+impl From<tokio_xmpp::Error> for ApplicationError {
+    fn from(e: tokio_xmpp::Error) -> Self {
+        let error = ApplicationError::transport(&e.to_string());
+        match e {
+            tokio_xmpp::Error::Io(io) => error.caused_by(io),
+            _ => error,
+        }
+    }
+}
+
+// JIDs parsed while running come from stanzas, so a bad one is the peer's
+// doing. The runner checks the JIDs in its settings itself, as config errors.
+impl From<xmpp_parsers::JidParseError> for ApplicationError {
+    fn from(e: xmpp_parsers::JidParseError) -> Self {
+        ApplicationError::transport("Invalid JID").caused_by(e)
+    }
+}
+
+impl From<InvalidGuess> for ApplicationError {
+    fn from(invalid: InvalidGuess) -> Self {
+        ApplicationError::InvalidGuess(invalid)
+    }
+}
+
+pub type AppResult<T> = Result<T, ApplicationError>;
@@ -0,0 +1,174 @@
+use std::fmt::Display;
+
+use chrono::prelude::*;
+
+use crate::{
+    locale::{messages, Locale},
+    time::{
+        duration::FormattedDuration,
+        format::{DurationFormat, DurationStyle},
+    },
+};
+
+use super::Game;
+
+const MISSING_GUESS: &str = "...";
+
+/// A runner-agnostic view of a game's round history.
+///
+/// Guesses in a round that hasn't been revealed yet are hidden, so a board
+/// can be shown to anyone. `Display` renders it as an aligned plain-text
+/// table; runners with richer formatting can walk the rows themselves.
+#[derive(Debug, Clone)]
+pub struct Board {
+    pub player_1: String,
+    pub player_2: String,
+    pub rounds: Vec<BoardRound>,
+    pub complete: bool,
+    pub duration: FormattedDuration,
+    pub locale: Locale,
+}
+
+#[derive(Debug, Clone)]
+pub struct BoardRound {
+    pub number: usize,
+    pub player_1_guess: BoardGuess,
+    pub player_2_guess: BoardGuess,
+    pub matched: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoardGuess {
+    Revealed(String),
+    Hidden,
+    Missing,
+}
+
+impl BoardGuess {
+    pub fn text(&self, locale: Locale) -> String {
+        match self {
+            BoardGuess::Revealed(guess) => guess.clone(),
+            BoardGuess::Hidden => messages::text(locale, "board.hidden", &[]),
+            BoardGuess::Missing => MISSING_GUESS.into(),
+        }
+    }
+}
+
+impl Board {
+    /// The board as of `now`, which only matters while the game is running.
+    pub fn new(game: &Game, now: DateTime<Utc>) -> Self {
+        let rounds = game
+            .guesses
+            .iter()
+            .enumerate()
+            .map(|(i, (g1, g2))| {
+                let revealed = i < game.current_round;
+                let show = |guess: &Option<String>| match guess {
+                    Some(guess) if revealed => BoardGuess::Revealed(guess.clone()),
+                    Some(_) => BoardGuess::Hidden,
+                    None => BoardGuess::Missing,
+                };
+                BoardRound {
+                    number: i + 1,
+                    player_1_guess: show(g1),
+                    player_2_guess: show(g2),
+                    matched: game.complete && i + 1 == game.current_round,
+                }
+            })
+            .collect();
+
+        let start = Utc.timestamp(game.start_time, 0);
+        let end = match game.end_time {
+            Some(end) => Utc.timestamp(end, 0),
+            None => now,
+        };
+
+        Self {
+            player_1: game.player_1_username.clone().unwrap_or_else(|| "???".into()),
+            player_2: game.player_2_username.clone().unwrap_or_else(|| "???".into()),
+            rounds,
+            complete: game.complete,
+            duration: FormattedDuration::between(start, end),
+            locale: game.locale,
+        }
+    }
+
+    /// Shows the players by another name, like a room nickname.
+    pub fn with_names(mut self, player_1: &str, player_2: &str) -> Self {
+        self.player_1 = player_1.into();
+        self.player_2 = player_2.into();
+        self
+    }
+
+    /// How long the game took, like "3 minutes, 12 seconds", in the game's
+    /// language.
+    pub fn duration_text(&self) -> String {
+        let format = DurationFormat::new(DurationStyle::Long)
+            .with_max_units(2)
+            .with_locale(self.locale);
+        self.duration.format(&format)
+    }
+
+    /// The "Round" column header in the game's language.
+    pub fn round_header(&self) -> String {
+        messages::text(self.locale, "board.round", &[])
+    }
+
+    /// The duration line under the table, like "Duration: 3 minutes".
+    pub fn duration_line(&self) -> String {
+        messages::text(self.locale, "board.duration", &[("duration", &self.duration_text())])
+    }
+
+    /// Column widths for the round number and each player's guesses, in
+    /// characters.
+    pub fn column_widths(&self) -> (usize, usize, usize) {
+        let width = |header: &str, guesses: &mut dyn Iterator<Item = &BoardGuess>| {
+            guesses
+                .map(|g| g.text(self.locale).chars().count())
+                .chain(std::iter::once(header.chars().count()))
+                .max()
+                .unwrap_or(0)
+        };
+        (
+            self.round_header().chars().count().max(self.rounds.len().to_string().len()),
+            width(&self.player_1, &mut self.rounds.iter().map(|r| &r.player_1_guess)),
+            width(&self.player_2, &mut self.rounds.iter().map(|r| &r.player_2_guess)),
+        )
+    }
+
+    /// The header and each round as padded cells, ready to be laid out.
+    pub fn rows(&self) -> Vec<[String; 3]> {
+        let (w0, w1, w2) = self.column_widths();
+        let pad = |text: &str, width: usize| {
+            let len = text.chars().count();
+            format!("{}{}", text, " ".repeat(width.saturating_sub(len)))
+        };
+        std::iter::once([
+            pad(&self.round_header(), w0),
+            pad(&self.player_1, w1),
+            pad(&self.player_2, w2),
+        ])
+        .chain(self.rounds.iter().map(|r| {
+            [
+                pad(&r.number.to_string(), w0),
+                pad(&r.player_1_guess.text(self.locale), w1),
+                pad(&r.player_2_guess.text(self.locale), w2),
+            ]
+        }))
+        .collect()
+    }
+}
+
+impl Display for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, row) in self.rows().iter().enumerate() {
+            let line = row.join("  ");
+            write!(f, "{}", line.trim_end())?;
+            if i > 0 && self.rounds[i - 1].matched {
+                write!(f, "  {}", messages::text(self.locale, "board.match", &[]))?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "{}", self.duration_line())
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{
+    association::AssociationModel,
+    locale::{messages, Locale},
+};
+
+use super::Guess;
+
+/// A nudge towards a word that bridges the last round's two guesses.
+///
+/// Hints never name the bridging word itself: the first hint in a round
+/// points at a related category, and any further hints show its letter
+/// pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Hint {
+    Category(String),
+    Pattern(String),
+}
+
+impl Hint {
+    pub fn text(&self, locale: Locale) -> String {
+        match self {
+            Hint::Category(category) => messages::text(locale, "hint.category", &[("word", category)]),
+            Hint::Pattern(pattern) => messages::text(locale, "hint.pattern", &[("pattern", pattern)]),
+        }
+    }
+}
+
+/// A hint given to a player, with the 1-based round it was asked for in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HintRecord {
+    pub round: usize,
+    pub username: String,
+    pub hint: Hint,
+}
+
+/// Builds the next hint from the revealed `guesses`, or `None` if the model
+/// knows nothing that bridges the last round.
+pub fn suggest(model: &dyn AssociationModel, guesses: &[Guess], hints_this_round: usize) -> Option<Hint> {
+    let locale = model.locale();
+    let (a, b) = match guesses.last() {
+        Some((Some(a), Some(b))) => (locale.normalize(a), locale.normalize(b)),
+        _ => return None,
+    };
+    let used: Vec<String> = guesses
+        .iter()
+        .flat_map(|(g1, g2)| g1.iter().chain(g2.iter()))
+        .map(|g| locale.normalize(g))
+        .collect();
+    let unused = |word: &&str| !used.contains(&locale.normalize(word));
+
+    let bridge = model
+        .related_to_both(&a, &b)
+        .into_iter()
+        .map(|(w, _)| w)
+        .find(unused)
+        .or_else(|| {
+            let mut candidates: Vec<_> = model.related(&a).into_iter().chain(model.related(&b)).map(|(w, _)| w).collect();
+            candidates.sort_unstable();
+            candidates.dedup();
+            model.rank(&candidates, &[&a, &b]).into_iter().map(|(w, _)| w).find(unused)
+        })?;
+
+    if hints_this_round == 0 {
+        let folded = locale.normalize(bridge);
+        let category = model
+            .related(bridge)
+            .into_iter()
+            .map(|(w, _)| w)
+            .find(|w| unused(w) && !locale.normalize(w).contains(&folded) && !folded.contains(&locale.normalize(w)));
+        if let Some(category) = category {
+            return Some(Hint::Category(category.into()));
+        }
+    }
+    Some(Hint::Pattern(pattern(bridge)))
+}
+
+/// Shows the first letter and a blank for each of the rest: `b _ _ _ _`.
+fn pattern(word: &str) -> String {
+    word.chars()
+        .enumerate()
+        .map(|(i, c)| if i == 0 || !c.is_alphanumeric() { c.to_string() } else { "_".into() })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
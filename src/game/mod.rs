@@ -1,19 +1,97 @@
 use std::{
-    fmt::Display,
+    collections::HashMap,
     sync::{Arc, Mutex},
 };
 
-use crate::{database::DatabaseEnum, AppResult, ApplicationError, time::duration::FormattedDuration};
+use crate::{
+    association::AssociationModelEnum,
+    database::DatabaseEnum,
+    dictionary::{DictionaryEnum, InvalidGuess},
+    locale::{messages, Locale},
+    error::Entity,
+    id::{IdGeneratorEnum, IdGeneratorFactory, IdGeneratorType},
+    time::clock::{ClockEnum, ClockFactory, ClockType},
+    AppResult, ApplicationError,
+};
 use chrono::prelude::*;
-use uuid::Uuid;
+use rand::{distributions::Alphanumeric, Rng};
+
+use self::{
+    board::Board,
+    hint::{Hint, HintRecord},
+};
+
+pub mod board;
+pub mod hint;
+
+const SECRET_LENGTH: usize = 32;
 
 pub struct WordGame {
     database: Arc<Mutex<DatabaseEnum>>,
+    dictionaries: HashMap<Locale, DictionaryEnum>,
+    association_models: HashMap<Locale, AssociationModelEnum>,
+    clock: ClockEnum,
+    ids: IdGeneratorEnum,
 }
 
 impl WordGame {
     pub fn new(database: Arc<Mutex<DatabaseEnum>>) -> Self {
-        Self { database }
+        Self {
+            database,
+            dictionaries: HashMap::new(),
+            association_models: HashMap::new(),
+            clock: ClockFactory::create(ClockType::System),
+            ids: IdGeneratorFactory::create(IdGeneratorType::Uuid),
+        }
+    }
+
+    /// Takes game times from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: ClockEnum) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Names games and sessions with ids from `ids` instead of UUIDs.
+    pub fn with_id_generator(mut self, ids: IdGeneratorEnum) -> Self {
+        self.ids = ids;
+        self
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    pub fn next_id(&mut self) -> String {
+        self.ids.next_id()
+    }
+
+    /// The game's board, with the time so far for a game in progress.
+    pub fn board(&self, game: &Game) -> Board {
+        Board::new(game, self.now())
+    }
+
+    /// Only accepts guesses the dictionary knows in games played in its
+    /// locale.
+    pub fn with_dictionary(mut self, dictionary: DictionaryEnum) -> Self {
+        self.dictionaries.insert(dictionary.locale(), dictionary);
+        self
+    }
+
+    /// Gives hints from `model` in games played in its locale. Games in a
+    /// locale without a model get no hints.
+    pub fn with_association_model(mut self, model: AssociationModelEnum) -> Self {
+        self.association_models.insert(model.locale(), model);
+        self
+    }
+
+    /// Checks a guess before it is submitted, returning it trimmed. Without
+    /// a dictionary for the locale, anything but blank input is a valid guess.
+    pub fn validate_guess(&self, locale: Locale, guess: &str) -> AppResult<String> {
+        match self.dictionaries.get(&locale) {
+            Some(dictionary) => dictionary.validate(guess),
+            None if guess.trim().is_empty() => Err(InvalidGuess::Empty.into()),
+            None => Ok(guess.trim().into()),
+        }
     }
 
     pub fn create_player(&mut self, username: &str, display_name: &str) -> AppResult<Player> {
@@ -22,29 +100,130 @@ impl WordGame {
         Ok(player)
     }
 
+    /// Gives the player a new random secret, replacing any earlier one.
+    /// Runners where anyone can claim any username hand it out once and
+    /// ask for it back with `authenticate`.
+    pub fn issue_secret(&mut self, username: &str) -> AppResult<String> {
+        let mut player = self.get_player(username)?;
+        let secret: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(SECRET_LENGTH)
+            .map(char::from)
+            .collect();
+        player.secret = Some(secret.clone());
+        self.save_player(&player)?;
+        Ok(secret)
+    }
+
+    /// The player `secret` was issued to.
+    pub fn authenticate(&self, secret: &str) -> AppResult<Player> {
+        let db = self.database.clone();
+        let db = db.lock().unwrap();
+        db.get_players()?
+            .into_iter()
+            .find(|p| p.secret.as_deref() == Some(secret))
+            .ok_or(ApplicationError::Unauthorized)
+    }
+
+    /// Sets the languages a player is happy to play in, most preferred first.
+    pub fn set_player_locales(&mut self, username: &str, locales: &[Locale]) -> AppResult<Player> {
+        let mut player = self.get_player(username)?;
+        if !locales.is_empty() {
+            player.locales = locales.to_vec();
+        }
+        self.save_player(&player)?;
+        Ok(player)
+    }
+
+    /// Sets the language the runners talk to the player in.
+    pub fn set_player_language(&mut self, username: &str, language: Locale) -> AppResult<Player> {
+        let mut player = self.get_player(username)?;
+        player.language = language;
+        self.save_player(&player)?;
+        Ok(player)
+    }
+
+    /// The language to talk to someone in, even if they have no player yet.
+    pub fn language_for(&self, username: &str) -> Locale {
+        self.get_player(username).map(|p| p.language).unwrap_or_default()
+    }
+
     //Create Game
     pub fn create_game(&mut self) -> AppResult<Game> {
-        let game = Game::new();
+        self.create_game_in(Locale::default())
+    }
+
+    pub fn create_game_in(&mut self, locale: Locale) -> AppResult<Game> {
+        let game = Game::new(&self.next_id(), locale, self.now().timestamp());
         self.save_game(&game)?;
         Ok(game)
     }
 
-    //Join game
+    /// Starts a game in `locale` with the player in the first seat. The
+    /// player has to be free and speak the language, which is checked before
+    /// the game is created so a refusal leaves no empty game behind.
+    pub fn start_game(&mut self, username: &str, locale: Locale) -> AppResult<Game> {
+        let player = self.get_player(username)?;
+        self.check_free(&player, "")?;
+        if !player.speaks(locale) {
+            return Err(ApplicationError::LanguageMismatch(locale));
+        }
+        let game = self.create_game_in(locale)?;
+        self.join_game(username, &game.id)?;
+        self.get_game(&game.id)
+    }
+
+    /// Puts the player in a game waiting for a partner who shares one of
+    /// their languages, or starts a new one in their preferred language.
+    pub fn matchmake(&mut self, username: &str) -> AppResult<Game> {
+        let player = self.get_player(username)?;
+        self.check_free(&player, "")?;
+        let open_games: Vec<Game> = {
+            let db = self.database.clone();
+            let db = db.lock().unwrap();
+            db.get_games()?
+                .into_iter()
+                .filter(|g| !g.complete && !g.is_full())
+                .filter(|g| g.player_1_username.is_some() && g.player_1_username.as_deref() != Some(username))
+                .collect()
+        };
+
+        let found = player
+            .locales
+            .iter()
+            .find_map(|locale| open_games.iter().find(|g| g.locale == *locale));
+        let game_id = match found {
+            Some(game) => game.id.clone(),
+            None => return self.start_game(username, player.preferred_locale()),
+        };
+        self.join_game(username, &game_id)?;
+        self.get_game(&game_id)
+    }
+
+    /// Seats the player in the game. A player holds one seat at a time, so
+    /// they have to finish or leave an unfinished game before joining another.
     pub fn join_game(&mut self, username: &str, game_id: &str) -> AppResult<()> {
         let mut player = self.get_player(username)?;
         let mut game = self.get_game(game_id)?;
         let game_id = game.id.clone();
 
-        if game.player_1_username == None {
+        if game.complete {
+            return Err(ApplicationError::GameComplete);
+        }
+        if game.player_1_username.as_deref() == Some(username) || game.player_2_username.as_deref() == Some(username) {
+            return Err(ApplicationError::AlreadyJoined);
+        }
+        self.check_free(&player, &game_id)?;
+        if !player.speaks(game.locale) {
+            return Err(ApplicationError::LanguageMismatch(game.locale));
+        }
+
+        if game.player_1_username.is_none() {
             game.player_1_username = Some(player.username.clone());
-        } else if game.player_2_username == None {
+        } else if game.player_2_username.is_none() {
             game.player_2_username = Some(player.username.clone());
         } else {
-            return Err(ApplicationError::new(
-                "cannot join error",
-                "Game is full",
-                None,
-            ));
+            return Err(ApplicationError::GameFull);
         }
         self.save_game(&game)?;
 
@@ -54,6 +233,55 @@ impl WordGame {
         Ok(())
     }
 
+    /// Fails if the player is still seated in an unfinished game other than
+    /// `game_id`.
+    fn check_free(&self, player: &Player, game_id: &str) -> AppResult<()> {
+        match player.current_game_id.as_deref() {
+            Some(current) if current != game_id && !self.get_game(current).map(|g| g.complete).unwrap_or(true) => {
+                Err(ApplicationError::InAnotherGame { game_id: current.into() })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Gives up the player's seat in an unfinished game so someone else can
+    /// take it. Their guess for the round in progress goes with them.
+    pub fn leave_game(&mut self, username: &str, game_id: &str) -> AppResult<()> {
+        let mut player = self.get_player(username)?;
+        let mut game = self.get_game(game_id)?;
+
+        if game.complete {
+            return Err(ApplicationError::GameComplete);
+        }
+        let round = game.current_round;
+        if game.player_1_username.as_deref() == Some(username) {
+            game.player_1_username = None;
+            if let Some(guess) = game.guesses.get_mut(round) {
+                guess.0 = None;
+            }
+        } else if game.player_2_username.as_deref() == Some(username) {
+            game.player_2_username = None;
+            if let Some(guess) = game.guesses.get_mut(round) {
+                guess.1 = None;
+            }
+        } else {
+            return Err(ApplicationError::NotInGame);
+        }
+        self.save_game(&game)?;
+
+        if player.current_game_id.as_deref() == Some(game_id) {
+            player.current_game_id = None;
+            self.save_player(&player)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&self) -> AppResult<()> {
+        let db = self.database.clone();
+        let mut db = db.lock().unwrap();
+        db.flush()
+    }
+
     pub fn debug(&self) {
         let db = self.database.clone();
         let db = db.lock().unwrap();
@@ -61,70 +289,158 @@ impl WordGame {
         println!("Games: {:?}", db.get_games());
     }
 
-    // //Leave game
-    // pub fn leave_current_game(&mut self, username: &str) {
-    //     todo!("Not Implemented")
-    // }
-
     //Guess
-    pub fn submit_guess(&mut self, username: &str, guess: &str) -> AppResult<()> {
+    pub fn submit_guess(&mut self, username: &str, guess: &str) -> AppResult<GuessOutcome> {
         let player = self.get_player(username)?;
-        // let current_game = player.current_game_id.ok_or(ApplicationError::new(
-        //     "no current game",
-        //     "Player does not have a current game",
-        //     None,
-        // ))?;
-        // let mut game = self.get_game(&current_game)?;
-
-        // if game.guesses.len() <= game.current_round {
-        //     game.guesses.push((None, None));
-        // }
-
-        // if Some(username.to_string()) == game.player_1_username {
-        //     if game.guesses[game.current_round].0 == None {
-        //         game.guesses[game.current_round].0 = Some(guess.into());
-        //     } else {
-        //         return Err(ApplicationError::new(
-        //             "already guessed",
-        //             "Player already guessed for this round",
-        //             None,
-        //         ));
-        //     }
-        // } else if Some(username.to_string()) == game.player_2_username {
-        //     if game.guesses[game.current_round].1 == None {
-        //         game.guesses[game.current_round].1 = Some(guess.into());
-        //     } else {
-        //         return Err(ApplicationError::new(
-        //             "already guessed",
-        //             "Player already guessed for this round",
-        //             None,
-        //         ));
-        //     }
-        // }
-
-        // let p1_guess = game.guesses[game.current_round]
-        //     .0
-        //     .as_ref()
-        //     .map(|g| g.to_lowercase());
-        // let p2_guess = game.guesses[game.current_round]
-        //     .1
-        //     .as_ref()
-        //     .map(|g| g.to_lowercase());
-
-        // if p1_guess == p2_guess {
-        //     game.complete = true;
-        //     game.end_time = Some(Utc::now().timestamp());
-        // }
-
-        // if p1_guess != None && p2_guess != None {
-        //     game.current_round += 1;
-        // }
-
-        // self.save_game(&game)?;
+        let current_game = player.current_game_id.ok_or(ApplicationError::NoCurrentGame)?;
+        let mut game = self.get_game(&current_game)?;
+        let guess = self.validate_guess(game.locale, guess)?;
+
+        if game.complete {
+            return Err(ApplicationError::GameComplete);
+        }
+
+        if game.guesses.len() <= game.current_round {
+            game.guesses.push((None, None));
+        }
+
+        if Some(username.to_string()) == game.player_1_username {
+            if game.guesses[game.current_round].0.is_none() {
+                game.guesses[game.current_round].0 = Some(guess);
+            } else {
+                return Err(ApplicationError::AlreadyGuessed);
+            }
+        } else if Some(username.to_string()) == game.player_2_username {
+            if game.guesses[game.current_round].1.is_none() {
+                game.guesses[game.current_round].1 = Some(guess);
+            } else {
+                return Err(ApplicationError::AlreadyGuessed);
+            }
+        } else {
+            return Err(ApplicationError::NotInGame);
+        }
+
+        let round = game.current_round + 1;
+        let p1_guess = game.guesses[game.current_round]
+            .0
+            .as_ref()
+            .map(|g| game.locale.normalize(g));
+        let p2_guess = game.guesses[game.current_round]
+            .1
+            .as_ref()
+            .map(|g| game.locale.normalize(g));
+
+        let outcome = if p1_guess.is_none() || p2_guess.is_none() {
+            GuessOutcome::Waiting { round }
+        } else if p1_guess == p2_guess {
+            game.complete = true;
+            game.end_time = Some(self.now().timestamp());
+            game.current_round += 1;
+            GuessOutcome::Matched { round }
+        } else {
+            game.current_round += 1;
+            GuessOutcome::Mismatched { round }
+        };
+
+        self.save_game(&game)?;
+
+        Ok(outcome)
+    }
+
+    /// Replaces a guess made in `round` (1-based) as long as the partner
+    /// has not guessed yet, which would have sealed the round.
+    pub fn correct_guess(&mut self, username: &str, round: usize, guess: &str) -> AppResult<GuessCorrection> {
+        let player = self.get_player(username)?;
+        let current_game = player.current_game_id.ok_or(ApplicationError::NoCurrentGame)?;
+        let mut game = self.get_game(&current_game)?;
+
+        if game.complete || round != game.current_round + 1 {
+            return Ok(GuessCorrection::Locked);
+        }
+        let guess = self.validate_guess(game.locale, guess)?;
+
+        let is_player_1 = Some(username.to_string()) == game.player_1_username;
+        let is_player_2 = Some(username.to_string()) == game.player_2_username;
+        let slot = match game.guesses.get_mut(game.current_round) {
+            Some(g) if is_player_1 => Some(&mut g.0),
+            Some(g) if is_player_2 => Some(&mut g.1),
+            _ => None,
+        };
+        match slot {
+            Some(slot) if slot.is_some() => *slot = Some(guess),
+            _ => {
+                return Err(ApplicationError::NoGuess { round })
+            }
+        }
+
+        self.save_game(&game)?;
+
+        Ok(GuessCorrection::Replaced)
+    }
+
+    /// Remembers that the player's guess in `round` (1-based) of their
+    /// current game came in the chat message `message_id`, so a later
+    /// correction of that message can be matched to the guess.
+    pub fn record_guess_message(&mut self, username: &str, round: usize, message_id: &str) -> AppResult<()> {
+        let player = self.get_player(username)?;
+        let current_game = player.current_game_id.ok_or(ApplicationError::NoCurrentGame)?;
+        let mut game = self.get_game(&current_game)?;
+
+        match game
+            .guess_messages
+            .iter_mut()
+            .find(|m| m.round == round && m.username == username)
+        {
+            Some(record) => record.message_ids.push(message_id.into()),
+            None => game.guess_messages.push(GuessMessage {
+                round,
+                username: username.into(),
+                message_ids: vec![message_id.into()],
+            }),
+        }
+        self.save_game(&game)?;
 
         Ok(())
     }
 
+    /// Gives the player a hint for their current round and records it on
+    /// the game, so wins can be told apart from unassisted ones.
+    pub fn request_hint(&mut self, username: &str) -> AppResult<Hint> {
+        let player = self.get_player(username)?;
+        let current_game = player.current_game_id.ok_or(ApplicationError::NoCurrentGame)?;
+        let mut game = self.get_game(&current_game)?;
+
+        if game.complete {
+            return Err(ApplicationError::GameComplete);
+        }
+        if game.partner_of(username).is_none() {
+            return Err(ApplicationError::NotInGame);
+        }
+        let model = match self.association_models.get(&game.locale) {
+            Some(model) if game.current_round > 0 => model,
+            _ => return Err(ApplicationError::NoHint),
+        };
+
+        let round = game.current_round + 1;
+        let hints_this_round = game
+            .hints
+            .iter()
+            .filter(|h| h.round == round && h.username == username)
+            .count();
+        let hint = hint::suggest(&**model, &game.guesses[..game.current_round], hints_this_round).ok_or(
+            ApplicationError::NoHint,
+        )?;
+
+        game.hints.push(HintRecord {
+            round,
+            username: username.into(),
+            hint: hint.clone(),
+        });
+        self.save_game(&game)?;
+
+        Ok(hint)
+    }
+
     pub fn is_game_complete(&self, game_id: &str) -> AppResult<bool> {
         let game = self.get_game(game_id)?;
 
@@ -137,36 +453,33 @@ impl WordGame {
         Ok(game.guesses)
     }
 
-    fn get_player(&self, username: &str) -> AppResult<Player> {
+    pub fn get_player(&self, username: &str) -> AppResult<Player> {
         let db = self.database.clone();
         let db = db.lock().unwrap();
         let player = db
             .get_player_by_username(username)?
-            .ok_or(ApplicationError::new(
-                "player not found",
-                "Could not find player",
-                None,
-            ))?;
+            .ok_or(ApplicationError::not_found(Entity::Player, username))?;
         Ok(player)
     }
 
+    pub fn current_game_for(&self, username: &str) -> AppResult<Option<Game>> {
+        let player = self.get_player(username)?;
+        match player.current_game_id {
+            Some(game_id) => Ok(Some(self.get_game(&game_id)?)),
+            None => Ok(None),
+        }
+    }
+
     pub fn has_player(&self, username: &str) -> bool {
         let db = self.database.clone();
         let db = db.lock().unwrap();
-        match db.get_player_by_username(username) {
-            Ok(Some(_)) => true,
-            _ => false
-        }
+        matches!(db.get_player_by_username(username), Ok(Some(_)))
     }
 
     pub fn get_game(&self, game_id: &str) -> AppResult<Game> {
         let db = self.database.clone();
         let db = db.lock().unwrap();
-        let game = db.get_game(game_id)?.ok_or(ApplicationError::new(
-            "game not found",
-            "Could not find game",
-            None,
-        ))?;
+        let game = db.get_game(game_id)?.ok_or(ApplicationError::not_found(Entity::Game, game_id))?;
         Ok(game)
     }
 
@@ -193,10 +506,97 @@ impl WordGame {
     //     todo!("Not Implemented")
     // }
 
-    // //Statistics
-    // pub fn statistics(&self) {
-    //     todo!("Not Implemented")
-    // }
+    //Statistics
+    pub fn statistics(&self, username: &str) -> AppResult<PlayerStatistics> {
+        let db = self.database.clone();
+        let db = db.lock().unwrap();
+        let games = db.get_games()?;
+        let stats = games
+            .iter()
+            .filter(|g| {
+                g.player_1_username.as_deref() == Some(username)
+                    || g.player_2_username.as_deref() == Some(username)
+            })
+            .fold(PlayerStatistics::default(), |mut stats, g| {
+                stats.games_played += 1;
+                if g.complete {
+                    stats.games_won += 1;
+                    stats.winning_rounds += g.current_round;
+                    if g.is_assisted() {
+                        stats.assisted_wins += 1;
+                    }
+                }
+                stats
+            });
+        Ok(stats)
+    }
+}
+
+/// The result of a single guess, with the 1-based round it was made in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GuessOutcome {
+    Waiting { round: usize },
+    Mismatched { round: usize },
+    Matched { round: usize },
+}
+
+impl GuessOutcome {
+    pub fn round(&self) -> usize {
+        match self {
+            GuessOutcome::Waiting { round } | GuessOutcome::Mismatched { round } | GuessOutcome::Matched { round } => {
+                *round
+            }
+        }
+    }
+}
+
+/// The chat messages a player's guess in a round was sent and corrected
+/// with, for runners that support message corrections.
+#[derive(Debug, Clone)]
+pub struct GuessMessage {
+    pub round: usize,
+    pub username: String,
+    pub message_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GuessCorrection {
+    Replaced,
+    Locked,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlayerStatistics {
+    pub games_played: usize,
+    pub games_won: usize,
+    pub winning_rounds: usize,
+    /// Wins where either player asked for a hint.
+    pub assisted_wins: usize,
+}
+
+impl PlayerStatistics {
+    pub fn average_rounds_to_win(&self) -> Option<f64> {
+        if self.games_won == 0 {
+            None
+        } else {
+            Some(self.winning_rounds as f64 / self.games_won as f64)
+        }
+    }
+
+    /// The statistics as runners show them to the player, one per line.
+    pub fn text(&self, locale: Locale) -> String {
+        let label = |key| messages::text(locale, key, &[]);
+        let mut lines = vec![
+            label("stats.title"),
+            format!("{}: {}", label("stats.games_played"), self.games_played),
+            format!("{}: {}", label("stats.games_won"), self.games_won),
+            format!("{}: {}", label("stats.assisted_wins"), self.assisted_wins),
+        ];
+        if let Some(average) = self.average_rounds_to_win() {
+            lines.push(format!("{}: {:.1}", label("stats.average_rounds"), average));
+        }
+        lines.join("\n")
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -204,21 +604,45 @@ pub struct Player {
     pub username: String,
     pub display_name: Option<String>,
     pub status: PlayerStatus,
+    pub current_game_id: Option<String>,
+    /// Languages the player plays in, most preferred first.
+    pub locales: Vec<Locale>,
+    /// The language runners talk to the player in.
+    pub language: Locale,
+    /// Proves who the player is to runners without their own accounts.
+    pub secret: Option<String>,
 }
 
 impl Player {
-    pub fn new(username: &str) -> Self {
+    pub fn new(username: &str, display_name: &str) -> Self {
+        let display_name = if display_name.is_empty() {
+            None
+        } else {
+            Some(display_name.into())
+        };
         Self {
             username: username.into(),
-            display_name: None,
+            display_name,
             status: PlayerStatus::New,
+            current_game_id: None,
+            locales: vec![Locale::default()],
+            language: Locale::default(),
+            secret: None,
         }
     }
+
+    pub fn preferred_locale(&self) -> Locale {
+        self.locales.first().copied().unwrap_or_default()
+    }
+
+    pub fn speaks(&self, locale: Locale) -> bool {
+        self.locales.contains(&locale)
+    }
 }
 
 impl std::fmt::Display for Player {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.display_name {
+        match &self.display_name {
             Some(name) => write!(f, "{}", name),
             None => write!(f, "{}", self.username),
         }
@@ -245,6 +669,7 @@ pub type Guess = (Option<String>, Option<String>);
 #[derive(Debug, Clone)]
 pub struct Game {
     pub id: String,
+    locale: Locale,
     start_time: i64,
     end_time: Option<i64>,
     complete: bool,
@@ -252,56 +677,104 @@ pub struct Game {
     player_1_username: Option<String>,
     player_2_username: Option<String>,
     guesses: Vec<Guess>,
+    guess_messages: Vec<GuessMessage>,
+    hints: Vec<HintRecord>,
 }
 
 impl Game {
-    fn new() -> Self {
+    fn new(id: &str, locale: Locale, start_time: i64) -> Self {
         Self {
-            id: Uuid::new_v4().to_string(),
-            start_time: Utc::now().timestamp(),
+            id: id.into(),
+            locale,
+            start_time,
             end_time: None,
             complete: false,
             current_round: 0,
             player_1_username: None,
             player_2_username: None,
             guesses: vec![],
+            guess_messages: vec![],
+            hints: vec![],
         }
     }
-}
 
-impl Display for Game {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut guesses = String::new();
-        let p1 = self
-            .player_1_username
-            .as_ref()
-            .map_or("???", |s| s.as_str());
-        let p2 = self
-            .player_2_username
-            .as_ref()
-            .map_or("???", |s| s.as_str());
-        guesses.push_str(p1);
-        guesses.push('\t');
-        guesses.push_str(p2);
-        guesses.push('\n');
-        self.guesses.iter().for_each(|g| {
-            if let Some(g1) = &g.0 {
-                guesses.push_str(g1);
-            }
-            if let Some(g2) = &g.1 {
-                guesses.push('\t');
-                guesses.push_str(g2);
-            }
-            guesses.push('\n');
-        });
-        let start = Utc.timestamp(self.start_time, 0);
-        let end = match self.end_time {
-            Some(end) => Utc.timestamp(end, 0),
-            None => Utc::now(),
-        };
-        let duration = end - start;
-        let duration = FormattedDuration::from(duration);
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// When the game was created, in seconds since the Unix epoch.
+    pub fn start_time(&self) -> i64 {
+        self.start_time
+    }
+
+    /// When the game was won, in seconds since the Unix epoch.
+    pub fn end_time(&self) -> Option<i64> {
+        self.end_time
+    }
+
+    pub fn current_round(&self) -> usize {
+        self.current_round
+    }
+
+    pub fn player_1_username(&self) -> Option<&str> {
+        self.player_1_username.as_deref()
+    }
+
+    pub fn player_2_username(&self) -> Option<&str> {
+        self.player_2_username.as_deref()
+    }
+
+    pub fn guesses(&self) -> &[Guess] {
+        &self.guesses
+    }
+
+    /// The round (1-based) of the player's guess sent or corrected with
+    /// the chat message `message_id`.
+    pub fn guess_round_for(&self, username: &str, message_id: &str) -> Option<usize> {
+        self.guess_messages
+            .iter()
+            .find(|m| m.username == username && m.message_ids.iter().any(|id| id == message_id))
+            .map(|m| m.round)
+    }
 
-        write!(f, "Guesses:\n{}\nDuration: {}", guesses.trim(), duration)
+    pub fn hints(&self) -> &[HintRecord] {
+        &self.hints
+    }
+
+    /// Whether either player has asked for a hint.
+    pub fn is_assisted(&self) -> bool {
+        !self.hints.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.player_1_username.is_some() && self.player_2_username.is_some()
+    }
+
+    pub fn partner_of(&self, username: &str) -> Option<&str> {
+        if self.player_1_username.as_deref() == Some(username) {
+            self.player_2_username.as_deref()
+        } else if self.player_2_username.as_deref() == Some(username) {
+            self.player_1_username.as_deref()
+        } else {
+            None
+        }
+    }
+
+    pub fn has_guessed_this_round(&self, username: &str) -> bool {
+        let guess = match self.guesses.get(self.current_round) {
+            Some(guess) => guess,
+            None => return false,
+        };
+        if self.player_1_username.as_deref() == Some(username) {
+            guess.0.is_some()
+        } else if self.player_2_username.as_deref() == Some(username) {
+            guess.1.is_some()
+        } else {
+            false
+        }
     }
 }
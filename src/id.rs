@@ -0,0 +1,58 @@
+use uuid::Uuid;
+
+use crate::trait_enum;
+
+pub enum IdGeneratorType {
+    Uuid,
+    /// Numbered ids like `game-1`, `game-2`, ...
+    Sequential(String),
+}
+
+/// Hands out ids for games and runner sessions.
+pub trait IdGenerator {
+    fn next_id(&mut self) -> String;
+}
+
+pub struct UuidGenerator;
+
+impl IdGenerator for UuidGenerator {
+    fn next_id(&mut self) -> String {
+        Uuid::new_v4().to_string()
+    }
+}
+
+/// Predictable ids for tests and replays.
+pub struct SequentialIdGenerator {
+    prefix: String,
+    next: u64,
+}
+
+impl SequentialIdGenerator {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.into(),
+            next: 1,
+        }
+    }
+}
+
+impl IdGenerator for SequentialIdGenerator {
+    fn next_id(&mut self) -> String {
+        let id = format!("{}-{}", self.prefix, self.next);
+        self.next += 1;
+        id
+    }
+}
+
+trait_enum!(IdGenerator, IdGeneratorEnum, UuidGenerator, SequentialIdGenerator);
+
+pub struct IdGeneratorFactory;
+
+impl IdGeneratorFactory {
+    pub fn create(id_generator_type: IdGeneratorType) -> IdGeneratorEnum {
+        match id_generator_type {
+            IdGeneratorType::Uuid => IdGeneratorEnum::UuidGenerator(UuidGenerator),
+            IdGeneratorType::Sequential(prefix) => IdGeneratorEnum::SequentialIdGenerator(SequentialIdGenerator::new(&prefix)),
+        }
+    }
+}
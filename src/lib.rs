@@ -2,26 +2,15 @@ pub mod runner;
 pub mod game;
 pub mod database;
 pub mod time;
+pub mod bot;
+pub mod association;
+pub mod dictionary;
+pub mod locale;
+pub mod error;
+pub mod id;
+pub mod json;
 
-#[derive(Debug)]
-#[allow(unused)]
-pub struct ApplicationError {
-    kind: String,
-    message: String,
-    cause: Option<Box<ApplicationError>>
-}
-
-impl ApplicationError {
-    pub fn new(kind: &str, message: &str, cause: Option<Box<ApplicationError>>) -> Self {
-        Self {
-            kind: kind.into(),
-            message: message.into(),
-            cause
-        }
-    }
-}
-
-pub type AppResult<T> = Result<T, ApplicationError>;
+pub use error::{AppResult, ApplicationError};
 
 #[macro_export]
 macro_rules! trait_enum {
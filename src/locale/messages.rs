@@ -0,0 +1,78 @@
+use std::{collections::HashMap, fmt::Display, sync::OnceLock};
+
+use super::Locale;
+
+const ENGLISH: &str = include_str!("../../data/messages/en.txt");
+const GERMAN: &str = include_str!("../../data/messages/de.txt");
+const TURKISH: &str = include_str!("../../data/messages/tr.txt");
+
+/// The user-facing text for one locale, keyed by message id.
+///
+/// Messages may contain `{name}` placeholders, filled in by `text`. A key
+/// missing from a locale falls back to English, and then to the key itself,
+/// so a gap in a translation never hides a message.
+pub struct Catalog {
+    locale: Locale,
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    /// Parses `key = text` lines, skipping blank lines and `#` comments.
+    pub fn parse(locale: Locale, source: &str) -> Self {
+        let messages = source
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(|l| l.split_once('='))
+            .map(|(key, text)| (key.trim().to_string(), text.trim().replace("\\n", "\n")))
+            .collect();
+        Self { locale, messages }
+    }
+
+    /// The bundled catalog for a locale, parsed on first use.
+    pub fn bundled(locale: Locale) -> &'static Self {
+        static CATALOGS: [OnceLock<Catalog>; 3] = [OnceLock::new(), OnceLock::new(), OnceLock::new()];
+        let (index, source) = match locale {
+            Locale::English => (0, ENGLISH),
+            Locale::German => (1, GERMAN),
+            Locale::Turkish => (2, TURKISH),
+        };
+        CATALOGS[index].get_or_init(|| Self::parse(locale, source))
+    }
+
+    pub fn locale(&self) -> Locale {
+        self.locale
+    }
+
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<_> = self.messages.keys().map(|k| k.as_str()).collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.messages.get(key).map(|m| m.as_str())
+    }
+
+    pub fn text(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let template = self
+            .get(key)
+            .or_else(|| Catalog::bundled(Locale::English).get(key))
+            .unwrap_or(key);
+        args.iter().fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), &value.to_string())
+        })
+    }
+}
+
+/// Looks up a message in the bundled catalog for `locale`.
+pub fn text(locale: Locale, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    Catalog::bundled(locale).text(key, args)
+}
+
+/// Whether `answer` is one of the space-separated words listed under `key`,
+/// like the ways of saying yes.
+pub fn is_answer(locale: Locale, key: &str, answer: &str) -> bool {
+    let answer = locale.normalize(answer);
+    text(locale, key, &[]).split_whitespace().any(|a| locale.normalize(a) == answer)
+}
@@ -0,0 +1,86 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::ApplicationError;
+
+pub mod messages;
+
+/// The language a game is played in.
+///
+/// A game's locale picks its dictionary and decides when two guesses are
+/// the same word, since case folding differs between languages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    German,
+    Turkish,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 3] = [Locale::English, Locale::German, Locale::Turkish];
+
+    /// The ISO 639-1 language code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::German => "de",
+            Locale::Turkish => "tr",
+        }
+    }
+
+    /// The language's name in English, for players who type it that way.
+    pub fn english_name(&self) -> &'static str {
+        match self {
+            Locale::English => "English",
+            Locale::German => "German",
+            Locale::Turkish => "Turkish",
+        }
+    }
+
+    /// Folds a word so that spellings players consider equal compare equal.
+    ///
+    /// German treats `ß` and `ss` alike, and Turkish keeps dotted and dotless
+    /// i apart (`I` lowercases to `ı`, `İ` to `i`).
+    pub fn normalize(&self, word: &str) -> String {
+        let word = word.trim();
+        match self {
+            Locale::English => word.to_lowercase(),
+            Locale::German => word.to_lowercase().replace('ß', "ss"),
+            Locale::Turkish => word
+                .chars()
+                .map(|c| match c {
+                    'I' => 'ı',
+                    'İ' => 'i',
+                    c => c,
+                })
+                .collect::<String>()
+                .to_lowercase(),
+        }
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Locale::English => write!(f, "English"),
+            Locale::German => write!(f, "Deutsch"),
+            Locale::Turkish => write!(f, "Türkçe"),
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = ApplicationError;
+
+    /// Parses a language code like `de` or `de-AT`, or a language name in
+    /// the language itself or in English.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let language = s.split(['-', '_']).next().unwrap_or_default();
+        Locale::ALL
+            .iter()
+            .find(|l| l.code() == language || l.to_string().to_lowercase() == s || l.english_name().to_lowercase() == s)
+            .copied()
+            .ok_or_else(|| ApplicationError::UnknownLanguage(s))
+    }
+}
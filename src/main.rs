@@ -1,17 +1,35 @@
 use std::sync::{Arc, Mutex};
 
+use structopt::StructOpt;
 use word_game::{
+    association::{AssociationModelFactory, AssociationModelType},
     database::{DatabaseFactory, DatabaseType},
+    dictionary::{DictionaryConfig, DictionaryFactory, DictionaryType},
+    locale::Locale,
     game::WordGame,
     runner::{RunnerFactory, RunnerType},
 };
 
+#[derive(StructOpt)]
+#[structopt(name = "word-game", about = "Find the same word as your partner")]
+struct Options {
+    /// Where to play: console, tui, xmpp, http, websocket, telnet, irc or matrix
+    #[structopt(short, long, env = "WORD_GAME_RUNNER", default_value = "xmpp")]
+    runner: RunnerType,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Options::from_args();
     let database = DatabaseFactory::create(DatabaseType::InMemory);
     let database = Arc::new(Mutex::new(database));
-    let game = WordGame::new(database);
-    let mut runner = RunnerFactory::create(RunnerType::XMPP);
+    let mut game = WordGame::new(database);
+    for locale in Locale::ALL.iter() {
+        let dictionary = DictionaryFactory::create(DictionaryType::Bundled(*locale), DictionaryConfig::default())?;
+        game = game.with_dictionary(dictionary);
+        game = game.with_association_model(AssociationModelFactory::create(AssociationModelType::Bundled(*locale))?);
+    }
+    let mut runner = RunnerFactory::create(options.runner)?;
     let game = Arc::new(Mutex::new(game));
 
     match runner.run(game).await {
@@ -19,7 +37,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("Hope you had fun!");
         }
         Err(e) => {
-            println!("Oh no! There was an error: {:?}", e);
+            println!("Oh no! There was an error: {}", e);
         }
     };
 
@@ -0,0 +1,34 @@
+use std::time::Duration;
+
+const INITIAL_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Exponential backoff between reconnect attempts, capped at one minute.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    next: Duration,
+}
+
+impl Backoff {
+    pub fn new() -> Self {
+        Self {
+            next: INITIAL_DELAY,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.next = INITIAL_DELAY;
+    }
+
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = std::cmp::min(self.next * 2, MAX_DELAY);
+        delay
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,255 @@
+//! Command handling shared by the chat runners.
+//!
+//! XMPP, IRC and Matrix take the same commands as text messages. The game
+//! side of each command lives here, and answers come back as text in the
+//! right language or as what happened, for the runner to word and deliver
+//! in its own way.
+
+use std::fmt::Display;
+
+use crate::{
+    game::{board::Board, Game, GuessOutcome, Player, WordGame},
+    locale::{messages, Locale},
+    AppResult,
+};
+
+/// How a guess sent as a chat message went.
+pub enum Guessed {
+    /// The guess didn't count, for the reason in the text.
+    Refused(String),
+    /// Nobody has joined the player in `game` yet.
+    NeedPartner(Game),
+    /// The guess counted and the partner's is still to come.
+    Waiting(String),
+    /// Both guesses for `round` are in, and `game` shows them. `reply`
+    /// tells the player their guess counted.
+    Revealed { reply: String, round: usize, game: Game },
+}
+
+/// The language code after a command, like `/start de`. Unknown codes come
+/// back as the list of supported ones.
+pub fn requested_locale(body: &str) -> Result<Option<Locale>, String> {
+    match body.split_whitespace().nth(1) {
+        Some(code) => code.parse().map(Some).map_err(|_| {
+            let codes: Vec<_> = Locale::ALL.iter().map(|l| l.code()).collect();
+            codes.join(", ")
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Makes `locale` the player's preferred language, keeping the others.
+pub fn add_locale(word_game: &mut WordGame, username: &str, locale: Locale) -> AppResult<Player> {
+    let player = word_game.get_player(username)?;
+    let mut locales = vec![locale];
+    locales.extend(player.locales.into_iter().filter(|l| *l != locale));
+    word_game.set_player_locales(username, &locales)
+}
+
+/// Creates a profile for a newcomer, shown as `display_name`.
+pub fn ensure_player(word_game: &mut WordGame, username: &str, display_name: &str) -> AppResult<()> {
+    if !word_game.has_player(username) {
+        word_game.create_player(username, display_name)?;
+    }
+    Ok(())
+}
+
+/// Creates the player if needed and applies a language given after the
+/// command. An unknown language comes back as the list of supported ones.
+pub fn prepare_player(
+    word_game: &mut WordGame,
+    username: &str,
+    display_name: &str,
+    body: &str,
+) -> AppResult<Result<Player, String>> {
+    ensure_player(word_game, username, display_name)?;
+    match requested_locale(body) {
+        Ok(Some(locale)) => add_locale(word_game, username, locale).map(Ok),
+        Ok(None) => word_game.get_player(username).map(Ok),
+        Err(codes) => Ok(Err(codes)),
+    }
+}
+
+/// Handles `/status`.
+pub fn status(word_game: &WordGame, username: &str, name: &str) -> String {
+    let language = word_game.language_for(username);
+    if word_game.has_player(username) {
+        messages::text(language, "xmpp.hello", &[("name", &name)])
+    } else {
+        messages::text(language, "xmpp.no_player", &[])
+    }
+}
+
+/// Handles `/language <code>`, creating a profile for newcomers so the
+/// preference sticks.
+pub fn change_language(word_game: &mut WordGame, username: &str, display_name: &str, body: &str) -> String {
+    let current = word_game.language_for(username);
+    let language = match body.split_whitespace().nth(1).map(str::parse::<Locale>) {
+        Some(Ok(language)) => language,
+        _ => {
+            let codes: Vec<_> = Locale::ALL.iter().map(|l| l.code()).collect();
+            return messages::text(current, "language.unknown", &[("codes", &codes.join(", "))]);
+        }
+    };
+
+    match ensure_player(word_game, username, display_name).and_then(|_| word_game.set_player_language(username, language)) {
+        Ok(_) => messages::text(language, "language.set", &[("language", &language)]),
+        Err(e) => {
+            println!("Could not change the language for {}: {:?}", username, e);
+            messages::text(current, "error.generic", &[])
+        }
+    }
+}
+
+/// Handles `/start [language]`, refusing an unknown language in the
+/// player's language.
+pub fn start(word_game: &mut WordGame, username: &str, display_name: &str, body: &str) -> AppResult<Result<Game, String>> {
+    let player = match prepare_player(word_game, username, display_name, body)? {
+        Ok(player) => player,
+        Err(codes) => {
+            let refusal = messages::text(word_game.language_for(username), "language.unknown", &[("codes", &codes)]);
+            return Ok(Err(refusal));
+        }
+    };
+    word_game.start_game(username, player.preferred_locale()).map(Ok)
+}
+
+/// Handles `/join <code>`, giving back the game as it was before the
+/// player joined, or why they can't.
+pub fn join_by_code(
+    word_game: &mut WordGame,
+    username: &str,
+    display_name: &str,
+    game_id: Option<&str>,
+) -> AppResult<Result<Game, String>> {
+    let language = word_game.language_for(username);
+    let text = |key: &str| messages::text(language, key, &[]);
+    let game_id = match game_id {
+        Some(game_id) => game_id,
+        None => return Ok(Err(text("adhoc.need_code"))),
+    };
+    ensure_player(word_game, username, display_name)?;
+    let game = match word_game.get_game(game_id) {
+        Ok(game) => game,
+        Err(_) => return Ok(Err(text("adhoc.no_such_game"))),
+    };
+    if game.is_full() {
+        return Ok(Err(text("adhoc.full")));
+    }
+    if !word_game.get_player(username)?.speaks(game.locale()) {
+        return Ok(Err(messages::text(language, "adhoc.wrong_language", &[("language", &game.locale())])));
+    }
+    word_game.join_game(username, game_id)?;
+    Ok(Ok(game))
+}
+
+/// Handles `/join [language]` for `game`, the one hosted where the command
+/// was sent. Refusals are in the game's language, for everyone there, and
+/// call the player `name`.
+pub fn join_hosted(
+    word_game: &mut WordGame,
+    username: &str,
+    name: &str,
+    body: &str,
+    game: &Game,
+) -> AppResult<Result<(), String>> {
+    let text = |key: &str, args: &[(&str, &dyn Display)]| messages::text(game.locale(), key, args);
+    if game.player_1_username() == Some(username) || game.player_2_username() == Some(username) {
+        return Ok(Err(text("room.already_joined", &[("name", &name)])));
+    }
+    if game.is_full() {
+        return Ok(Err(text("room.full", &[])));
+    }
+    let player = match prepare_player(word_game, username, name, body)? {
+        Ok(player) => player,
+        Err(codes) => return Ok(Err(text("language.unknown", &[("codes", &codes)]))),
+    };
+    if !player.speaks(game.locale()) {
+        let refusal = text(
+            "room.wrong_language",
+            &[("name", &name), ("language", &game.locale()), ("code", &game.locale().code())],
+        );
+        return Ok(Err(refusal));
+    }
+    word_game.join_game(username, &game.id)?;
+    Ok(Ok(()))
+}
+
+/// Takes `body` as the player's guess in `game`, remembering `message_id`
+/// as the message it came in so it can be corrected later.
+pub fn guess(
+    word_game: &mut WordGame,
+    username: &str,
+    game: &Game,
+    body: &str,
+    message_id: Option<&str>,
+) -> AppResult<Guessed> {
+    let language = word_game.language_for(username);
+    if !game.is_full() {
+        return Ok(Guessed::NeedPartner(game.clone()));
+    }
+    let is_player_1 = game.player_1_username() == Some(username);
+    let already_guessed = game
+        .guesses()
+        .get(game.current_round())
+        .is_some_and(|g| if is_player_1 { g.0.is_some() } else { g.1.is_some() });
+    if already_guessed {
+        return Ok(Guessed::Refused(messages::text(language, "guess.already", &[])));
+    }
+    if let Err(e) = word_game.validate_guess(game.locale(), body) {
+        return Ok(Guessed::Refused(e.user_message(language)));
+    }
+
+    let outcome = word_game.submit_guess(username, body)?;
+    if let Some(id) = message_id {
+        word_game.record_guess_message(username, outcome.round(), id)?;
+    }
+    match outcome {
+        GuessOutcome::Waiting { round } => Ok(Guessed::Waiting(messages::text(language, "guess.waiting", &[("round", &round)]))),
+        GuessOutcome::Mismatched { round } | GuessOutcome::Matched { round } => Ok(Guessed::Revealed {
+            reply: messages::text(language, "guess.received", &[("round", &round)]),
+            round,
+            game: word_game.get_game(&game.id)?,
+        }),
+    }
+}
+
+/// What to announce once both guesses of `round` are in: the guesses, and
+/// then the win, to show with `board`, or the mismatch.
+pub fn reveal(game: &Game, round: usize, board: &Board) -> (String, String) {
+    let locale = game.locale();
+    let (guess_1, guess_2) = &game.guesses()[round - 1];
+    let reveal = messages::text(
+        locale,
+        "room.reveal",
+        &[
+            ("round", &round),
+            ("player_1", &board.player_1),
+            ("guess_1", &guess_1.as_deref().unwrap_or("")),
+            ("player_2", &board.player_2),
+            ("guess_2", &guess_2.as_deref().unwrap_or("")),
+        ],
+    );
+    let outcome = if game.is_complete() {
+        messages::text(locale, "game.won", &[("player_1", &board.player_1), ("player_2", &board.player_2)])
+    } else {
+        messages::text(locale, "room.mismatch", &[])
+    };
+    (reveal, outcome)
+}
+
+/// Handles `/hint`.
+pub fn hint(word_game: &mut WordGame, username: &str) -> AppResult<String> {
+    let language = word_game.language_for(username);
+    let hint = word_game.request_hint(username)?;
+    Ok(messages::text(language, "hint.text", &[("hint", &hint.text(language))]))
+}
+
+/// Lists `commands`, written with `prefix`, under the heading `title`.
+pub fn command_list(language: Locale, title: &str, prefix: char, commands: &[(&str, &str)]) -> String {
+    let mut list = messages::text(language, title, &[]);
+    for (command, key) in commands {
+        list.push_str(&format!("\n{}{} - {}", prefix, command, messages::text(language, key, &[])));
+    }
+    list
+}
@@ -2,7 +2,12 @@ use std::{io::{stdout, Write, stdin}, sync::{Arc, Mutex}};
 use rpassword::prompt_password_stdout;
 use async_trait::async_trait;
 
-use crate::{game::WordGame, AppResult, ApplicationError};
+use crate::{
+    bot::{BotPlayer, Difficulty},
+    game::{Player, WordGame},
+    locale::{messages, Locale},
+    AppResult,
+};
 
 use super::Runner;
 
@@ -24,42 +29,60 @@ impl Default for ConsoleRunner {
 
 const PLAYER_1_USERNAME: &str = "player1";
 const PLAYER_2_USERNAME: &str = "player2";
+const HINT_COMMAND: &str = "/hint";
 
 #[async_trait(?Send)]
 impl Runner for ConsoleRunner {
     async fn run(&mut self, word_game: Arc<Mutex<WordGame>>) -> AppResult<()> {
-        println!("Welcome to the word game!");
-        let player_1_name = prompt("Enter player 1's name:");
-        let word_game = word_game;
+        println!("{}", messages::text(Locale::default(), "welcome", &[]));
+        let locale = prompt_locale();
+        let text = |key: &str, args: &[(&str, &dyn std::fmt::Display)]| messages::text(locale, key, args);
+
+        let player_1_name = prompt(&text("console.player_name", &[("number", &1)]));
         let mut word_game = word_game.lock().unwrap();
-        let p1 = word_game.create_player(PLAYER_1_USERNAME, player_1_name.as_str())?;
-        let player_2_name = prompt("Enter player 2's name:");
-        let p2 = word_game.create_player(PLAYER_2_USERNAME, player_2_name.as_str())?;
-        
-        let game = word_game.create_game()?;
+        word_game.create_player(PLAYER_1_USERNAME, player_1_name.as_str())?;
+        word_game.set_player_language(PLAYER_1_USERNAME, locale)?;
+        let p1 = word_game.set_player_locales(PLAYER_1_USERNAME, &[locale])?;
+        let mut bot = prompt_opponent(locale)?;
+        let player_2_name = match &bot {
+            Some(_) => text("console.computer_name", &[]),
+            None => prompt(&text("console.player_name", &[("number", &2)])),
+        };
+        word_game.create_player(PLAYER_2_USERNAME, player_2_name.as_str())?;
+        word_game.set_player_language(PLAYER_2_USERNAME, locale)?;
+        let p2 = word_game.set_player_locales(PLAYER_2_USERNAME, &[locale])?;
+
+        let game = word_game.create_game_in(locale)?;
         let game_id = game.id;
         word_game.join_game(PLAYER_1_USERNAME, &game_id)?;
         word_game.join_game(PLAYER_2_USERNAME, &game_id)?;
 
+        println!("{}", text("hint.how_to", &[("command", &HINT_COMMAND)]));
+
         while !word_game.is_game_complete(&game_id)? {
-            let p1_prompt = format!("{}, enter your guess:", p1);
-            let p1_guess = prompt_no_show(&p1_prompt)?;
+            let p1_guess = prompt_guess(&mut word_game, &p1, locale)?;
             word_game.submit_guess(&p1.username, &p1_guess)?;
             
-            let p2_prompt = format!("{}, enter your guess:", p2);
-            let p2_guess = prompt_no_show(&p2_prompt)?;
+            let p2_guess = match bot.as_mut() {
+                Some(bot) => {
+                    let game = word_game.get_game(&game_id)?;
+                    println!("{}", text("console.computer_guessed", &[("name", &p2)]));
+                    bot.guess(&game)
+                }
+                None => prompt_guess(&mut word_game, &p2, locale)?,
+            };
             word_game.submit_guess(&p2.username, &p2_guess)?;
 
             if !word_game.is_game_complete(&game_id)? {
-                println!("Aww, shucks... Those didn't match.");
+                println!("{}", text("round.mismatch", &[]));
                 let game = word_game.get_game(&game_id)?;
-                println!("{}", &game);
+                println!("{}", word_game.board(&game));
             }
         }
 
-        println!("{} and {}, you won!!! Congrats!", p1, p2);
+        println!("{}", text("game.won", &[("player_1", &p1), ("player_2", &p2)]));
         let game = word_game.get_game(&game_id)?;
-        println!("{}", &game);
+        println!("{}", word_game.board(&game));
 
         Ok(())
     }
@@ -74,6 +97,60 @@ impl Runner for ConsoleRunner {
 //     Ok(())
 // }
 
+/// Reads a hidden guess, handing out hints and asking again until the
+/// player types a valid word.
+fn prompt_guess(word_game: &mut WordGame, player: &Player, locale: Locale) -> AppResult<String> {
+    let guess_prompt = messages::text(locale, "console.guess_prompt", &[("name", player)]);
+    loop {
+        let guess = prompt_no_show(&guess_prompt)?;
+        if guess.trim() != HINT_COMMAND {
+            match word_game.validate_guess(locale, &guess) {
+                Ok(guess) => return Ok(guess),
+                Err(e) => {
+                    println!("{}", messages::text(locale, "guess.try_again", &[("reason", &e.user_message(locale))]));
+                    continue;
+                }
+            }
+        }
+        match word_game.request_hint(&player.username) {
+            Ok(hint) => println!("{}", messages::text(locale, "hint.text", &[("hint", &hint.text(locale))])),
+            Err(e) => println!("{}", e.user_message(locale)),
+        }
+    }
+}
+
+fn prompt_locale() -> Locale {
+    let codes: Vec<_> = Locale::ALL.iter().map(|l| l.code()).collect();
+    let default = Locale::default();
+    let answer = prompt(&messages::text(
+        default,
+        "console.choose_language",
+        &[("codes", &codes.join("/")), ("default", &default.code())],
+    ));
+    if answer.is_empty() {
+        return Locale::default();
+    }
+    answer.parse().unwrap_or_else(|_| {
+        println!("{}", messages::text(default, "console.unknown_language", &[("language", &default)]));
+        Locale::default()
+    })
+}
+
+/// Asks whether player 2 is the computer, and if so how hard it plays.
+fn prompt_opponent(locale: Locale) -> AppResult<Option<BotPlayer>> {
+    let answer = prompt(&messages::text(locale, "console.play_computer", &[]));
+    if !messages::is_answer(locale, "console.yes_answers", &answer) {
+        return Ok(None);
+    }
+    let answer = prompt(&messages::text(locale, "console.difficulty", &[]));
+    let difficulty = if messages::is_answer(locale, "console.easy_answers", &answer) {
+        Difficulty::Easy
+    } else {
+        Difficulty::Hard
+    };
+    BotPlayer::new(PLAYER_2_USERNAME, difficulty, locale).map(Some)
+}
+
 fn prompt(prompt: &str) -> String {
     print!("{} ", prompt);
     stdout()
@@ -87,8 +164,7 @@ fn prompt(prompt: &str) -> String {
 }
 
 fn prompt_no_show(prompt: &str) -> AppResult<String> {
-    let input = prompt_password_stdout(&format!("{} ", prompt))
-        .map_err(|e| ApplicationError::new(&format!("{:?}", e.kind()), "Could not read guess", None))?;
+    let input = prompt_password_stdout(&format!("{} ", prompt))?;
 
     Ok(input)
 }
\ No newline at end of file
@@ -0,0 +1,318 @@
+use chrono::prelude::*;
+
+use crate::{
+    error::Entity,
+    game::{board::BoardGuess, Game, GuessOutcome, Player, PlayerStatistics, WordGame},
+    json::Json,
+    locale::Locale,
+    time::format::{DurationFormat, DurationStyle},
+    ApplicationError,
+};
+
+use super::{
+    request::Request,
+    response::{Response, Status},
+};
+
+/// Answers one request against the game.
+///
+/// Errors from the game are explained in the first language of the
+/// request's `Accept-Language` header that the game knows.
+pub fn handle(request: &Request, word_game: &mut WordGame) -> Response {
+    let locale = request
+        .header("Accept-Language")
+        .and_then(|languages| {
+            languages
+                .split(',')
+                .find_map(|language| language.split(';').next()?.parse::<Locale>().ok())
+        })
+        .unwrap_or_default();
+
+    match route(request, word_game) {
+        Ok(response) => response,
+        Err(Refusal::Game(e)) => Response::from_error(&e, locale),
+        Err(Refusal::Http(response)) => response,
+    }
+}
+
+/// Why a request failed: the game said no, or the request never made sense.
+enum Refusal {
+    Game(ApplicationError),
+    Http(Response),
+}
+
+impl From<ApplicationError> for Refusal {
+    fn from(e: ApplicationError) -> Self {
+        Refusal::Game(e)
+    }
+}
+
+fn bad_request(message: &str) -> Refusal {
+    Refusal::Http(Response::refuse(Status::BadRequest, message))
+}
+
+type ApiResult = Result<Response, Refusal>;
+
+fn route(request: &Request, word_game: &mut WordGame) -> ApiResult {
+    let segments = request.segments();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let method = request.method.as_str();
+
+    match (method, segments.as_slice()) {
+        ("POST", ["players"]) => create_player(request, word_game),
+        ("GET", ["players", username]) => Ok(Response::ok(player_json(&word_game.get_player(username)?))),
+        ("GET", ["players", username, "statistics"]) => {
+            word_game.get_player(username)?;
+            Ok(Response::ok(statistics_json(&word_game.statistics(username)?)))
+        }
+        ("POST", ["games"]) => create_game(request, word_game),
+        ("GET", ["games", game_id]) => {
+            let viewer = authenticate(request, word_game)?;
+            let game = word_game.get_game(game_id)?;
+            let viewer = viewer.as_ref().map(|p| p.username.as_str());
+            Ok(Response::ok(game_json(word_game, &game, viewer)))
+        }
+        ("POST", ["games", game_id, "join"]) => {
+            let player = require_player(request, word_game)?;
+            word_game.join_game(&player.username, game_id)?;
+            let game = word_game.get_game(game_id)?;
+            Ok(Response::ok(game_json(word_game, &game, Some(&player.username))))
+        }
+        ("POST", ["games", game_id, "leave"]) => {
+            let player = require_player(request, word_game)?;
+            word_game.leave_game(&player.username, game_id)?;
+            let game = word_game.get_game(game_id)?;
+            Ok(Response::ok(game_json(word_game, &game, Some(&player.username))))
+        }
+        ("POST", ["games", game_id, "guesses"]) => submit_guess(request, word_game, game_id),
+        (_, ["players"])
+        | (_, ["players", _])
+        | (_, ["players", _, "statistics"])
+        | (_, ["games"])
+        | (_, ["games", _])
+        | (_, ["games", _, "join"])
+        | (_, ["games", _, "leave"])
+        | (_, ["games", _, "guesses"]) => Err(Refusal::Http(Response::refuse(
+            Status::MethodNotAllowed,
+            &format!("{} is not allowed on {}", method, request.path),
+        ))),
+        _ => Err(Refusal::Http(Response::refuse(
+            Status::NotFound,
+            &format!("There is nothing at {}", request.path),
+        ))),
+    }
+}
+
+/// The player whose token is in the `Authorization: Bearer` header, if the
+/// request carries one. A header with a token nobody holds is refused.
+fn authenticate(request: &Request, word_game: &WordGame) -> Result<Option<Player>, Refusal> {
+    let header = match request.header("Authorization") {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    let token = match header.split_once(' ') {
+        Some((scheme, token)) if scheme.eq_ignore_ascii_case("Bearer") => token.trim(),
+        _ => return Err(ApplicationError::Unauthorized.into()),
+    };
+    Ok(Some(word_game.authenticate(token)?))
+}
+
+/// Like `authenticate`, for requests that act as a player.
+fn require_player(request: &Request, word_game: &WordGame) -> Result<Player, Refusal> {
+    authenticate(request, word_game)?.ok_or_else(|| ApplicationError::Unauthorized.into())
+}
+
+/// `{"username", "display_name"?, "locales"?, "language"?}`. Locales and
+/// language are language codes, and default to English. The reply carries
+/// the player's `token`, which later requests send as
+/// `Authorization: Bearer {token}`. It is only ever shown here.
+fn create_player(request: &Request, word_game: &mut WordGame) -> ApiResult {
+    let body = json_body(request)?;
+    let username = string_field(&body, "username")?.trim();
+    if username.is_empty() {
+        return Err(bad_request("\"username\" must not be blank"));
+    }
+    let display_name = optional_string_field(&body, "display_name")?.unwrap_or_default();
+    let locales = match body.get("locales") {
+        None | Some(Json::Null) => vec![],
+        Some(Json::Array(codes)) => codes
+            .iter()
+            .map(|code| code.as_str().ok_or_else(|| bad_request("\"locales\" must be a list of language codes")))
+            .map(|code| Ok(code?.parse::<Locale>()?))
+            .collect::<Result<Vec<_>, Refusal>>()?,
+        Some(_) => return Err(bad_request("\"locales\" must be a list of language codes")),
+    };
+    let language = optional_string_field(&body, "language")?
+        .map(str::parse::<Locale>)
+        .transpose()?;
+
+    if word_game.has_player(username) {
+        return Err(ApplicationError::already_exists(Entity::Player, username).into());
+    }
+    word_game.create_player(username, display_name)?;
+    let mut player = word_game.set_player_locales(username, &locales)?;
+    if let Some(language) = language.or_else(|| locales.first().copied()) {
+        player = word_game.set_player_language(username, language)?;
+    }
+    let token = word_game.issue_secret(username)?;
+    let mut body = player_json(&player);
+    if let Json::Object(fields) = &mut body {
+        fields.push(("token".into(), token.into()));
+    }
+    Ok(Response::created(body))
+}
+
+/// `{"locale"?}`. The authenticated player, if any, joins the new game,
+/// which is then played in their preferred language unless `locale` says
+/// otherwise.
+fn create_game(request: &Request, word_game: &mut WordGame) -> ApiResult {
+    let player = authenticate(request, word_game)?;
+    let body = json_body(request)?;
+    let locale = optional_string_field(&body, "locale")?
+        .map(str::parse::<Locale>)
+        .transpose()?;
+    let locale = locale.unwrap_or_else(|| player.as_ref().map(Player::preferred_locale).unwrap_or_default());
+
+    let game = match &player {
+        Some(player) => word_game.start_game(&player.username, locale)?,
+        None => word_game.create_game_in(locale)?,
+    };
+    let viewer = player.as_ref().map(|p| p.username.as_str());
+    Ok(Response::created(game_json(word_game, &game, viewer)))
+}
+
+/// `{"guess"}`, for the round in progress of the authenticated player's
+/// current game.
+fn submit_guess(request: &Request, word_game: &mut WordGame, game_id: &str) -> ApiResult {
+    let player = require_player(request, word_game)?;
+    let username = player.username.as_str();
+    let body = json_body(request)?;
+    let guess = string_field(&body, "guess")?;
+
+    let game = word_game.get_game(game_id)?;
+    match word_game.current_game_for(username)? {
+        Some(current) if current.id == game.id => {}
+        Some(_) => return Err(ApplicationError::NotInGame.into()),
+        None => return Err(ApplicationError::NoCurrentGame.into()),
+    }
+
+    let (outcome, round) = match word_game.submit_guess(username, guess)? {
+        GuessOutcome::Waiting { round } => ("waiting", round),
+        GuessOutcome::Mismatched { round } => ("mismatched", round),
+        GuessOutcome::Matched { round } => ("matched", round),
+    };
+    Ok(Response::ok(Json::object(vec![
+        ("outcome", outcome.into()),
+        ("round", round.into()),
+    ])))
+}
+
+fn json_body(request: &Request) -> Result<Json, Refusal> {
+    if request.body.is_empty() {
+        return Ok(Json::Object(vec![]));
+    }
+    let text = std::str::from_utf8(&request.body).map_err(|_| bad_request("The body must be UTF-8"))?;
+    match text.parse::<Json>() {
+        Ok(body @ Json::Object(_)) => Ok(body),
+        Ok(_) => Err(bad_request("The body must be a JSON object")),
+        Err(e) => Err(bad_request(&format!("The body is not valid JSON: {}", e))),
+    }
+}
+
+fn string_field<'a>(body: &'a Json, name: &str) -> Result<&'a str, Refusal> {
+    optional_string_field(body, name)?.ok_or_else(|| bad_request(&format!("\"{}\" is required", name)))
+}
+
+fn optional_string_field<'a>(body: &'a Json, name: &str) -> Result<Option<&'a str>, Refusal> {
+    match body.get(name) {
+        None | Some(Json::Null) => Ok(None),
+        Some(Json::String(value)) => Ok(Some(value)),
+        Some(_) => Err(bad_request(&format!("\"{}\" must be a string", name))),
+    }
+}
+
+pub(crate) fn player_json(player: &Player) -> Json {
+    let locales: Vec<&str> = player.locales.iter().map(|l| l.code()).collect();
+    Json::object(vec![
+        ("username", player.username.as_str().into()),
+        ("display_name", player.display_name.clone().into()),
+        ("language", player.language.code().into()),
+        ("locales", locales.into()),
+        ("current_game", player.current_game_id.clone().into()),
+    ])
+}
+
+fn statistics_json(statistics: &PlayerStatistics) -> Json {
+    Json::object(vec![
+        ("games_played", statistics.games_played.into()),
+        ("games_won", statistics.games_won.into()),
+        ("assisted_wins", statistics.assisted_wins.into()),
+        ("average_rounds_to_win", statistics.average_rounds_to_win().into()),
+    ])
+}
+
+/// The game as `viewer` may see it: everyone's revealed guesses, and the
+/// viewer's own guess in the round still being played.
+pub(crate) fn game_json(word_game: &WordGame, game: &Game, viewer: Option<&str>) -> Json {
+    let board = word_game.board(game);
+    let seat = |username: Option<&str>| viewer.is_some() && username == viewer;
+    let (viewer_is_1, viewer_is_2) = (seat(game.player_1_username()), seat(game.player_2_username()));
+
+    let players: Vec<Json> = [game.player_1_username(), game.player_2_username()]
+        .iter()
+        .map(|username| match username {
+            Some(username) => Json::object(vec![
+                ("username", (*username).into()),
+                (
+                    "display_name",
+                    word_game.get_player(username).ok().and_then(|p| p.display_name).into(),
+                ),
+            ]),
+            None => Json::Null,
+        })
+        .collect();
+
+    let rounds: Vec<Json> = board
+        .rounds
+        .iter()
+        .zip(game.guesses())
+        .map(|(round, (g1, g2))| {
+            let guess = |shown: &BoardGuess, own: &Option<String>, is_viewer: bool| {
+                let word = match shown {
+                    BoardGuess::Revealed(word) => Some(word.clone()),
+                    BoardGuess::Hidden if is_viewer => own.clone(),
+                    _ => None,
+                };
+                Json::object(vec![
+                    ("ready", (*shown != BoardGuess::Missing).into()),
+                    ("word", word.into()),
+                ])
+            };
+            Json::object(vec![
+                ("number", round.number.into()),
+                ("player_1", guess(&round.player_1_guess, g1, viewer_is_1)),
+                ("player_2", guess(&round.player_2_guess, g2, viewer_is_2)),
+                ("matched", round.matched.into()),
+            ])
+        })
+        .collect();
+
+    let round = if game.is_complete() {
+        game.current_round()
+    } else {
+        game.current_round() + 1
+    };
+    let timestamp = |seconds: i64| Utc.timestamp(seconds, 0).to_rfc3339();
+
+    Json::object(vec![
+        ("id", game.id.as_str().into()),
+        ("locale", game.locale().code().into()),
+        ("complete", game.is_complete().into()),
+        ("round", round.into()),
+        ("players", Json::Array(players)),
+        ("rounds", Json::Array(rounds)),
+        ("started_at", timestamp(game.start_time()).into()),
+        ("ended_at", game.end_time().map(timestamp).into()),
+        ("duration", board.duration.format(&DurationFormat::new(DurationStyle::Iso8601)).into()),
+    ])
+}
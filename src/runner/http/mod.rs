@@ -0,0 +1,129 @@
+use std::{
+    future::Future,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use tokio::{
+    io::BufReader,
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{game::WordGame, AppResult, ApplicationError};
+
+use self::{
+    request::read_request,
+    response::{Response, Status},
+};
+
+use super::{address_env, shutdown_signal, Runner};
+
+pub mod api;
+pub mod request;
+pub mod response;
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+
+/// Serves the game as a JSON REST API.
+///
+/// | Method | Path | Body |
+/// |--------|------|------|
+/// | POST | `/players` | `{"username", "display_name"?, "locales"?, "language"?}` |
+/// | GET | `/players/{username}` | |
+/// | GET | `/players/{username}/statistics` | |
+/// | POST | `/games` | `{"locale"?}` |
+/// | GET | `/games/{id}` | |
+/// | POST | `/games/{id}/join` | |
+/// | POST | `/games/{id}/leave` | |
+/// | POST | `/games/{id}/guesses` | `{"guess"}` |
+///
+/// Creating a player replies with a `token`. Requests act as, and games are
+/// shown to, the player whose token is sent as `Authorization: Bearer
+/// {token}`; joining, leaving and guessing need one.
+///
+/// Game errors come back with a 4xx or 5xx status and a body of
+/// `{"error": code, "message": text}`. Each connection carries one request.
+pub struct HttpRunner {
+    address: SocketAddr,
+    listener: Option<TcpListener>,
+}
+
+impl HttpRunner {
+    pub fn new() -> AppResult<Self> {
+        Ok(Self::with_address(address_env("WORD_GAME_HTTP_ADDR", DEFAULT_ADDRESS)?))
+    }
+
+    pub fn with_address(address: SocketAddr) -> Self {
+        Self { address, listener: None }
+    }
+
+    /// Starts listening, returning the address in use, which tells which
+    /// port was picked for port 0. `run_until` binds by itself otherwise.
+    pub async fn bind(&mut self) -> AppResult<SocketAddr> {
+        let listener = TcpListener::bind(self.address).await?;
+        let address = listener.local_addr()?;
+        self.listener = Some(listener);
+        Ok(address)
+    }
+
+    /// Serves requests until `shutdown` resolves.
+    pub async fn run_until<F: Future<Output = ()>>(&mut self, game: Arc<Mutex<WordGame>>, shutdown: F) -> AppResult<()> {
+        if self.listener.is_none() {
+            self.bind().await?;
+        }
+        let listener = self
+            .listener
+            .take()
+            .ok_or_else(|| ApplicationError::transport("Not listening"))?;
+        println!("Serving the word game on http://{}", listener.local_addr()?);
+        tokio::pin!(shutdown);
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    println!("Shutting down...");
+                    break;
+                }
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        tokio::spawn(handle_connection(stream, game.clone()));
+                    }
+                    Err(e) => println!("Could not accept a connection: {}", e),
+                }
+            }
+        }
+
+        game.lock().unwrap().flush()?;
+
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Runner for HttpRunner {
+    async fn run(&mut self, game: Arc<Mutex<WordGame>>) -> AppResult<()> {
+        self.run_until(game, shutdown_signal()).await
+    }
+}
+
+async fn handle_connection(stream: TcpStream, game: Arc<Mutex<WordGame>>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    let response = match read_request(&mut reader).await {
+        Ok(Some(request)) => {
+            let mut word_game = game.lock().unwrap();
+            api::handle(&request, &mut word_game)
+        }
+        Ok(None) => return,
+        Err(status) => Response::refuse(status, status.reason()),
+    };
+    if response.status == Status::InternalServerError {
+        println!("Internal error: {}", response.body);
+    }
+
+    if let Err(e) = response.write_to(&mut writer).await {
+        println!("Could not send a response: {}", e);
+    }
+}
@@ -0,0 +1,159 @@
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+
+use super::response::Status;
+
+/// Largest request head, request line and headers together, we read.
+const MAX_HEAD_BYTES: usize = 8 * 1024;
+/// Largest request body we accept.
+pub const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// An HTTP/1.1 request, with its path already split from the query.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// The first header called `name`, whatever its case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// The path split on `/`, with each segment percent-decoded.
+    pub fn segments(&self) -> Vec<String> {
+        self.path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(percent_decode)
+            .collect()
+    }
+}
+
+/// Reads one request. `Ok(None)` means the client hung up before sending
+/// anything; otherwise a malformed request gives the status to refuse it
+/// with.
+pub async fn read_request<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Request>, Status> {
+    let mut head_bytes = 0;
+    let request_line = match read_line(reader, &mut head_bytes).await? {
+        Some(line) => line,
+        None => return Ok(None),
+    };
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target, version) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version), None) => (method, target, version),
+        _ => return Err(Status::BadRequest),
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(Status::BadRequest);
+    }
+
+    let mut headers = vec![];
+    loop {
+        let line = read_line(reader, &mut head_bytes).await?.ok_or(Status::BadRequest)?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').ok_or(Status::BadRequest)?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, parse_query(query)),
+        None => (target, vec![]),
+    };
+    let mut request = Request {
+        method: method.to_uppercase(),
+        path: path.into(),
+        query,
+        headers,
+        body: vec![],
+    };
+
+    if request.header("Transfer-Encoding").is_some() {
+        return Err(Status::LengthRequired);
+    }
+    let length = match request.header("Content-Length") {
+        Some(length) => length.parse::<usize>().map_err(|_| Status::BadRequest)?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err(Status::PayloadTooLarge);
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body).await.map_err(|_| Status::BadRequest)?;
+
+    Ok(Some(request))
+}
+
+/// Reads a line without its line ending, counting it against the head
+/// limit. `None` at the end of the stream.
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R, head_bytes: &mut usize) -> Result<Option<String>, Status> {
+    let mut line = vec![];
+    let limit = (MAX_HEAD_BYTES - *head_bytes) as u64 + 1;
+    let read = reader
+        .take(limit)
+        .read_until(b'\n', &mut line)
+        .await
+        .map_err(|_| Status::BadRequest)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    *head_bytes += read;
+    if *head_bytes > MAX_HEAD_BYTES {
+        return Err(Status::HeaderFieldsTooLarge);
+    }
+    if line.last() != Some(&b'\n') {
+        return Err(Status::BadRequest);
+    }
+    let line = String::from_utf8(line).map_err(|_| Status::BadRequest)?;
+    Ok(Some(line.trim_end_matches(&['\r', '\n'][..]).to_string()))
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(&name.replace('+', " ")), percent_decode(&value.replace('+', " ")))
+        })
+        .collect()
+}
+
+/// Decodes `%XX` escapes, leaving anything that isn't a valid escape as it
+/// is.
+pub fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,156 @@
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::{json::Json, locale::Locale, ApplicationError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Created,
+    BadRequest,
+    Unauthorized,
+    NotFound,
+    MethodNotAllowed,
+    Conflict,
+    LengthRequired,
+    PayloadTooLarge,
+    UnprocessableEntity,
+    HeaderFieldsTooLarge,
+    InternalServerError,
+}
+
+impl Status {
+    pub fn code(&self) -> u16 {
+        match self {
+            Status::Ok => 200,
+            Status::Created => 201,
+            Status::BadRequest => 400,
+            Status::Unauthorized => 401,
+            Status::NotFound => 404,
+            Status::MethodNotAllowed => 405,
+            Status::Conflict => 409,
+            Status::LengthRequired => 411,
+            Status::PayloadTooLarge => 413,
+            Status::UnprocessableEntity => 422,
+            Status::HeaderFieldsTooLarge => 431,
+            Status::InternalServerError => 500,
+        }
+    }
+
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Status::Ok => "OK",
+            Status::Created => "Created",
+            Status::BadRequest => "Bad Request",
+            Status::Unauthorized => "Unauthorized",
+            Status::NotFound => "Not Found",
+            Status::MethodNotAllowed => "Method Not Allowed",
+            Status::Conflict => "Conflict",
+            Status::LengthRequired => "Length Required",
+            Status::PayloadTooLarge => "Payload Too Large",
+            Status::UnprocessableEntity => "Unprocessable Entity",
+            Status::HeaderFieldsTooLarge => "Request Header Fields Too Large",
+            Status::InternalServerError => "Internal Server Error",
+        }
+    }
+
+    /// A short, stable name for errors that never reach the game, in the
+    /// style of `ApplicationError::code`.
+    fn error_code(&self) -> &'static str {
+        match self {
+            Status::NotFound => "no_route",
+            Status::MethodNotAllowed => "method_not_allowed",
+            Status::LengthRequired => "length_required",
+            Status::PayloadTooLarge => "payload_too_large",
+            Status::HeaderFieldsTooLarge => "headers_too_large",
+            Status::InternalServerError => "internal",
+            _ => "bad_request",
+        }
+    }
+}
+
+impl From<&ApplicationError> for Status {
+    fn from(e: &ApplicationError) -> Self {
+        match e {
+            ApplicationError::NotFound { .. } => Status::NotFound,
+            ApplicationError::Unauthorized => Status::Unauthorized,
+            ApplicationError::AlreadyExists { .. }
+            | ApplicationError::GameFull
+            | ApplicationError::GameComplete
+            | ApplicationError::AlreadyJoined
+            | ApplicationError::InAnotherGame { .. }
+            | ApplicationError::AlreadyGuessed
+            | ApplicationError::NotInGame
+            | ApplicationError::NoCurrentGame
+            | ApplicationError::NoGuess { .. }
+            | ApplicationError::LanguageMismatch(_)
+            | ApplicationError::NoHint => Status::Conflict,
+            ApplicationError::InvalidGuess(_)
+            | ApplicationError::UnknownLanguage(_)
+            | ApplicationError::InvalidDuration(_) => Status::UnprocessableEntity,
+            ApplicationError::Storage { .. }
+            | ApplicationError::Transport { .. }
+            | ApplicationError::Config { .. }
+            | ApplicationError::Io(_) => Status::InternalServerError,
+        }
+    }
+}
+
+/// A response with a JSON body.
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: Status,
+    pub body: Json,
+}
+
+impl Response {
+    pub fn new(status: Status, body: Json) -> Self {
+        Self { status, body }
+    }
+
+    pub fn ok(body: Json) -> Self {
+        Self::new(Status::Ok, body)
+    }
+
+    pub fn created(body: Json) -> Self {
+        Self::new(Status::Created, body)
+    }
+
+    /// An error the game raised, explained in `locale`.
+    pub fn from_error(e: &ApplicationError, locale: Locale) -> Self {
+        Self::new(Status::from(e), error_body(e.code(), &e.user_message(locale)))
+    }
+
+    /// A request the API refuses before it reaches the game.
+    pub fn refuse(status: Status, message: &str) -> Self {
+        Self::new(status, error_body(status.error_code(), message))
+    }
+
+    pub async fn write_to<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> std::io::Result<()> {
+        let body = self.body.to_string();
+        write_response(writer, self.status, "application/json; charset=utf-8", body.as_bytes()).await
+    }
+}
+
+/// Writes a whole response and leaves the connection to be closed, for
+/// bodies that aren't JSON.
+pub async fn write_response<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    status: Status,
+    content_type: &str,
+    body: &[u8],
+) -> std::io::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status.code(),
+        status.reason(),
+        content_type,
+        body.len()
+    );
+    writer.write_all(head.as_bytes()).await?;
+    writer.write_all(body).await?;
+    writer.flush().await
+}
+
+fn error_body(code: &str, message: &str) -> Json {
+    Json::object(vec![("error", code.into()), ("message", message.into())])
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Where an event goes: back down the connection that caused it, or to
+/// every connection of a player.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recipient {
+    Connection,
+    Player(String),
+}
+
+/// Who is connected as which player, so runners that keep connections
+/// open can push events to them.
+///
+/// A player may be connected more than once, say from a phone and a laptop,
+/// and every connection gets every event.
+pub struct Hub<T> {
+    next_id: u64,
+    connections: HashMap<String, Vec<(u64, UnboundedSender<T>)>>,
+}
+
+impl<T: Clone> Hub<T> {
+    pub fn new() -> Self {
+        Self {
+            next_id: 0,
+            connections: HashMap::new(),
+        }
+    }
+
+    /// Sends `username`'s events to `sender` until `unsubscribe` is called
+    /// with the id returned.
+    pub fn subscribe(&mut self, username: &str, sender: UnboundedSender<T>) -> u64 {
+        self.next_id += 1;
+        self.connections
+            .entry(username.into())
+            .or_default()
+            .push((self.next_id, sender));
+        self.next_id
+    }
+
+    pub fn unsubscribe(&mut self, username: &str, id: u64) {
+        if let Some(senders) = self.connections.get_mut(username) {
+            senders.retain(|(i, _)| *i != id);
+            if senders.is_empty() {
+                self.connections.remove(username);
+            }
+        }
+    }
+
+    pub fn send(&self, username: &str, event: &T) {
+        for (_, sender) in self.connections.get(username).into_iter().flatten() {
+            // A closed connection unsubscribes itself on the way out.
+            let _ = sender.send(event.clone());
+        }
+    }
+
+    pub fn is_connected(&self, username: &str) -> bool {
+        self.connections.contains_key(username)
+    }
+}
+
+impl<T: Clone> Default for Hub<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
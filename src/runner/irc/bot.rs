@@ -0,0 +1,308 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    game::{board::Board, Game, WordGame},
+    locale::{messages, Locale},
+    runner::commands::{self, Guessed},
+    AppResult,
+};
+
+use super::message::{lowercase, Message};
+
+/// Someone the bot has talked to, under the nickname they use now.
+struct Known {
+    nick: String,
+    username: String,
+}
+
+/// The bot's side of an IRC conversation, apart from the connection.
+///
+/// Players send it the same commands as the XMPP bot in private messages,
+/// and it announces new games, reveals and wins in its channel.
+///
+/// A player's username comes from the nickname they first talked to the
+/// bot with. When they change nicknames the bot follows along, so they
+/// stay the same player; someone else taking the old nickname meanwhile
+/// is turned away rather than handed their games.
+pub struct IrcBot {
+    network: String,
+    configured_nick: String,
+    nick: String,
+    channel: String,
+    registered: bool,
+    // Lowercase nickname -> who uses it
+    known: HashMap<String, Known>,
+}
+
+impl IrcBot {
+    /// A bot on `network`, which is part of every username so players from
+    /// different networks don't mix.
+    pub fn new(network: &str, nick: &str, channel: &str) -> Self {
+        Self {
+            network: network.into(),
+            configured_nick: nick.into(),
+            nick: nick.into(),
+            channel: channel.into(),
+            registered: false,
+            known: HashMap::new(),
+        }
+    }
+
+    /// The nickname the server knows the bot by.
+    pub fn nick(&self) -> &str {
+        &self.nick
+    }
+
+    pub fn is_registered(&self) -> bool {
+        self.registered
+    }
+
+    /// The messages that open a connection. Nicknames may have changed
+    /// hands while the bot was away, so it forgets who used them.
+    pub fn register(&mut self, password: Option<&str>) -> Vec<Message> {
+        self.nick = self.configured_nick.clone();
+        self.registered = false;
+        self.known.clear();
+        let mut messages = vec![];
+        if let Some(password) = password {
+            messages.push(Message::new("PASS", &[password]));
+        }
+        messages.push(Message::new("NICK", &[&self.nick]));
+        messages.push(Message::new("USER", &[&self.nick, "0", "*", "Word game"]));
+        messages
+    }
+
+    /// The username for a nickname, if it has talked to the bot.
+    pub fn username_for(&self, nick: &str) -> Option<&str> {
+        self.known.get(&lowercase(nick)).map(|known| known.username.as_str())
+    }
+
+    pub fn handle(&mut self, message: &Message, game: &Arc<Mutex<WordGame>>) -> Vec<Message> {
+        match message.command.as_str() {
+            "PING" => {
+                let params: Vec<&str> = message.params.iter().map(|p| p.as_str()).collect();
+                vec![Message::new("PONG", &params)]
+            }
+            // RPL_WELCOME: registration went through.
+            "001" => {
+                self.registered = true;
+                if let Some(nick) = message.param(0) {
+                    self.nick = nick.into();
+                }
+                println!("Registered as {}, joining {}", self.nick, self.channel);
+                vec![Message::new("JOIN", &[&self.channel])]
+            }
+            // ERR_NICKNAMEINUSE while registering: try a variation.
+            "433" if !self.registered => {
+                self.nick.push('_');
+                vec![Message::new("NICK", &[&self.nick])]
+            }
+            "NICK" => {
+                if let (Some(old), Some(new)) = (message.nick(), message.param(0)) {
+                    self.handle_nick_change(old, new);
+                }
+                vec![]
+            }
+            // Once someone is out of the channel the bot no longer sees
+            // their nickname changes, so it can't vouch for it anymore.
+            "QUIT" | "PART" => {
+                if let Some(nick) = message.nick() {
+                    self.known.remove(&lowercase(nick));
+                }
+                vec![]
+            }
+            "KICK" if message.param(1).map(lowercase) == Some(lowercase(&self.nick)) => {
+                println!("Kicked from {}, rejoining", self.channel);
+                vec![Message::new("JOIN", &[&self.channel])]
+            }
+            "KICK" => {
+                if let Some(nick) = message.param(1) {
+                    self.known.remove(&lowercase(nick));
+                }
+                vec![]
+            }
+            "PRIVMSG" => match (message.nick(), message.param(0), message.param(1)) {
+                // CTCP requests, like ACTION or VERSION, aren't commands.
+                (_, _, Some(text)) if text.starts_with('\u{1}') => vec![],
+                (Some(nick), Some(target), Some(text)) if lowercase(target) == lowercase(&self.nick) => {
+                    self.handle_private(nick, text.trim(), game)
+                }
+                _ => vec![],
+            },
+            _ => vec![],
+        }
+    }
+
+    fn handle_nick_change(&mut self, old: &str, new: &str) {
+        if lowercase(old) == lowercase(&self.nick) {
+            self.nick = new.into();
+            return;
+        }
+        // Whoever had the new nickname before isn't using it anymore.
+        self.known.remove(&lowercase(new));
+        if let Some(known) = self.known.remove(&lowercase(old)) {
+            println!("{} is now known as {}", old, new);
+            let known = Known {
+                nick: new.into(),
+                username: known.username,
+            };
+            self.known.insert(lowercase(new), known);
+        }
+    }
+
+    /// The username for `nick`, getting to know them if needed. `None`
+    /// means the player is already here under another nickname.
+    fn identify(&mut self, nick: &str) -> Option<String> {
+        let key = lowercase(nick);
+        if let Some(known) = self.known.get(&key) {
+            return Some(known.username.clone());
+        }
+        let username = format!("irc://{}/{}", self.network, key);
+        if self.known.values().any(|known| known.username == username) {
+            return None;
+        }
+        let known = Known {
+            nick: nick.into(),
+            username: username.clone(),
+        };
+        self.known.insert(key, known);
+        Some(username)
+    }
+
+    fn handle_private(&mut self, nick: &str, body: &str, game: &Arc<Mutex<WordGame>>) -> Vec<Message> {
+        let username = match self.identify(nick) {
+            Some(username) => username,
+            None => {
+                let text = messages::text(Locale::default(), "irc.identity_taken", &[("name", &nick)]);
+                return Message::privmsg(nick, &text);
+            }
+        };
+        let mut word_game = game.lock().unwrap();
+        self.handle_command(nick, &username, body, &mut word_game)
+            .unwrap_or_else(|e| {
+                println!("Error handling a message from {}: {:?}", nick, e);
+                Message::privmsg(nick, &e.user_message(word_game.language_for(&username)))
+            })
+    }
+
+    fn handle_command(&mut self, nick: &str, username: &str, body: &str, word_game: &mut WordGame) -> AppResult<Vec<Message>> {
+        let language = word_game.language_for(username);
+        let text = |key: &str, args: &[(&str, &dyn Display)]| messages::text(language, key, args);
+        let reply = |text: &str| Message::privmsg(nick, text);
+        let command = body.split_whitespace().next().unwrap_or_default().to_lowercase();
+
+        match command.as_str() {
+            "" | "/help" => Ok(reply(&list_commands(language))),
+            "/status" => Ok(reply(&commands::status(word_game, username, nick))),
+            "/language" => Ok(reply(&commands::change_language(word_game, username, nick, body))),
+            "/start" => self.start(nick, username, body, word_game),
+            "/join" => self.join(nick, username, body, word_game),
+            "/stats" => Ok(reply(&word_game.statistics(username)?.text(language))),
+            "/board" => match word_game.current_game_for(username)? {
+                Some(game) => Ok(reply(&self.board_for(word_game, &game).to_string())),
+                None => Ok(reply(&text("error.no_current_game", &[]))),
+            },
+            "/hint" => Ok(reply(&commands::hint(word_game, username)?)),
+            command if command.starts_with('/') => {
+                Ok(reply(&format!("{}\n{}", text("adhoc.unknown_command", &[]), list_commands(language))))
+            }
+            _ => self.guess(nick, username, body, word_game),
+        }
+    }
+
+    fn start(&mut self, nick: &str, username: &str, body: &str, word_game: &mut WordGame) -> AppResult<Vec<Message>> {
+        let game = match commands::start(word_game, username, nick, body)? {
+            Ok(game) => game,
+            Err(refusal) => return Ok(Message::privmsg(nick, &refusal)),
+        };
+
+        let created = messages::text(word_game.language_for(username), "adhoc.created", &[("code", &game.id)]);
+        let mut messages = Message::privmsg(nick, &created);
+        let announcement = messages::text(
+            game.locale(),
+            "irc.started",
+            &[("name", &nick), ("language", &game.locale()), ("code", &game.id)],
+        );
+        messages.extend(Message::privmsg(&self.channel, &announcement));
+        Ok(messages)
+    }
+
+    fn join(&mut self, nick: &str, username: &str, body: &str, word_game: &mut WordGame) -> AppResult<Vec<Message>> {
+        let game = match commands::join_by_code(word_game, username, nick, body.split_whitespace().nth(1))? {
+            Ok(game) => game,
+            Err(refusal) => return Ok(Message::privmsg(nick, &refusal)),
+        };
+
+        let joined = messages::text(word_game.language_for(username), "adhoc.joined", &[]);
+        let mut messages = Message::privmsg(nick, &joined);
+        if let Some(partner) = game.player_1_username().or_else(|| game.player_2_username()) {
+            let partner = self.nick_for(word_game, partner);
+            let announcement = messages::text(game.locale(), "room.playing", &[("player_1", &partner), ("player_2", &nick)]);
+            messages.extend(Message::privmsg(&self.channel, &announcement));
+        }
+        Ok(messages)
+    }
+
+    fn guess(&mut self, nick: &str, username: &str, body: &str, word_game: &mut WordGame) -> AppResult<Vec<Message>> {
+        let language = word_game.language_for(username);
+        let before = match word_game.current_game_for(username)? {
+            Some(game) if !game.is_complete() => game,
+            _ => return Ok(Message::privmsg(nick, &messages::text(language, "error.no_current_game", &[]))),
+        };
+        let (reply, round, after) = match commands::guess(word_game, username, &before, body, None)? {
+            Guessed::Refused(reply) | Guessed::Waiting(reply) => return Ok(Message::privmsg(nick, &reply)),
+            Guessed::NeedPartner(game) => {
+                let reply = messages::text(language, "irc.need_partner", &[("code", &game.id)]);
+                return Ok(Message::privmsg(nick, &reply));
+            }
+            Guessed::Revealed { reply, round, game } => (reply, round, game),
+        };
+
+        let mut messages = Message::privmsg(nick, &reply);
+        let board = self.board_for(word_game, &after);
+        let (reveal, outcome) = commands::reveal(&after, round, &board);
+        messages.extend(Message::privmsg(&self.channel, &reveal));
+        if after.is_complete() {
+            messages.extend(Message::privmsg(&self.channel, &format!("{}\n{}", outcome, board)));
+        } else {
+            messages.extend(Message::privmsg(&self.channel, &outcome));
+        }
+        Ok(messages)
+    }
+
+    /// What to call a player: the nickname they use now, or the name they
+    /// started with once they have left.
+    fn nick_for(&self, word_game: &WordGame, username: &str) -> String {
+        match self.known.values().find(|known| known.username == username) {
+            Some(known) => known.nick.clone(),
+            None => word_game
+                .get_player(username)
+                .map_or_else(|_| username.to_string(), |player| player.to_string()),
+        }
+    }
+
+    fn board_for(&self, word_game: &WordGame, game: &Game) -> Board {
+        let name = |username: Option<&str>| username.map_or("???".into(), |u| self.nick_for(word_game, u));
+        let (player_1, player_2) = (name(game.player_1_username()), name(game.player_2_username()));
+        word_game.board(game).with_names(&player_1, &player_2)
+    }
+}
+
+fn list_commands(language: Locale) -> String {
+    let commands_list = [
+        ("start [language]", "command.new_game"),
+        ("join <code>", "command.join_code"),
+        ("board", "command.board"),
+        ("hint", "command.hint"),
+        ("stats", "command.stats"),
+        ("status", "command.status"),
+        ("language <code>", "command.language"),
+        ("help", "command.help"),
+    ];
+    let commands = commands::command_list(language, "xmpp.commands", '/', &commands_list);
+    format!("{}\n{}", commands, messages::text(language, "command.guess", &[]))
+}
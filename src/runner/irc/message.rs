@@ -0,0 +1,126 @@
+use std::fmt;
+
+/// Most text a message may carry so the line stays within the 512 bytes
+/// IRC allows, leaving room for the prefix the server adds when relaying.
+pub const MAX_TEXT_BYTES: usize = 400;
+
+/// One line of the IRC protocol (RFC 1459, section 2.3.1).
+///
+/// IRCv3 message tags are accepted but dropped, since nothing here needs
+/// them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub prefix: Option<String>,
+    pub command: String,
+    pub params: Vec<String>,
+}
+
+impl Message {
+    pub fn new(command: &str, params: &[&str]) -> Self {
+        Self {
+            prefix: None,
+            command: command.into(),
+            params: params.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    /// Parses a line as received, with or without its line ending.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut rest = line.trim_end_matches(['\r', '\n']);
+        if rest.starts_with('@') {
+            rest = rest.split_once(' ')?.1.trim_start();
+        }
+        let prefix = match rest.strip_prefix(':') {
+            Some(prefixed) => {
+                let (prefix, after) = prefixed.split_once(' ')?;
+                rest = after.trim_start();
+                Some(prefix.to_string())
+            }
+            None => None,
+        };
+
+        let (middle, trailing) = match rest.split_once(" :") {
+            Some((middle, trailing)) => (middle, Some(trailing)),
+            None => (rest, None),
+        };
+        let mut params = middle.split(' ').filter(|p| !p.is_empty()).map(String::from);
+        let command = params.next()?.to_uppercase();
+        let mut params: Vec<String> = params.collect();
+        params.extend(trailing.map(String::from));
+
+        Some(Self { prefix, command, params })
+    }
+
+    /// The nickname of whoever sent the message, for user prefixes like
+    /// `nick!user@host`.
+    pub fn nick(&self) -> Option<&str> {
+        let prefix = self.prefix.as_deref()?;
+        prefix.split(['!', '@']).next()
+    }
+
+    pub fn param(&self, index: usize) -> Option<&str> {
+        self.params.get(index).map(|p| p.as_str())
+    }
+
+    /// `text` as private or channel messages to `target`, one per line and
+    /// wrapped to fit the line length limit. Blank lines are skipped, as
+    /// IRC can't send them.
+    pub fn privmsg(target: &str, text: &str) -> Vec<Self> {
+        text.lines()
+            .flat_map(|line| wrap(line.trim_end(), MAX_TEXT_BYTES))
+            .map(|line| Self::new("PRIVMSG", &[target, &line]))
+            .collect()
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(prefix) = &self.prefix {
+            write!(f, ":{} ", prefix)?;
+        }
+        write!(f, "{}", self.command)?;
+        for (i, param) in self.params.iter().enumerate() {
+            let last = i + 1 == self.params.len();
+            if last && (param.is_empty() || param.contains(' ') || param.starts_with(':')) {
+                write!(f, " :{}", param)?;
+            } else {
+                write!(f, " {}", param)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Nicknames and channels compare case-insensitively, with `[]\~` as the
+/// upper case of `{}|^` (RFC 1459, section 2.2).
+pub fn lowercase(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '[' => '{',
+            ']' => '}',
+            '\\' => '|',
+            '~' => '^',
+            c => c.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+/// Splits `line` into pieces of at most `max` bytes, at spaces where it
+/// can and never inside a character.
+fn wrap(line: &str, max: usize) -> Vec<String> {
+    let mut pieces = vec![];
+    let mut rest = line;
+    while rest.len() > max {
+        let mut end = max;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let end = rest[..end].rfind(' ').filter(|&space| space > 0).unwrap_or(end);
+        pieces.push(rest[..end].to_string());
+        rest = rest[end..].trim_start();
+    }
+    if !rest.is_empty() {
+        pieces.push(rest.to_string());
+    }
+    pieces
+}
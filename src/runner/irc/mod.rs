@@ -0,0 +1,173 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{tcp::OwnedWriteHalf, TcpStream},
+};
+
+use crate::{game::WordGame, time::duration::FormattedDuration, AppResult};
+
+use self::{bot::IrcBot, message::Message};
+
+use super::{backoff::Backoff, required_env, shutdown_signal, Runner};
+
+pub mod bot;
+pub mod message;
+
+const DEFAULT_NICK: &str = "WordGame";
+
+/// Plays the game over IRC.
+///
+/// The bot joins one channel, takes commands in private messages and
+/// announces games in the channel; see `IrcBot`. The connection is plain
+/// TCP, so put a TLS tunnel in front for networks that require it. Like
+/// the XMPP bot it reconnects with backoff whenever the connection drops.
+pub struct IrcRunner {
+    server: String,
+    password: Option<String>,
+    running: bool,
+    bot: IrcBot,
+}
+
+impl IrcRunner {
+    pub fn new() -> AppResult<Self> {
+        let server = required_env("WORD_GAME_IRC_SERVER")?;
+        let channel = required_env("WORD_GAME_IRC_CHANNEL")?;
+        let nick = std::env::var("WORD_GAME_IRC_NICK").unwrap_or_else(|_| DEFAULT_NICK.into());
+        let runner = Self::with_server(&server, &nick, &channel);
+        Ok(match std::env::var("WORD_GAME_IRC_PASSWORD") {
+            Ok(password) => runner.with_password(&password),
+            Err(_) => runner,
+        })
+    }
+
+    /// A bot for `channel` on `server`, an address like `irc.example.org:6667`.
+    pub fn with_server(server: &str, nick: &str, channel: &str) -> Self {
+        let network = server.rsplit_once(':').map_or(server, |(host, _)| host);
+        Self {
+            server: server.into(),
+            password: None,
+            running: true,
+            bot: IrcBot::new(network, nick, channel),
+        }
+    }
+
+    /// Sends `password` with `PASS` when connecting.
+    pub fn with_password(mut self, password: &str) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Runs the bot until `shutdown` resolves, reconnecting whenever the
+    /// connection drops.
+    pub async fn run_until<F: Future<Output = ()>>(&mut self, game: Arc<Mutex<WordGame>>, shutdown: F) -> AppResult<()> {
+        let mut backoff = Backoff::new();
+        tokio::pin!(shutdown);
+        self.running = true;
+
+        while self.running {
+            match TcpStream::connect(&self.server).await {
+                Ok(stream) => {
+                    println!("Connected to {}", self.server);
+                    self.converse(stream, &game, &mut backoff, &mut shutdown).await;
+                }
+                Err(e) => println!("Could not connect to {}: {}", self.server, e),
+            }
+            if !self.running {
+                break;
+            }
+
+            let delay = backoff.next_delay();
+            println!("Reconnecting in {}...", FormattedDuration::from(delay));
+            tokio::select! {
+                _ = &mut shutdown => {
+                    println!("Shutting down...");
+                    self.running = false;
+                }
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+
+        game.lock().unwrap().flush()?;
+
+        Ok(())
+    }
+
+    /// Talks to the server until the connection drops or `shutdown`
+    /// resolves.
+    async fn converse<F: Future<Output = ()> + Unpin>(
+        &mut self,
+        stream: TcpStream,
+        game: &Arc<Mutex<WordGame>>,
+        backoff: &mut Backoff,
+        shutdown: &mut F,
+    ) {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        // Not every client sends UTF-8, so lines are read as bytes. Reading
+        // into a buffer that outlives the select loses nothing on shutdown.
+        let mut buffer = vec![];
+        if send(&mut writer, self.bot.register(self.password.as_deref())).await.is_err() {
+            return;
+        }
+
+        loop {
+            tokio::select! {
+                _ = &mut *shutdown => {
+                    println!("Shutting down...");
+                    self.running = false;
+                    let _ = send(&mut writer, vec![Message::new("QUIT", &["Bye!"])]).await;
+                    return;
+                }
+                read = reader.read_until(b'\n', &mut buffer) => {
+                    match read {
+                        Ok(0) => {
+                            println!("Disconnected from {}", self.server);
+                            return;
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            println!("Disconnected from {}: {}", self.server, e);
+                            return;
+                        }
+                    }
+                    let line = String::from_utf8_lossy(&buffer).into_owned();
+                    buffer.clear();
+                    let message = match Message::parse(&line) {
+                        Some(message) => message,
+                        None => continue,
+                    };
+                    if message.command == "ERROR" {
+                        println!("The server closed the connection: {}", message.param(0).unwrap_or_default());
+                        return;
+                    }
+                    let replies = self.bot.handle(&message, game);
+                    if self.bot.is_registered() {
+                        backoff.reset();
+                    }
+                    if send(&mut writer, replies).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Runner for IrcRunner {
+    async fn run(&mut self, game: Arc<Mutex<WordGame>>) -> AppResult<()> {
+        self.run_until(game, shutdown_signal()).await
+    }
+}
+
+async fn send(writer: &mut OwnedWriteHalf, messages: Vec<Message>) -> std::io::Result<()> {
+    for message in messages {
+        writer.write_all(format!("{}\r\n", message).as_bytes()).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,479 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    game::{board::Board, Game, WordGame},
+    json::Json,
+    locale::{messages, Locale},
+    runner::commands::{self, Guessed},
+    AppResult,
+};
+
+/// Account data listing the direct message rooms with each user.
+pub const DIRECT_EVENT: &str = "m.direct";
+/// Account data mapping each room hosting a game to the game's code.
+pub const HOSTED_EVENT: &str = "org.wordgame.hosted";
+
+/// Something the bot wants done on the homeserver.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Join(String),
+    Send {
+        room: String,
+        text: String,
+        /// An HTML version, for boards that need a monospaced font.
+        html: Option<String>,
+    },
+    /// Replaces the account data of type `kind`.
+    SetAccountData { kind: String, content: Json },
+}
+
+impl Action {
+    fn send(room: &str, text: &str) -> Self {
+        Action::Send {
+            room: room.into(),
+            text: text.into(),
+            html: None,
+        }
+    }
+
+    fn send_board(room: &str, intro: Option<&str>, board: &Board) -> Self {
+        let board = board.to_string();
+        let (text, html) = match intro {
+            Some(intro) => (format!("{}\n{}", intro, board), format!("<p>{}</p><pre>{}</pre>", escape(intro), escape(&board))),
+            None => (board.clone(), format!("<pre>{}</pre>", escape(&board))),
+        };
+        Action::Send {
+            room: room.into(),
+            text,
+            html: Some(html),
+        }
+    }
+}
+
+/// The bot's side of a Matrix account, apart from the homeserver.
+///
+/// It joins every room it is invited to. In direct messages players can
+/// use all the commands and send their guesses; in other rooms anyone can
+/// `!start` a game hosted there or `!join` it. Reveals go to the room
+/// hosting the game, or to both players' direct messages for games
+/// started by code.
+///
+/// Commands start with `!`, since most clients keep `/` for their own,
+/// but `/` works too. Players are known by their Matrix user id.
+///
+/// A room is a direct message only if its invite said so or the account's
+/// `m.direct` lists it. The bot keeps `m.direct` and the rooms hosting
+/// games in account data, so a restarted bot picks them up again from its
+/// first sync.
+pub struct MatrixBot {
+    user_id: String,
+    // Player -> their direct message rooms, newest last, as in `m.direct`
+    direct: BTreeMap<String, Vec<String>>,
+    // Room -> the game it hosts
+    hosted: BTreeMap<String, String>,
+}
+
+impl MatrixBot {
+    pub fn new(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.into(),
+            direct: BTreeMap::new(),
+            hosted: BTreeMap::new(),
+        }
+    }
+
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Works through a sync response. With `catching_up` the messages in it
+    /// are history from before the bot started and are left alone; invites
+    /// are still accepted.
+    pub fn handle_sync(&mut self, sync: &Json, game: &Arc<Mutex<WordGame>>, catching_up: bool) -> Vec<Action> {
+        let account_data = sync.get("account_data").and_then(|data| data.get("events"));
+        for event in account_data.and_then(Json::as_array).unwrap_or_default() {
+            self.load_account_data(event);
+        }
+
+        let rooms = match sync.get("rooms") {
+            Some(rooms) => rooms,
+            None => return vec![],
+        };
+        let mut actions = vec![];
+
+        for (room_id, room) in entries(rooms.get("invite")) {
+            let events = room.get("invite_state").and_then(|state| state.get("events"));
+            let invite = events
+                .and_then(Json::as_array)
+                .unwrap_or_default()
+                .iter()
+                .find(|event| {
+                    event.get("type").and_then(Json::as_str) == Some("m.room.member")
+                        && event.get("state_key").and_then(Json::as_str) == Some(self.user_id.as_str())
+                });
+            println!("Invited to {}", room_id);
+            actions.push(Action::Join(room_id.clone()));
+            if let Some(invite) = invite {
+                let is_direct = invite
+                    .get("content")
+                    .and_then(|content| content.get("is_direct"))
+                    .and_then(Json::as_bool)
+                    .unwrap_or(false);
+                if let (true, Some(sender)) = (is_direct, invite.get("sender").and_then(Json::as_str)) {
+                    actions.extend(self.remember_direct(sender, room_id));
+                }
+            }
+        }
+
+        for (room_id, room) in entries(rooms.get("join")) {
+            if catching_up {
+                continue;
+            }
+            let events = room.get("timeline").and_then(|timeline| timeline.get("events"));
+            for event in events.and_then(Json::as_array).unwrap_or_default() {
+                if let Some((sender, body)) = self.text_message(event) {
+                    actions.extend(self.handle_message(room_id, sender, body, game));
+                }
+            }
+        }
+
+        for (room_id, _) in entries(rooms.get("leave")) {
+            let mut direct_changed = false;
+            for rooms in self.direct.values_mut() {
+                let before = rooms.len();
+                rooms.retain(|room| room != room_id);
+                direct_changed |= rooms.len() != before;
+            }
+            self.direct.retain(|_, rooms| !rooms.is_empty());
+            if direct_changed {
+                actions.push(self.direct_data());
+            }
+            if self.hosted.remove(room_id).is_some() {
+                actions.push(self.hosted_data());
+            }
+        }
+
+        actions
+    }
+
+    /// Picks up rooms saved in account data, by this bot before a restart
+    /// or, for `m.direct`, by another client on the account.
+    fn load_account_data(&mut self, event: &Json) {
+        let content = event.get("content");
+        match event.get("type").and_then(Json::as_str) {
+            Some(DIRECT_EVENT) => {
+                self.direct = entries(content)
+                    .iter()
+                    .map(|(user, rooms)| {
+                        let rooms = rooms.as_array().unwrap_or_default();
+                        (user.clone(), rooms.iter().filter_map(Json::as_str).map(String::from).collect())
+                    })
+                    .collect();
+            }
+            Some(HOSTED_EVENT) => {
+                self.hosted = entries(content)
+                    .iter()
+                    .filter_map(|(room, game_id)| Some((room.clone(), game_id.as_str()?.to_string())))
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+
+    fn is_direct(&self, room: &str) -> bool {
+        self.direct.values().any(|rooms| rooms.iter().any(|r| r == room))
+    }
+
+    /// The room to send a player news in, the newest one they have.
+    fn direct_room(&self, username: &str) -> Option<&String> {
+        self.direct.get(username)?.last()
+    }
+
+    /// Makes `room` the player's newest direct message room, saving the
+    /// change if there is one.
+    fn remember_direct(&mut self, username: &str, room: &str) -> Option<Action> {
+        let rooms = self.direct.entry(username.into()).or_default();
+        if rooms.last().map(String::as_str) == Some(room) {
+            return None;
+        }
+        rooms.retain(|r| r != room);
+        rooms.push(room.into());
+        Some(self.direct_data())
+    }
+
+    fn host(&mut self, room: &str, game_id: &str) -> Action {
+        self.hosted.insert(room.into(), game_id.into());
+        self.hosted_data()
+    }
+
+    fn direct_data(&self) -> Action {
+        let content = self
+            .direct
+            .iter()
+            .map(|(user, rooms)| (user.as_str(), rooms.iter().map(String::as_str).collect::<Vec<_>>().into()))
+            .collect();
+        Action::SetAccountData {
+            kind: DIRECT_EVENT.into(),
+            content: Json::object(content),
+        }
+    }
+
+    fn hosted_data(&self) -> Action {
+        let content = self.hosted.iter().map(|(room, game_id)| (room.as_str(), game_id.as_str().into())).collect();
+        Action::SetAccountData {
+            kind: HOSTED_EVENT.into(),
+            content: Json::object(content),
+        }
+    }
+
+    /// The sender and body of a text message from someone else.
+    fn text_message<'a>(&self, event: &'a Json) -> Option<(&'a str, &'a str)> {
+        let sender = event.get("sender")?.as_str()?;
+        let content = event.get("content")?;
+        let is_text = event.get("type")?.as_str()? == "m.room.message" && content.get("msgtype")?.as_str()? == "m.text";
+        if !is_text || sender == self.user_id {
+            return None;
+        }
+        Some((sender, content.get("body")?.as_str()?))
+    }
+
+    fn handle_message(&mut self, room: &str, sender: &str, body: &str, game: &Arc<Mutex<WordGame>>) -> Vec<Action> {
+        let body = body.trim();
+        let direct = self.is_direct(room);
+        let mut actions = vec![];
+        if direct {
+            actions.extend(self.remember_direct(sender, room));
+        }
+        let mut word_game = game.lock().unwrap();
+        let result = if direct {
+            self.handle_direct(room, sender, body, &mut word_game)
+        } else {
+            self.handle_room(room, sender, body, &mut word_game)
+        };
+        actions.extend(result.unwrap_or_else(|e| {
+            println!("Error handling a message from {} in {}: {:?}", sender, room, e);
+            vec![Action::send(room, &e.user_message(word_game.language_for(sender)))]
+        }));
+        actions
+    }
+
+    fn handle_room(&mut self, room: &str, username: &str, body: &str, word_game: &mut WordGame) -> AppResult<Vec<Action>> {
+        let language = self.room_language(room, word_game);
+        let text = |key: &str, args: &[(&str, &dyn Display)]| messages::text(language, key, args);
+        let name = display_name(username);
+        let running = match self.hosted.get(room) {
+            Some(game_id) => Some(word_game.get_game(game_id)?).filter(|game| !game.is_complete()),
+            None => None,
+        };
+
+        match command(body).as_deref() {
+            Some("start") => {
+                if running.is_some() {
+                    return Ok(vec![Action::send(room, &text("matrix.game_in_progress", &[]))]);
+                }
+                let game = match commands::start(word_game, username, &name, body)? {
+                    Ok(game) => game,
+                    Err(refusal) => return Ok(vec![Action::send(room, &refusal)]),
+                };
+                let locale = game.locale();
+                let saved = self.host(room, &game.id);
+                let reply = messages::text(locale, "matrix.started", &[("name", &name), ("language", &locale)]);
+                Ok(vec![saved, Action::send(room, &reply)])
+            }
+            Some("join") => {
+                let game = match running {
+                    Some(game) => game,
+                    None => return Ok(vec![Action::send(room, &text("matrix.no_game", &[]))]),
+                };
+                if let Err(refusal) = commands::join_hosted(word_game, username, &name, body, &game)? {
+                    return Ok(vec![Action::send(room, &refusal)]);
+                }
+                let partner = game.player_1_username().or_else(|| game.player_2_username()).unwrap_or_default();
+                let partner = self.name_for(word_game, partner);
+                Ok(vec![Action::send(room, &text("matrix.playing", &[("player_1", &partner), ("player_2", &name)]))])
+            }
+            Some("board") => match self.hosted.get(room) {
+                Some(game_id) => {
+                    let game = word_game.get_game(game_id)?;
+                    Ok(vec![Action::send_board(room, None, &self.board_for(word_game, &game))])
+                }
+                None => Ok(vec![Action::send(room, &text("matrix.no_game", &[]))]),
+            },
+            Some("help") => Ok(vec![Action::send(room, &list_room_commands(language))]),
+            _ => Ok(vec![]),
+        }
+    }
+
+    fn handle_direct(&mut self, room: &str, username: &str, body: &str, word_game: &mut WordGame) -> AppResult<Vec<Action>> {
+        let language = word_game.language_for(username);
+        let text = |key: &str, args: &[(&str, &dyn Display)]| messages::text(language, key, args);
+        let reply = |text: &str| Ok(vec![Action::send(room, text)]);
+
+        match command(body).as_deref() {
+            Some("help") | Some("") => reply(&list_commands(language)),
+            Some("status") => reply(&commands::status(word_game, username, &display_name(username))),
+            Some("language") => reply(&commands::change_language(word_game, username, &display_name(username), body)),
+            Some("start") => match commands::start(word_game, username, &display_name(username), body)? {
+                Ok(game) => reply(&text("adhoc.created", &[("code", &game.id)])),
+                Err(refusal) => reply(&refusal),
+            },
+            Some("join") => self.join_by_code(room, username, body, word_game),
+            Some("stats") => reply(&word_game.statistics(username)?.text(language)),
+            Some("board") => match word_game.current_game_for(username)? {
+                Some(game) => Ok(vec![Action::send_board(room, None, &self.board_for(word_game, &game))]),
+                None => reply(&text("error.no_current_game", &[])),
+            },
+            Some("hint") => reply(&commands::hint(word_game, username)?),
+            Some(_) => reply(&format!("{}\n{}", text("adhoc.unknown_command", &[]), list_commands(language))),
+            None => self.guess(room, username, body, word_game),
+        }
+    }
+
+    fn join_by_code(&mut self, room: &str, username: &str, body: &str, word_game: &mut WordGame) -> AppResult<Vec<Action>> {
+        let game_id = body.split_whitespace().nth(1);
+        let game = match commands::join_by_code(word_game, username, &display_name(username), game_id)? {
+            Ok(game) => game,
+            Err(refusal) => return Ok(vec![Action::send(room, &refusal)]),
+        };
+
+        let joined = messages::text(word_game.language_for(username), "adhoc.joined", &[]);
+        let mut actions = vec![Action::send(room, &joined)];
+        let partner = game.player_1_username().or_else(|| game.player_2_username());
+        if let Some(host) = self.host_of(&game.id) {
+            let partner = self.name_for(word_game, partner.unwrap_or_default());
+            let name = display_name(username);
+            let announcement = messages::text(game.locale(), "matrix.playing", &[("player_1", &partner), ("player_2", &name)]);
+            actions.push(Action::send(&host, &announcement));
+        } else if let Some((partner, partner_room)) = partner.and_then(|p| Some((p, self.direct_room(p)?))) {
+            let name = self.name_for(word_game, username);
+            let news = messages::text(word_game.language_for(partner), "matrix.partner_joined", &[("name", &name)]);
+            actions.push(Action::send(partner_room, &news));
+        }
+        Ok(actions)
+    }
+
+    fn guess(&mut self, room: &str, username: &str, body: &str, word_game: &mut WordGame) -> AppResult<Vec<Action>> {
+        let language = word_game.language_for(username);
+        let before = match word_game.current_game_for(username)? {
+            Some(game) if !game.is_complete() => game,
+            _ => return Ok(vec![Action::send(room, &messages::text(language, "error.no_current_game", &[]))]),
+        };
+        let (reply, round, after) = match commands::guess(word_game, username, &before, body, None)? {
+            Guessed::Refused(reply) | Guessed::Waiting(reply) => return Ok(vec![Action::send(room, &reply)]),
+            Guessed::NeedPartner(game) => {
+                let reply = messages::text(language, "matrix.need_partner", &[("code", &game.id)]);
+                return Ok(vec![Action::send(room, &reply)]);
+            }
+            Guessed::Revealed { reply, round, game } => (reply, round, game),
+        };
+
+        let mut actions = vec![Action::send(room, &reply)];
+        let board = self.board_for(word_game, &after);
+        let (reveal, outcome) = commands::reveal(&after, round, &board);
+        for target in self.audience(&after) {
+            actions.push(Action::send(&target, &reveal));
+            if after.is_complete() {
+                actions.push(Action::send_board(&target, Some(&outcome), &board));
+            } else {
+                actions.push(Action::send(&target, &outcome));
+            }
+        }
+        Ok(actions)
+    }
+
+    /// Where news of a game goes: the room hosting it, or else the direct
+    /// messages of its players.
+    fn audience(&self, game: &Game) -> Vec<String> {
+        if let Some(host) = self.host_of(&game.id) {
+            return vec![host];
+        }
+        [game.player_1_username(), game.player_2_username()]
+            .iter()
+            .flatten()
+            .filter_map(|player| self.direct_room(player).cloned())
+            .collect()
+    }
+
+    fn host_of(&self, game_id: &str) -> Option<String> {
+        self.hosted.iter().find(|(_, hosted)| hosted.as_str() == game_id).map(|(room, _)| room.clone())
+    }
+
+    /// Messages in a room are in the language of the game it hosts.
+    fn room_language(&self, room: &str, word_game: &WordGame) -> Locale {
+        self.hosted
+            .get(room)
+            .and_then(|game_id| word_game.get_game(game_id).ok())
+            .map(|game| game.locale())
+            .unwrap_or_default()
+    }
+
+    fn name_for(&self, word_game: &WordGame, username: &str) -> String {
+        word_game
+            .get_player(username)
+            .map_or_else(|_| display_name(username), |player| player.to_string())
+    }
+
+    fn board_for(&self, word_game: &WordGame, game: &Game) -> Board {
+        let name = |username: Option<&str>| username.map_or("???".into(), |u| self.name_for(word_game, u));
+        let (player_1, player_2) = (name(game.player_1_username()), name(game.player_2_username()));
+        word_game.board(game).with_names(&player_1, &player_2)
+    }
+}
+
+/// The command a message starts with, without its `!` or `/`, or `None`
+/// for anything else, like a guess. An empty message counts as asking for
+/// help.
+fn command(body: &str) -> Option<String> {
+    let first = body.split_whitespace().next().unwrap_or_default();
+    if first.is_empty() {
+        return Some(String::new());
+    }
+    first
+        .strip_prefix('!')
+        .or_else(|| first.strip_prefix('/'))
+        .map(str::to_lowercase)
+}
+
+/// The localpart of a user id, so `@alice:example.org` plays as `alice`.
+fn display_name(user_id: &str) -> String {
+    let localpart = user_id.trim_start_matches('@');
+    localpart.split(':').next().unwrap_or(localpart).to_string()
+}
+
+/// The fields of a JSON object, or none for anything else.
+fn entries(json: Option<&Json>) -> &[(String, Json)] {
+    json.and_then(Json::as_object).unwrap_or_default()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn list_commands(language: Locale) -> String {
+    let commands_list = [
+        ("start [language]", "command.new_game"),
+        ("join <code>", "command.join_code"),
+        ("board", "command.board"),
+        ("hint", "command.hint"),
+        ("stats", "command.stats"),
+        ("status", "command.status"),
+        ("language <code>", "command.language"),
+        ("help", "command.help"),
+    ];
+    let commands = commands::command_list(language, "xmpp.commands", '!', &commands_list);
+    format!("{}\n{}", commands, messages::text(language, "command.guess", &[]))
+}
+
+fn list_room_commands(language: Locale) -> String {
+    let commands_list = [
+        ("start [language]", "command.start"),
+        ("join [language]", "command.join"),
+        ("board", "command.board"),
+        ("help", "command.help"),
+    ];
+    commands::command_list(language, "room.commands", '!', &commands_list)
+}
@@ -0,0 +1,251 @@
+use std::time::Duration;
+
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+use tokio_native_tls::{native_tls, TlsConnector};
+
+use crate::{json::Json, AppResult, ApplicationError};
+
+const CLIENT_API: &str = "/_matrix/client/v3";
+/// How long a request may take on top of any long-polling it asks for.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Largest response body we read; initial syncs of busy accounts are big.
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+/// How often a rate-limited request is retried before giving up.
+const MAX_RETRIES: usize = 3;
+
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+/// Talks to a homeserver through the parts of the Matrix client-server API
+/// the bot needs: sync, join, send and account data.
+///
+/// Every request gets a connection of its own, which keeps this a plain
+/// HTTP/1.1 exchange; the bot makes few enough requests that it doesn't
+/// matter.
+pub struct Homeserver {
+    tls: Option<TlsConnector>,
+    host: String,
+    port: u16,
+    base_path: String,
+    token: String,
+}
+
+impl Homeserver {
+    /// A client for the homeserver at `url`, like `https://matrix.example.org`,
+    /// acting with the access token `token`.
+    pub fn new(url: &str, token: &str) -> AppResult<Self> {
+        let (tls, rest) = if let Some(rest) = url.strip_prefix("https://") {
+            let connector = native_tls::TlsConnector::new()
+                .map_err(|e| ApplicationError::config("Could not set up TLS").caused_by(e))?;
+            (Some(TlsConnector::from(connector)), rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            (None, rest)
+        } else {
+            return Err(ApplicationError::config(&format!("Expected an http or https URL, got {}", url)));
+        };
+        let (authority, base_path) = match rest.find('/') {
+            Some(slash) => (&rest[..slash], rest[slash..].trim_end_matches('/')),
+            None => (rest, ""),
+        };
+        let default_port = if tls.is_some() { 443 } else { 80 };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port
+                    .parse()
+                    .map_err(|_| ApplicationError::config(&format!("Invalid port in {}", url)))?;
+                (host, port)
+            }
+            None => (authority, default_port),
+        };
+        if host.is_empty() {
+            return Err(ApplicationError::config(&format!("Missing host in {}", url)));
+        }
+
+        Ok(Self {
+            tls,
+            host: host.into(),
+            port,
+            base_path: base_path.into(),
+            token: token.into(),
+        })
+    }
+
+    /// Waits up to `timeout` for anything new since the `since` token, or
+    /// returns the account's current state when there is no token yet.
+    pub async fn sync(&self, since: Option<&str>, timeout: Duration) -> AppResult<Json> {
+        let mut path = format!("/sync?timeout={}", timeout.as_millis());
+        if let Some(since) = since {
+            path.push_str(&format!("&since={}", percent_encode(since)));
+        }
+        self.request("GET", &path, None, timeout).await
+    }
+
+    pub async fn join(&self, room_id: &str) -> AppResult<()> {
+        let path = format!("/join/{}", percent_encode(room_id));
+        self.request("POST", &path, Some(&Json::object(vec![])), Duration::ZERO)
+            .await
+            .map(|_| ())
+    }
+
+    /// Sends a message event with `content` to the room. `transaction_id`
+    /// makes retries safe: the homeserver sends each id only once.
+    pub async fn send_message(&self, room_id: &str, transaction_id: &str, content: &Json) -> AppResult<()> {
+        let path = format!(
+            "/rooms/{}/send/m.room.message/{}",
+            percent_encode(room_id),
+            percent_encode(transaction_id)
+        );
+        self.request("PUT", &path, Some(content), Duration::ZERO).await.map(|_| ())
+    }
+
+    /// Replaces `user_id`'s account data of type `kind` with `content`.
+    pub async fn set_account_data(&self, user_id: &str, kind: &str, content: &Json) -> AppResult<()> {
+        let path = format!("/user/{}/account_data/{}", percent_encode(user_id), percent_encode(kind));
+        self.request("PUT", &path, Some(content), Duration::ZERO).await.map(|_| ())
+    }
+
+    /// Makes a request, waiting and retrying when the homeserver says it is
+    /// rate limited.
+    async fn request(&self, method: &str, path: &str, body: Option<&Json>, wait: Duration) -> AppResult<Json> {
+        let mut retries = 0;
+        loop {
+            let exchange = self.exchange(method, path, body);
+            let (status, response) = tokio::time::timeout(wait + REQUEST_TIMEOUT, exchange)
+                .await
+                .map_err(|_| ApplicationError::transport(&format!("{} {} timed out", method, path)))??;
+            if (200..300).contains(&status) {
+                return Ok(response);
+            }
+
+            let retry_after = response.get("retry_after_ms").and_then(Json::as_u64);
+            if let (429, Some(retry_after), true) = (status, retry_after, retries < MAX_RETRIES) {
+                retries += 1;
+                tokio::time::sleep(Duration::from_millis(retry_after)).await;
+                continue;
+            }
+            let errcode = response.get("errcode").and_then(Json::as_str).unwrap_or("unknown");
+            let error = response.get("error").and_then(Json::as_str).unwrap_or_default();
+            return Err(ApplicationError::transport(&format!(
+                "The homeserver refused {} {}: {} {} {}",
+                method, path, status, errcode, error
+            )));
+        }
+    }
+
+    async fn exchange(&self, method: &str, path: &str, body: Option<&Json>) -> AppResult<(u16, Json)> {
+        let stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+        let mut connection: Box<dyn Connection> = match &self.tls {
+            Some(tls) => Box::new(
+                tls.connect(&self.host, stream)
+                    .await
+                    .map_err(|e| ApplicationError::transport("TLS handshake failed").caused_by(e))?,
+            ),
+            None => Box::new(stream),
+        };
+
+        let body = body.map(|body| body.to_string()).unwrap_or_default();
+        let mut head = format!(
+            "{} {}{}{} HTTP/1.1\r\nHost: {}\r\nAuthorization: Bearer {}\r\nAccept: application/json\r\nConnection: close\r\n",
+            method, self.base_path, CLIENT_API, path, self.host, self.token
+        );
+        if method != "GET" {
+            head.push_str(&format!("Content-Type: application/json\r\nContent-Length: {}\r\n", body.len()));
+        }
+        head.push_str("\r\n");
+        connection.write_all(head.as_bytes()).await?;
+        connection.write_all(body.as_bytes()).await?;
+        connection.flush().await?;
+
+        let mut reader = BufReader::new(connection);
+        let (status, body) = read_response(&mut reader).await?;
+        let text = String::from_utf8_lossy(&body);
+        let json = if text.trim().is_empty() {
+            Json::object(vec![])
+        } else {
+            text.parse::<Json>()
+                .map_err(|e| ApplicationError::transport("The homeserver sent invalid JSON").caused_by(e))?
+        };
+        Ok((status, json))
+    }
+}
+
+/// Reads a response's status and body, whether the body comes with a
+/// length, in chunks or simply until the connection closes.
+async fn read_response<R: AsyncBufRead + Unpin>(reader: &mut R) -> AppResult<(u16, Vec<u8>)> {
+    let invalid = || ApplicationError::transport("The homeserver sent an invalid HTTP response");
+    let status_line = read_line(reader).await?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(invalid)?;
+
+    let mut length = None;
+    let mut chunked = false;
+    loop {
+        let line = read_line(reader).await?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').ok_or_else(invalid)?;
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("Content-Length") {
+            length = Some(value.parse::<usize>().map_err(|_| invalid())?);
+        } else if name.eq_ignore_ascii_case("Transfer-Encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        }
+    }
+
+    let mut body = vec![];
+    if chunked {
+        loop {
+            let size = read_line(reader).await?;
+            let size = size.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size, 16).map_err(|_| invalid())?;
+            if size == 0 {
+                break;
+            }
+            if body.len() + size > MAX_BODY_BYTES {
+                return Err(ApplicationError::transport("The homeserver's response is too large"));
+            }
+            let start = body.len();
+            body.resize(start + size, 0);
+            reader.read_exact(&mut body[start..]).await?;
+            read_line(reader).await?;
+        }
+    } else if let Some(length) = length {
+        if length > MAX_BODY_BYTES {
+            return Err(ApplicationError::transport("The homeserver's response is too large"));
+        }
+        body.resize(length, 0);
+        reader.read_exact(&mut body).await?;
+    } else {
+        reader.take(MAX_BODY_BYTES as u64).read_to_end(&mut body).await?;
+    }
+    Ok((status, body))
+}
+
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> AppResult<String> {
+    let mut line = vec![];
+    (&mut *reader).take(8 * 1024).read_until(b'\n', &mut line).await?;
+    if !line.ends_with(b"\n") {
+        return Err(ApplicationError::transport("The homeserver closed the connection early"));
+    }
+    Ok(String::from_utf8_lossy(&line).trim_end().to_string())
+}
+
+/// Escapes everything but unreserved characters, for ids in paths and
+/// queries. Room ids start with `!` and aliases with `#`, which mustn't
+/// reach the homeserver as they are.
+pub fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            byte => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,133 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use crate::{game::WordGame, json::Json, time::duration::FormattedDuration, AppResult};
+
+use self::{
+    bot::{Action, MatrixBot},
+    client::Homeserver,
+};
+
+use super::{backoff::Backoff, required_env, shutdown_signal, Runner};
+
+pub mod bot;
+pub mod client;
+
+const SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Plays the game on Matrix through the client-server API.
+///
+/// The runner long-polls `/sync` and hands what comes back to `MatrixBot`,
+/// then carries out the joins and messages it asks for. Messages that
+/// arrived before the bot started are skipped. Failed syncs are retried
+/// with backoff.
+pub struct MatrixRunner {
+    homeserver: Homeserver,
+    bot: MatrixBot,
+    sync_timeout: Duration,
+}
+
+impl MatrixRunner {
+    pub fn new() -> AppResult<Self> {
+        let url = required_env("WORD_GAME_MATRIX_HOMESERVER")?;
+        let user_id = required_env("WORD_GAME_MATRIX_USER_ID")?;
+        let token = required_env("WORD_GAME_MATRIX_TOKEN")?;
+        Self::with_homeserver(&url, &user_id, &token)
+    }
+
+    /// A bot logged in as `user_id` with the access token `token`.
+    pub fn with_homeserver(url: &str, user_id: &str, token: &str) -> AppResult<Self> {
+        Ok(Self {
+            homeserver: Homeserver::new(url, token)?,
+            bot: MatrixBot::new(user_id),
+            sync_timeout: SYNC_TIMEOUT,
+        })
+    }
+
+    /// Long-polls for `sync_timeout` at a time instead of 30 seconds.
+    pub fn with_sync_timeout(mut self, sync_timeout: Duration) -> Self {
+        self.sync_timeout = sync_timeout;
+        self
+    }
+
+    /// Runs the bot until `shutdown` resolves.
+    pub async fn run_until<F: Future<Output = ()>>(&mut self, game: Arc<Mutex<WordGame>>, shutdown: F) -> AppResult<()> {
+        let mut backoff = Backoff::new();
+        let mut since: Option<String> = None;
+        tokio::pin!(shutdown);
+
+        loop {
+            let timeout = if since.is_some() { self.sync_timeout } else { Duration::ZERO };
+            let synced = tokio::select! {
+                _ = &mut shutdown => {
+                    println!("Shutting down...");
+                    break;
+                }
+                synced = self.homeserver.sync(since.as_deref(), timeout) => synced,
+            };
+
+            match synced {
+                Ok(sync) => {
+                    backoff.reset();
+                    let actions = self.bot.handle_sync(&sync, &game, since.is_none());
+                    for action in actions {
+                        self.perform(action, &game).await;
+                    }
+                    if let Some(next_batch) = sync.get("next_batch").and_then(Json::as_str) {
+                        since = Some(next_batch.into());
+                    }
+                }
+                Err(e) => {
+                    println!("Could not sync: {}", e);
+                    let delay = backoff.next_delay();
+                    println!("Retrying in {}...", FormattedDuration::from(delay));
+                    tokio::select! {
+                        _ = &mut shutdown => {
+                            println!("Shutting down...");
+                            break;
+                        }
+                        _ = tokio::time::sleep(delay) => {}
+                    }
+                }
+            }
+        }
+
+        game.lock().unwrap().flush()?;
+
+        Ok(())
+    }
+
+    async fn perform(&self, action: Action, game: &Arc<Mutex<WordGame>>) {
+        let result = match &action {
+            Action::Join(room) => self.homeserver.join(room).await,
+            Action::Send { room, text, html } => {
+                let mut content = vec![("msgtype", "m.notice".into()), ("body", text.as_str().into())];
+                if let Some(html) = html {
+                    content.push(("format", "org.matrix.custom.html".into()));
+                    content.push(("formatted_body", html.as_str().into()));
+                }
+                let transaction_id = game.lock().unwrap().next_id();
+                self.homeserver
+                    .send_message(room, &transaction_id, &Json::object(content))
+                    .await
+            }
+            Action::SetAccountData { kind, content } => {
+                self.homeserver.set_account_data(self.bot.user_id(), kind, content).await
+            }
+        };
+        if let Err(e) = result {
+            println!("Could not carry out {:?}: {}", action, e);
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Runner for MatrixRunner {
+    async fn run(&mut self, game: Arc<Mutex<WordGame>>) -> AppResult<()> {
+        self.run_until(game, shutdown_signal()).await
+    }
+}
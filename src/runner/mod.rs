@@ -1,44 +1,121 @@
-use std::{fmt, sync::{Mutex, Arc}};
+use std::{net::SocketAddr, sync::{Mutex, Arc}};
 use async_trait::async_trait;
 
-use crate::{game::WordGame, AppResult};
+use crate::{game::WordGame, AppResult, ApplicationError};
 
-use self::{xmpp::XmppRunner, console::ConsoleRunner};
+use self::{xmpp::XmppRunner, console::ConsoleRunner, tui::TuiRunner, http::HttpRunner, websocket::WebSocketRunner, telnet::TelnetRunner, irc::IrcRunner, matrix::MatrixRunner};
 
 pub mod xmpp;
 pub mod console;
+pub mod tui;
+pub mod http;
+pub mod websocket;
+pub mod telnet;
+pub mod irc;
+pub mod matrix;
+pub mod backoff;
+pub mod hub;
+pub mod commands;
 
 #[async_trait(?Send)]
 pub trait Runner {
     async fn run(&mut self, game: Arc<Mutex<WordGame>>) -> AppResult<()>;
 }
 
-#[derive(Debug, Clone)]
-pub struct RunnerError {
-    detail: String,
-}
+/// Resolves once the process is asked to stop with SIGINT or SIGTERM.
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
 
-impl fmt::Display for RunnerError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Runner Error: {}", self.detail)
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(e) => {
+                println!("Could not listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
     }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Reads a setting the runner can't start without from the environment.
+pub(crate) fn required_env(name: &str) -> AppResult<String> {
+    std::env::var(name).map_err(|e| ApplicationError::config(&format!("Expected {} to be set", name)).caused_by(e))
+}
+
+/// Reads the address to listen on from the environment, or `default`.
+pub(crate) fn address_env(name: &str, default: &str) -> AppResult<SocketAddr> {
+    let address = std::env::var(name).unwrap_or_else(|_| default.into());
+    address.parse().map_err(|e| {
+        ApplicationError::config(&format!("Expected {} to be an address like {}", name, default)).caused_by(e)
+    })
 }
 
 pub enum RunnerType {
     Console,
+    Tui,
     XMPP,
+    Http,
+    WebSocket,
+    Telnet,
+    Irc,
+    Matrix,
+}
+
+impl RunnerType {
+    pub const NAMES: [&'static str; 8] = ["console", "tui", "xmpp", "http", "websocket", "telnet", "irc", "matrix"];
+}
+
+impl std::str::FromStr for RunnerType {
+    type Err = ApplicationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "console" => Ok(RunnerType::Console),
+            "tui" => Ok(RunnerType::Tui),
+            "xmpp" => Ok(RunnerType::XMPP),
+            "http" => Ok(RunnerType::Http),
+            "websocket" => Ok(RunnerType::WebSocket),
+            "telnet" => Ok(RunnerType::Telnet),
+            "irc" => Ok(RunnerType::Irc),
+            "matrix" => Ok(RunnerType::Matrix),
+            _ => Err(ApplicationError::config(&format!(
+                "Unknown runner \"{}\", expected one of {}",
+                s,
+                RunnerType::NAMES.join(", ")
+            ))),
+        }
+    }
 }
 
 pub struct RunnerFactory;
 
 impl RunnerFactory {
-    pub fn create(runner_type: RunnerType) -> Box<dyn Runner>
+    /// Builds the runner from its environment variables, failing with a
+    /// config error when one is missing or malformed.
+    pub fn create(runner_type: RunnerType) -> AppResult<Box<dyn Runner>>
     {
-        match runner_type {
+        Ok(match runner_type {
             RunnerType::XMPP => {
-                Box::new(XmppRunner::new())
+                Box::new(XmppRunner::new()?)
             },
             RunnerType::Console => Box::new(ConsoleRunner::new()),
-        }
+            RunnerType::Tui => Box::new(TuiRunner::new()),
+            RunnerType::Http => Box::new(HttpRunner::new()?),
+            RunnerType::WebSocket => Box::new(WebSocketRunner::new()?),
+            RunnerType::Telnet => Box::new(TelnetRunner::new()?),
+            RunnerType::Irc => Box::new(IrcRunner::new()?),
+            RunnerType::Matrix => Box::new(MatrixRunner::new()?),
+        })
     }
 }
\ No newline at end of file
@@ -0,0 +1,172 @@
+use std::{
+    future::Future,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::unbounded_channel,
+};
+
+use crate::{game::WordGame, AppResult, ApplicationError};
+
+use self::{
+    protocol::{to_network, LineDecoder, ECHO_OFF, ECHO_ON},
+    session::Session,
+};
+
+use super::{
+    hub::{Hub, Recipient},
+    address_env, shutdown_signal, Runner,
+};
+
+pub mod protocol;
+pub mod session;
+
+const DEFAULT_ADDRESS: &str = "0.0.0.0:2323";
+
+/// Lets players `telnet` or `nc` into the game from their own terminals.
+///
+/// Every connection is a `Session` and they all share one `WordGame`.
+/// While a player types a guess the server claims the telnet echo option,
+/// so the guess doesn't show on their screen.
+pub struct TelnetRunner {
+    address: SocketAddr,
+    listener: Option<TcpListener>,
+}
+
+impl TelnetRunner {
+    pub fn new() -> AppResult<Self> {
+        Ok(Self::with_address(address_env("WORD_GAME_TELNET_ADDR", DEFAULT_ADDRESS)?))
+    }
+
+    pub fn with_address(address: SocketAddr) -> Self {
+        Self { address, listener: None }
+    }
+
+    /// Starts listening, returning the address in use, which tells which
+    /// port was picked for port 0. `run_until` binds by itself otherwise.
+    pub async fn bind(&mut self) -> AppResult<SocketAddr> {
+        let listener = TcpListener::bind(self.address).await?;
+        let address = listener.local_addr()?;
+        self.listener = Some(listener);
+        Ok(address)
+    }
+
+    /// Serves players until `shutdown` resolves.
+    pub async fn run_until<F: Future<Output = ()>>(&mut self, game: Arc<Mutex<WordGame>>, shutdown: F) -> AppResult<()> {
+        if self.listener.is_none() {
+            self.bind().await?;
+        }
+        let listener = self
+            .listener
+            .take()
+            .ok_or_else(|| ApplicationError::transport("Not listening"))?;
+        println!("Serving the word game on telnet://{}", listener.local_addr()?);
+        let hub = Arc::new(Mutex::new(Hub::new()));
+        tokio::pin!(shutdown);
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    println!("Shutting down...");
+                    break;
+                }
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        tokio::spawn(handle_connection(stream, game.clone(), hub.clone()));
+                    }
+                    Err(e) => println!("Could not accept a connection: {}", e),
+                }
+            }
+        }
+
+        game.lock().unwrap().flush()?;
+
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Runner for TelnetRunner {
+    async fn run(&mut self, game: Arc<Mutex<WordGame>>) -> AppResult<()> {
+        self.run_until(game, shutdown_signal()).await
+    }
+}
+
+async fn handle_connection(stream: TcpStream, game: Arc<Mutex<WordGame>>, hub: Arc<Mutex<Hub<String>>>) {
+    let (mut reader, mut writer) = stream.into_split();
+    let (events, mut pushed) = unbounded_channel::<String>();
+    let mut decoder = LineDecoder::new();
+    let mut session = Session::new();
+    let mut subscription: Option<(String, u64)> = None;
+    let mut hiding_input = false;
+    let mut buffer = [0; 1024];
+
+    let mut output = to_network(&Session::greeting());
+    loop {
+        if !output.is_empty() {
+            let hide = session.hides_input(&game.lock().unwrap());
+            if hide != hiding_input {
+                output.extend_from_slice(if hide { &ECHO_OFF } else { &ECHO_ON });
+                hiding_input = hide;
+            }
+            if writer.write_all(&output).await.is_err() {
+                break;
+            }
+            output.clear();
+        }
+        if session.is_done() {
+            break;
+        }
+
+        // Reading into a buffer can be cancelled safely, unlike reading a
+        // whole line, so both sources can share one select.
+        tokio::select! {
+            read = reader.read(&mut buffer) => {
+                let read = match read {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => read,
+                };
+                for line in decoder.decode(&buffer[..read]) {
+                    // Nothing the player typed was shown while hidden, so
+                    // at least move them to a fresh line.
+                    if hiding_input {
+                        output.extend_from_slice(b"\r\n");
+                    }
+                    // The hub stays locked until a new name is claimed, so
+                    // two terminals can't pick the same one at once.
+                    let mut word_game = game.lock().unwrap();
+                    let mut hub = hub.lock().unwrap();
+                    let deliveries = session.handle_line(&line, &mut word_game, |username| hub.is_connected(username));
+                    drop(word_game);
+                    if subscription.is_none() {
+                        subscription = session
+                            .player()
+                            .map(|player| (player.to_string(), hub.subscribe(player, events.clone())));
+                    }
+                    for (recipient, text) in deliveries {
+                        match recipient {
+                            Recipient::Connection => output.extend(to_network(&text)),
+                            Recipient::Player(player) => hub.send(&player, &text),
+                        }
+                    }
+                    if session.is_done() {
+                        break;
+                    }
+                }
+            }
+            Some(text) = pushed.recv() => output.extend(to_network(&text)),
+        }
+    }
+
+    if hiding_input {
+        let _ = writer.write_all(&ECHO_ON).await;
+    }
+    if let Some((player, id)) = subscription {
+        hub.lock().unwrap().unsubscribe(&player, id);
+    }
+}
@@ -0,0 +1,128 @@
+/// Interpret As Command: starts every telnet command (RFC 854).
+pub const IAC: u8 = 255;
+pub const WILL: u8 = 251;
+pub const WONT: u8 = 252;
+pub const DO: u8 = 253;
+pub const DONT: u8 = 254;
+/// Subnegotiation begin and end.
+pub const SB: u8 = 250;
+pub const SE: u8 = 240;
+/// The echo option (RFC 857).
+pub const ECHO: u8 = 1;
+
+/// Tells the client the server will echo, so it stops echoing what the
+/// player types. The server then doesn't, which hides the input.
+pub const ECHO_OFF: [u8; 3] = [IAC, WILL, ECHO];
+/// Hands echoing back to the client.
+pub const ECHO_ON: [u8; 3] = [IAC, WONT, ECHO];
+
+/// Longest line we keep; anything past it is dropped.
+pub const MAX_LINE_CHARS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Data,
+    /// After a carriage return, where a line feed or NUL that follows is
+    /// part of the same line ending.
+    Return,
+    Command,
+    Option,
+    Subnegotiation,
+    SubnegotiationCommand,
+}
+
+/// Splits the bytes a telnet or netcat client sends into lines, dropping
+/// telnet commands and applying backspaces.
+///
+/// Lines end with CR LF, CR NUL, a lone CR or a lone LF, whichever the
+/// client sends.
+#[derive(Debug)]
+pub struct LineDecoder {
+    state: State,
+    line: Vec<u8>,
+}
+
+impl LineDecoder {
+    pub fn new() -> Self {
+        Self {
+            state: State::Data,
+            line: vec![],
+        }
+    }
+
+    /// Feeds in bytes as they arrive, returning the lines they finish.
+    pub fn decode(&mut self, bytes: &[u8]) -> Vec<String> {
+        let mut lines = vec![];
+        for &byte in bytes {
+            self.state = match (self.state, byte) {
+                (State::Data, IAC) | (State::Return, IAC) => State::Command,
+                (State::Data, b'\r') | (State::Return, b'\r') => {
+                    lines.push(self.take_line());
+                    State::Return
+                }
+                (State::Data, b'\n') => {
+                    lines.push(self.take_line());
+                    State::Data
+                }
+                (State::Return, b'\n') | (State::Return, 0) => State::Data,
+                (State::Data, byte) | (State::Return, byte) => {
+                    self.push(byte);
+                    State::Data
+                }
+                (State::Command, IAC) => {
+                    // An escaped 255, which is never part of valid UTF-8.
+                    State::Data
+                }
+                (State::Command, WILL) | (State::Command, WONT) | (State::Command, DO) | (State::Command, DONT) => {
+                    State::Option
+                }
+                (State::Command, SB) => State::Subnegotiation,
+                (State::Command, _) | (State::Option, _) => State::Data,
+                (State::Subnegotiation, IAC) => State::SubnegotiationCommand,
+                (State::Subnegotiation, _) => State::Subnegotiation,
+                (State::SubnegotiationCommand, SE) => State::Data,
+                (State::SubnegotiationCommand, _) => State::Subnegotiation,
+            };
+        }
+        lines
+    }
+
+    fn push(&mut self, byte: u8) {
+        match byte {
+            // Backspace and delete take back the last character, which may
+            // be several bytes long.
+            0x08 | 0x7F => {
+                while let Some(byte) = self.line.pop() {
+                    if byte & 0xC0 != 0x80 {
+                        break;
+                    }
+                }
+            }
+            0 => {}
+            byte if self.line.len() < MAX_LINE_CHARS * 4 => self.line.push(byte),
+            _ => {}
+        }
+    }
+
+    fn take_line(&mut self) -> String {
+        let line = String::from_utf8_lossy(&self.line).trim().to_string();
+        self.line.clear();
+        line
+    }
+}
+
+impl Default for LineDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Text as telnet wants it, with every line ending in CR LF.
+pub fn to_network(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len() + 2);
+    for line in text.split('\n') {
+        bytes.extend_from_slice(line.trim_end_matches('\r').as_bytes());
+        bytes.extend_from_slice(b"\r\n");
+    }
+    bytes
+}
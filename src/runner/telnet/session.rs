@@ -0,0 +1,376 @@
+use std::fmt::Display;
+
+use crate::{
+    game::{Game, GuessOutcome, WordGame},
+    locale::{messages, Locale},
+    runner::hub::Recipient,
+    AppResult, ApplicationError,
+};
+
+/// Longest name a player may pick.
+const MAX_NAME_CHARS: usize = 32;
+
+pub type Delivery = (Recipient, String);
+
+/// One terminal's conversation with the game.
+///
+/// The player first picks a name, which is their username from then on.
+/// New names get a secret, which the player must type to pick the name
+/// again on a later visit. In the lobby they can `/new`, `/join CODE` or
+/// `/play` to be matched with someone; in a game every line that isn't a
+/// command is a guess.
+#[derive(Debug, Default)]
+pub struct Session {
+    player: Option<String>,
+    /// The name whose secret we asked for.
+    claiming: Option<String>,
+    done: bool,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn greeting() -> String {
+        let locale = Locale::default();
+        format!(
+            "{}\n{}",
+            messages::text(locale, "welcome", &[]),
+            messages::text(locale, "telnet.name_prompt", &[])
+        )
+    }
+
+    /// The player the terminal speaks for, once they have picked a name.
+    pub fn player(&self) -> Option<&str> {
+        self.player.as_deref()
+    }
+
+    /// Whether the player asked to disconnect.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Whether the player is typing a secret or a guess, which their
+    /// terminal shouldn't show.
+    pub fn hides_input(&self, word_game: &WordGame) -> bool {
+        self.claiming.is_some() || matches!(self.running_game(word_game), Some(game) if game.is_full())
+    }
+
+    /// Answers a line the player typed. `is_connected` tells whether a name
+    /// is already in use by another terminal.
+    pub fn handle_line(&mut self, line: &str, word_game: &mut WordGame, is_connected: impl Fn(&str) -> bool) -> Vec<Delivery> {
+        let line = line.trim();
+        if line.is_empty() {
+            return vec![];
+        }
+        let username = match (self.player.clone(), self.claiming.take()) {
+            (Some(username), _) => username,
+            (None, Some(name)) => return self.check_secret(&name, line, word_game, is_connected),
+            (None, None) => return self.pick_name(line, word_game, is_connected),
+        };
+        let reply = |text: String| vec![(Recipient::Connection, text)];
+        let text = |key: &str, args: &[(&str, &dyn Display)]| messages::text(word_game.language_for(&username), key, args);
+
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line, ""),
+        };
+        let result = match command.to_lowercase().as_str() {
+            "/quit" => {
+                self.done = true;
+                return reply(text("telnet.goodbye", &[]));
+            }
+            "/help" => return reply(self.help(word_game)),
+            "/new" => self.new_game(&username, word_game),
+            "/join" if argument.is_empty() => return reply(text("adhoc.need_code", &[])),
+            "/join" => self.join(&username, argument, word_game),
+            "/play" => self.matchmake(&username, word_game),
+            "/leave" => self.leave(&username, word_game),
+            "/board" => match word_game.current_game_for(&username) {
+                Ok(Some(game)) => Ok(reply(board(&game, word_game))),
+                Ok(None) => Ok(reply(text("error.no_current_game", &[]))),
+                Err(e) => Err(e),
+            },
+            "/stats" => word_game
+                .statistics(&username)
+                .map(|stats| reply(stats.text(word_game.language_for(&username)))),
+            "/language" => argument.parse::<Locale>().and_then(|language| {
+                word_game.set_player_language(&username, language)?;
+                Ok(reply(messages::text(language, "language.set", &[("language", &language)])))
+            }),
+            command if command.starts_with('/') => return reply(text("telnet.unknown_command", &[])),
+            _ => self.guess(&username, line, word_game),
+        };
+        result.unwrap_or_else(|e| reply(e.user_message(word_game.language_for(&username))))
+    }
+
+    fn pick_name(&mut self, name: &str, word_game: &mut WordGame, is_connected: impl Fn(&str) -> bool) -> Vec<Delivery> {
+        let locale = Locale::default();
+        let valid = name.chars().count() <= MAX_NAME_CHARS
+            && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return vec![(Recipient::Connection, messages::text(locale, "telnet.bad_name", &[]))];
+        }
+        let username = name.to_lowercase();
+        if is_connected(&username) {
+            return vec![(Recipient::Connection, messages::text(locale, "telnet.name_taken", &[("name", &name)]))];
+        }
+
+        if word_game.has_player(&username) {
+            let text = match word_game.get_player(&username) {
+                Ok(player) if player.secret.is_some() => {
+                    self.claiming = Some(name.into());
+                    messages::text(locale, "telnet.secret_prompt", &[("name", &name)])
+                }
+                Ok(_) => messages::text(locale, "telnet.name_claimed", &[("name", &name)]),
+                Err(e) => e.user_message(locale),
+            };
+            return vec![(Recipient::Connection, text)];
+        }
+
+        let result = word_game
+            .create_player(&username, name)
+            .and_then(|_| word_game.issue_secret(&username));
+        let secret = match result {
+            Ok(secret) => secret,
+            Err(e) => return vec![(Recipient::Connection, e.user_message(locale))],
+        };
+        let note = messages::text(locale, "telnet.secret", &[("secret", &secret), ("name", &name)]);
+        self.welcome(username, Some(note), word_game)
+    }
+
+    /// Lets the player in as `name` if `secret` is theirs, or asks for a
+    /// name again.
+    fn check_secret(&mut self, name: &str, secret: &str, word_game: &WordGame, is_connected: impl Fn(&str) -> bool) -> Vec<Delivery> {
+        let locale = Locale::default();
+        let username = name.to_lowercase();
+        if is_connected(&username) {
+            return vec![(Recipient::Connection, messages::text(locale, "telnet.name_taken", &[("name", &name)]))];
+        }
+        match word_game.authenticate(secret) {
+            Ok(player) if player.username == username => self.welcome(username, None, word_game),
+            _ => {
+                let text = format!(
+                    "{}\n{}",
+                    ApplicationError::Unauthorized.user_message(locale),
+                    messages::text(locale, "telnet.name_prompt", &[])
+                );
+                vec![(Recipient::Connection, text)]
+            }
+        }
+    }
+
+    /// Greets the player who just got their name, followed by `note`.
+    fn welcome(&mut self, username: String, note: Option<String>, word_game: &WordGame) -> Vec<Delivery> {
+        let locale = word_game.language_for(&username);
+        let mut lines = match word_game.get_player(&username) {
+            Ok(player) => vec![messages::text(locale, "telnet.hello", &[("name", &player)])],
+            Err(e) => return vec![(Recipient::Connection, e.user_message(locale))],
+        };
+        lines.extend(note);
+        self.player = Some(username);
+        lines.push(self.help(word_game));
+        vec![(Recipient::Connection, lines.join("\n"))]
+    }
+
+    /// What the player can do from where they are.
+    fn help(&self, word_game: &WordGame) -> String {
+        let username = self.player.as_deref().unwrap_or_default();
+        let locale = word_game.language_for(username);
+        match self.running_game(word_game) {
+            Some(game) if game.is_full() => messages::text(locale, "telnet.game_help", &[]),
+            Some(game) => messages::text(locale, "telnet.waiting_partner", &[("code", &game.id)]),
+            None => messages::text(locale, "telnet.lobby_help", &[]),
+        }
+    }
+
+    /// The player's current game, unless it is over.
+    fn running_game(&self, word_game: &WordGame) -> Option<Game> {
+        let game = word_game.current_game_for(self.player.as_deref()?).ok()??;
+        if game.is_complete() {
+            None
+        } else {
+            Some(game)
+        }
+    }
+
+    /// Refuses to start another game while one is running.
+    fn ensure_free(&self, username: &str, word_game: &WordGame) -> Option<Vec<Delivery>> {
+        self.running_game(word_game).map(|game| {
+            let text = messages::text(word_game.language_for(username), "telnet.already_playing", &[("code", &game.id)]);
+            vec![(Recipient::Connection, text)]
+        })
+    }
+
+    fn new_game(&self, username: &str, word_game: &mut WordGame) -> AppResult<Vec<Delivery>> {
+        if let Some(refusal) = self.ensure_free(username, word_game) {
+            return Ok(refusal);
+        }
+        let player = word_game.get_player(username)?;
+        let game = word_game.start_game(username, player.preferred_locale())?;
+        let text = messages::text(player.language, "telnet.created", &[("code", &game.id)]);
+        Ok(vec![(Recipient::Connection, text)])
+    }
+
+    fn join(&self, username: &str, game_id: &str, word_game: &mut WordGame) -> AppResult<Vec<Delivery>> {
+        if let Some(refusal) = self.ensure_free(username, word_game) {
+            return Ok(refusal);
+        }
+        word_game.join_game(username, game_id)?;
+        joined(username, &word_game.get_game(game_id)?, word_game)
+    }
+
+    fn matchmake(&self, username: &str, word_game: &mut WordGame) -> AppResult<Vec<Delivery>> {
+        if let Some(refusal) = self.ensure_free(username, word_game) {
+            return Ok(refusal);
+        }
+        let game = word_game.matchmake(username)?;
+        joined(username, &game, word_game)
+    }
+
+    fn leave(&self, username: &str, word_game: &mut WordGame) -> AppResult<Vec<Delivery>> {
+        let game = match self.running_game(word_game) {
+            Some(game) => game,
+            None => return Ok(vec![(Recipient::Connection, self.help(word_game))]),
+        };
+        word_game.leave_game(username, &game.id)?;
+
+        let mut deliveries = vec![(
+            Recipient::Connection,
+            format!("{}\n{}", text_for(word_game, username, "telnet.left", &[]), self.help(word_game)),
+        )];
+        if let Some(partner) = game.partner_of(username) {
+            let player = word_game.get_player(username)?;
+            let args: &[(&str, &dyn Display)] = &[("name", &player), ("code", &game.id)];
+            deliveries.push((
+                Recipient::Player(partner.into()),
+                text_for(word_game, partner, "telnet.partner_left", args),
+            ));
+        }
+        Ok(deliveries)
+    }
+
+    fn guess(&self, username: &str, guess: &str, word_game: &mut WordGame) -> AppResult<Vec<Delivery>> {
+        let game = match self.running_game(word_game) {
+            Some(game) if game.is_full() => game,
+            _ => return Ok(vec![(Recipient::Connection, self.help(word_game))]),
+        };
+        let locale = word_game.language_for(username);
+        let guess = match word_game.validate_guess(game.locale(), guess) {
+            Ok(guess) => guess,
+            Err(e) => {
+                let text = messages::text(locale, "guess.try_again", &[("reason", &e.user_message(locale))]);
+                return Ok(vec![(Recipient::Connection, text)]);
+            }
+        };
+        let outcome = word_game.submit_guess(username, &guess)?;
+        let game = word_game.get_game(&game.id)?;
+        let partner = game.partner_of(username).unwrap_or_default().to_string();
+        // Both players hear about a finished round, each in their own
+        // language.
+        let to_both = |write: &dyn Fn(&str) -> String| {
+            vec![
+                (Recipient::Connection, write(username)),
+                (Recipient::Player(partner.clone()), write(&partner)),
+            ]
+        };
+
+        let deliveries = match outcome {
+            GuessOutcome::Waiting { round } => {
+                let player = word_game.get_player(username)?;
+                vec![
+                    (Recipient::Connection, text_for(word_game, username, "guess.waiting", &[("round", &round)])),
+                    (
+                        Recipient::Player(partner.clone()),
+                        text_for(word_game, &partner, "telnet.partner_guessed", &[("name", &player), ("round", &round)]),
+                    ),
+                ]
+            }
+            GuessOutcome::Mismatched { round } => to_both(&|player| {
+                format!(
+                    "{}\n{}\n{}",
+                    reveal(&game, round, player, word_game),
+                    text_for(word_game, player, "round.mismatch", &[]),
+                    text_for(word_game, player, "telnet.next_round", &[("round", &(round + 1))])
+                )
+            }),
+            GuessOutcome::Matched { round } => {
+                let board = word_game.board(&game);
+                let (player_1, player_2) = names(&game, word_game);
+                to_both(&|player| {
+                    format!(
+                        "{}\n{}\n{}\n{}",
+                        reveal(&game, round, player, word_game),
+                        text_for(word_game, player, "game.won", &[("player_1", &player_1), ("player_2", &player_2)]),
+                        text_for(word_game, player, "telnet.took", &[("duration", &board.duration_text())]),
+                        text_for(word_game, player, "telnet.lobby_help", &[])
+                    )
+                })
+            }
+        };
+        Ok(deliveries)
+    }
+}
+
+/// The news for a player who joined a game, and for their partner.
+fn joined(username: &str, game: &Game, word_game: &WordGame) -> AppResult<Vec<Delivery>> {
+    let partner = match game.partner_of(username) {
+        Some(partner) => partner,
+        None => {
+            let text = text_for(word_game, username, "telnet.waiting_partner", &[("code", &game.id)]);
+            return Ok(vec![(Recipient::Connection, text)]);
+        }
+    };
+    let player = word_game.get_player(username)?;
+    let partner_player = word_game.get_player(partner)?;
+    let language = game.locale();
+    Ok(vec![
+        (
+            Recipient::Connection,
+            text_for(word_game, username, "telnet.joined", &[("name", &partner_player), ("language", &language)]),
+        ),
+        (
+            Recipient::Player(partner.into()),
+            text_for(word_game, partner, "telnet.partner_joined", &[("name", &player)]),
+        ),
+    ])
+}
+
+fn text_for(word_game: &WordGame, username: &str, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    messages::text(word_game.language_for(username), key, args)
+}
+
+/// The display names of both players, in seat order.
+fn names(game: &Game, word_game: &WordGame) -> (String, String) {
+    let name = |username: Option<&str>| match username {
+        Some(username) => word_game
+            .get_player(username)
+            .map_or_else(|_| username.to_string(), |p| p.to_string()),
+        None => "???".into(),
+    };
+    (name(game.player_1_username()), name(game.player_2_username()))
+}
+
+/// Both guesses of a finished round, told to `player`.
+fn reveal(game: &Game, round: usize, player: &str, word_game: &WordGame) -> String {
+    let (player_1, player_2) = names(game, word_game);
+    let (guess_1, guess_2) = game.guesses().get(round - 1).cloned().unwrap_or_default();
+    text_for(
+        word_game,
+        player,
+        "room.reveal",
+        &[
+            ("round", &round),
+            ("player_1", &player_1),
+            ("guess_1", &guess_1.unwrap_or_default()),
+            ("player_2", &player_2),
+            ("guess_2", &guess_2.unwrap_or_default()),
+        ],
+    )
+}
+
+fn board(game: &Game, word_game: &WordGame) -> String {
+    let (player_1, player_2) = names(game, word_game);
+    word_game.board(game).with_names(&player_1, &player_2).to_string()
+}
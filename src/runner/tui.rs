@@ -0,0 +1,296 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Paragraph, Row, Table},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    game::{board::Board, GuessOutcome, Player, WordGame},
+    locale::{messages, Locale},
+    time::format::{DurationFormat, DurationStyle},
+    AppResult,
+};
+
+use super::Runner;
+
+const PLAYER_USERNAMES: [&str; 2] = ["player1", "player2"];
+const TICK: Duration = Duration::from_millis(250);
+
+/// A full-screen pass-and-play runner for two players sharing a terminal.
+///
+/// Guesses are typed into a masked field, and the screen asks to pass the
+/// keyboard between turns so neither player sees the other's guess.
+pub struct TuiRunner {}
+
+impl TuiRunner {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for TuiRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait(?Send)]
+impl Runner for TuiRunner {
+    async fn run(&mut self, word_game: Arc<Mutex<WordGame>>) -> AppResult<()> {
+        let mut terminal = ratatui::try_init()?;
+        let result = run_app(&mut terminal, &word_game);
+        ratatui::try_restore()?;
+        result
+    }
+}
+
+/// What the screen is waiting for, with the index of the player it's for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    EnterName(usize),
+    Handoff(usize),
+    Guess(usize),
+    Won,
+}
+
+/// The state behind the screen. Key presses move it along and drive the
+/// game; drawing only reads it.
+pub struct TuiApp {
+    phase: Phase,
+    input: String,
+    players: Vec<Player>,
+    game_id: Option<String>,
+    status: String,
+    quit: bool,
+    locale: Locale,
+}
+
+impl TuiApp {
+    pub fn new() -> Self {
+        Self {
+            phase: Phase::EnterName(0),
+            input: String::new(),
+            players: vec![],
+            game_id: None,
+            status: messages::text(Locale::default(), "welcome", &[]),
+            quit: false,
+            locale: Locale::default(),
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn status(&self) -> &str {
+        &self.status
+    }
+
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    fn player_name(&self, index: usize) -> String {
+        self.players.get(index).map_or_else(
+            || self.text("tui.player", &[("number", &(index + 1))]),
+            |p| format!("{}", p),
+        )
+    }
+
+    fn text(&self, key: &str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+        messages::text(self.locale, key, args)
+    }
+
+    /// Applies a key press. Game errors are shown in the status line rather
+    /// than ending the session.
+    pub fn handle_key(&mut self, code: KeyCode, word_game: &mut WordGame) {
+        if let Err(e) = self.try_handle_key(code, word_game) {
+            self.status = e.user_message(self.locale);
+        }
+    }
+
+    fn try_handle_key(&mut self, code: KeyCode, word_game: &mut WordGame) -> AppResult<()> {
+        match (self.phase, code) {
+            (_, KeyCode::Esc) => self.quit = true,
+            (Phase::Won, KeyCode::Enter) | (Phase::Won, KeyCode::Char('q')) => self.quit = true,
+            (Phase::Won, _) => {}
+            (Phase::Handoff(player), KeyCode::Enter) => self.phase = Phase::Guess(player),
+            (Phase::Handoff(_), _) => {}
+            (_, KeyCode::Backspace) => {
+                self.input.pop();
+            }
+            (_, KeyCode::Char(c)) => self.input.push(c),
+            (Phase::EnterName(player), KeyCode::Enter) => self.submit_name(player, word_game)?,
+            (Phase::Guess(player), KeyCode::Enter) => self.submit_guess(player, word_game)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn submit_name(&mut self, player: usize, word_game: &mut WordGame) -> AppResult<()> {
+        let name = self.input.trim().to_string();
+        self.input.clear();
+        self.players.truncate(player);
+        self.players.push(word_game.create_player(PLAYER_USERNAMES[player], &name)?);
+
+        if player == 0 {
+            self.phase = Phase::EnterName(1);
+            return Ok(());
+        }
+
+        let game = word_game.create_game()?;
+        for username in PLAYER_USERNAMES.iter() {
+            word_game.join_game(username, &game.id)?;
+        }
+        self.locale = game.locale();
+        self.game_id = Some(game.id);
+        self.status = self.text(
+            "tui.goes_first",
+            &[("player_1", &self.player_name(0)), ("player_2", &self.player_name(1))],
+        );
+        self.phase = Phase::Handoff(0);
+        Ok(())
+    }
+
+    fn submit_guess(&mut self, player: usize, word_game: &mut WordGame) -> AppResult<()> {
+        let guess = self.input.trim().to_string();
+        self.input.clear();
+        let locale = self.locale;
+        let guess = match word_game.validate_guess(locale, &guess) {
+            Ok(guess) => guess,
+            Err(e) => {
+                self.status = messages::text(locale, "guess.try_again", &[("reason", &e.user_message(locale))]);
+                return Ok(());
+            }
+        };
+
+        self.status = match word_game.submit_guess(PLAYER_USERNAMES[player], &guess)? {
+            GuessOutcome::Waiting { .. } => {
+                self.phase = Phase::Handoff(1 - player);
+                self.text("tui.pass", &[("name", &self.player_name(1 - player))])
+            }
+            GuessOutcome::Mismatched { round } => {
+                self.phase = Phase::Handoff(0);
+                self.text("tui.mismatch", &[("round", &round), ("name", &self.player_name(0))])
+            }
+            GuessOutcome::Matched { round } => {
+                self.phase = Phase::Won;
+                self.text(
+                    "tui.won",
+                    &[("player_1", &self.player_name(0)), ("player_2", &self.player_name(1)), ("round", &round)],
+                )
+            }
+        };
+        Ok(())
+    }
+
+    fn draw(&self, frame: &mut Frame, board: Option<Board>) {
+        let [top, history, input] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
+        .areas(frame.area());
+        let [status, timer] = Layout::horizontal([Constraint::Min(20), Constraint::Length(20)]).areas(top);
+
+        let board = board.map(|b| b.with_names(&self.player_name(0), &self.player_name(1)));
+
+        frame.render_widget(Paragraph::new(self.status.as_str()).block(Block::bordered().title(self.text("tui.status", &[]))), status);
+        let clock = DurationFormat::new(DurationStyle::Compact).with_max_units(2).with_separator(" ");
+        let elapsed = board.as_ref().map_or("-".into(), |b| b.duration.format(&clock));
+        frame.render_widget(Paragraph::new(elapsed).block(Block::bordered().title(self.text("tui.time", &[]))), timer);
+        self.draw_history(frame, history, board.as_ref());
+        self.draw_input(frame, input);
+    }
+
+    fn draw_history(&self, frame: &mut Frame, area: Rect, board: Option<&Board>) {
+        let block = Block::bordered().title(self.text("tui.rounds", &[]));
+        let board = match board {
+            Some(board) => board,
+            None => {
+                frame.render_widget(Paragraph::new(self.text("tui.no_game", &[])).block(block), area);
+                return;
+            }
+        };
+
+        let (w0, w1, w2) = board.column_widths();
+        let mut rows = board.rows().into_iter();
+        let header = rows
+            .next()
+            .map(|r| Row::new(r.to_vec()).style(Style::default().add_modifier(Modifier::BOLD)))
+            .unwrap_or_default();
+        let rows = rows.zip(board.rounds.iter()).map(|(cells, round)| {
+            let style = if round.matched {
+                Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            Row::new(cells.to_vec()).style(style)
+        });
+        let widths = [
+            Constraint::Length(w0 as u16),
+            Constraint::Length(w1 as u16),
+            Constraint::Length(w2 as u16),
+        ];
+        frame.render_widget(Table::new(rows, widths).header(header).column_spacing(2).block(block), area);
+    }
+
+    fn draw_input(&self, frame: &mut Frame, area: Rect) {
+        let (title, text) = match self.phase {
+            Phase::EnterName(player) => (self.text("tui.enter_name", &[("number", &(player + 1))]), self.input.clone()),
+            Phase::Guess(player) => (
+                self.text("tui.enter_guess", &[("name", &self.player_name(player))]),
+                "*".repeat(self.input.chars().count()),
+            ),
+            Phase::Handoff(player) => (self.text("tui.ready", &[("name", &self.player_name(player))]), String::new()),
+            Phase::Won => (self.text("tui.quit", &[]), String::new()),
+        };
+        let width = text.chars().count() as u16;
+        frame.render_widget(Paragraph::new(Line::from(text)).block(Block::bordered().title(title)), area);
+        if matches!(self.phase, Phase::EnterName(_) | Phase::Guess(_)) {
+            frame.set_cursor_position(Position::new(area.x + 1 + width, area.y + 1));
+        }
+    }
+}
+
+impl Default for TuiApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn run_app(terminal: &mut DefaultTerminal, word_game: &Arc<Mutex<WordGame>>) -> AppResult<()> {
+    let mut app = TuiApp::new();
+
+    while !app.quit {
+        let board = match &app.game_id {
+            Some(game_id) => {
+                let word_game = word_game.lock().unwrap();
+                Some(word_game.board(&word_game.get_game(game_id)?))
+            }
+            None => None,
+        };
+        terminal.draw(|frame| app.draw(frame, board))?;
+
+        if !event::poll(TICK)? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                let mut word_game = word_game.lock().unwrap();
+                app.handle_key(key.code, &mut word_game);
+            }
+        }
+    }
+
+    Ok(())
+}
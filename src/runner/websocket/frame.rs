@@ -0,0 +1,209 @@
+use std::io::{self, ErrorKind};
+
+use sha1::{Digest, Sha1};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Appended to a client's key to prove the server speaks WebSocket
+/// (RFC 6455, section 1.3).
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Largest message we accept, across all of its fragments.
+pub const MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+/// The `Sec-WebSocket-Accept` answer to a client's `Sec-WebSocket-Key`.
+pub fn accept_key(key: &str) -> String {
+    let digest = Sha1::digest(format!("{}{}", key.trim(), HANDSHAKE_GUID).as_bytes());
+    base64::encode(digest)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    Continuation,
+    Text,
+    Binary,
+    Close,
+    Ping,
+    Pong,
+}
+
+impl Opcode {
+    fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            0x0 => Some(Opcode::Continuation),
+            0x1 => Some(Opcode::Text),
+            0x2 => Some(Opcode::Binary),
+            0x8 => Some(Opcode::Close),
+            0x9 => Some(Opcode::Ping),
+            0xA => Some(Opcode::Pong),
+            _ => None,
+        }
+    }
+
+    fn bits(&self) -> u8 {
+        match self {
+            Opcode::Continuation => 0x0,
+            Opcode::Text => 0x1,
+            Opcode::Binary => 0x2,
+            Opcode::Close => 0x8,
+            Opcode::Ping => 0x9,
+            Opcode::Pong => 0xA,
+        }
+    }
+
+    fn is_control(&self) -> bool {
+        matches!(self, Opcode::Close | Opcode::Ping | Opcode::Pong)
+    }
+}
+
+/// A single frame, unmasked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub fin: bool,
+    pub opcode: Opcode,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    pub fn text(text: &str) -> Self {
+        Self { fin: true, opcode: Opcode::Text, payload: text.as_bytes().to_vec() }
+    }
+
+    pub fn control(opcode: Opcode, payload: &[u8]) -> Self {
+        Self { fin: true, opcode, payload: payload.to_vec() }
+    }
+}
+
+/// A whole message, put back together from its fragments.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    Close,
+}
+
+fn protocol_error(reason: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, reason)
+}
+
+/// Reads one frame. Clients must mask what they send and servers must not,
+/// so `masked` says which side we are reading from.
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R, masked: bool) -> io::Result<Frame> {
+    let mut head = [0; 2];
+    reader.read_exact(&mut head).await?;
+    let fin = head[0] & 0x80 != 0;
+    if head[0] & 0x70 != 0 {
+        return Err(protocol_error("Reserved bits are set"));
+    }
+    let opcode = Opcode::from_bits(head[0] & 0x0F).ok_or_else(|| protocol_error("Unknown opcode"))?;
+    if (head[1] & 0x80 != 0) != masked {
+        return Err(protocol_error("Unexpected masking"));
+    }
+
+    let length = match head[1] & 0x7F {
+        126 => reader.read_u16().await? as u64,
+        127 => reader.read_u64().await?,
+        length => length as u64,
+    };
+    if opcode.is_control() && (length > 125 || !fin) {
+        return Err(protocol_error("Control frames must be short and whole"));
+    }
+    if length > MAX_MESSAGE_BYTES as u64 {
+        return Err(protocol_error("Frame too large"));
+    }
+
+    let mut mask = [0; 4];
+    if masked {
+        reader.read_exact(&mut mask).await?;
+    }
+    let mut payload = vec![0; length as usize];
+    reader.read_exact(&mut payload).await?;
+    if masked {
+        apply_mask(&mut payload, mask);
+    }
+
+    Ok(Frame { fin, opcode, payload })
+}
+
+/// Writes a frame, masking it with `mask` when sent from a client.
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, frame: &Frame, mask: Option<[u8; 4]>) -> io::Result<()> {
+    let mut bytes = Vec::with_capacity(frame.payload.len() + 14);
+    bytes.push(((frame.fin as u8) << 7) | frame.opcode.bits());
+
+    let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+    let length = frame.payload.len();
+    if length < 126 {
+        bytes.push(mask_bit | length as u8);
+    } else if length <= u16::MAX as usize {
+        bytes.push(mask_bit | 126);
+        bytes.extend_from_slice(&(length as u16).to_be_bytes());
+    } else {
+        bytes.push(mask_bit | 127);
+        bytes.extend_from_slice(&(length as u64).to_be_bytes());
+    }
+
+    let mut payload = frame.payload.clone();
+    if let Some(mask) = mask {
+        bytes.extend_from_slice(&mask);
+        apply_mask(&mut payload, mask);
+    }
+    bytes.extend_from_slice(&payload);
+
+    writer.write_all(&bytes).await?;
+    writer.flush().await
+}
+
+/// Puts messages back together from the frames of one side of a
+/// WebSocket. Control frames may come between the fragments of another
+/// message; they are returned right away and the fragments so far are kept
+/// for the next call.
+pub struct MessageReader<R> {
+    reader: R,
+    masked: bool,
+    fragments: Option<(Opcode, Vec<u8>)>,
+}
+
+impl<R: AsyncRead + Unpin> MessageReader<R> {
+    /// Reads from `reader`; `masked` is as for `read_frame`.
+    pub fn new(reader: R, masked: bool) -> Self {
+        Self { reader, masked, fragments: None }
+    }
+
+    /// Reads frames until a whole message arrives.
+    pub async fn read(&mut self) -> io::Result<Message> {
+        loop {
+            let frame = read_frame(&mut self.reader, self.masked).await?;
+            match frame.opcode {
+                Opcode::Ping => return Ok(Message::Ping(frame.payload)),
+                Opcode::Pong => return Ok(Message::Pong(frame.payload)),
+                Opcode::Close => return Ok(Message::Close),
+                Opcode::Continuation => match self.fragments.as_mut() {
+                    Some((_, payload)) => payload.extend_from_slice(&frame.payload),
+                    None => return Err(protocol_error("Continuation without a message")),
+                },
+                opcode if self.fragments.is_none() => self.fragments = Some((opcode, frame.payload)),
+                _ => return Err(protocol_error("Message started inside another")),
+            }
+
+            let length = self.fragments.as_ref().map_or(0, |(_, payload)| payload.len());
+            if length > MAX_MESSAGE_BYTES {
+                return Err(protocol_error("Message too large"));
+            }
+            if frame.fin {
+                return match self.fragments.take() {
+                    Some((Opcode::Text, payload)) => String::from_utf8(payload)
+                        .map(Message::Text)
+                        .map_err(|_| protocol_error("Text is not UTF-8")),
+                    Some((_, payload)) => Ok(Message::Binary(payload)),
+                    None => Err(protocol_error("Empty message")),
+                };
+            }
+        }
+    }
+}
+
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    future::Future,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::unbounded_channel,
+};
+
+use crate::{game::WordGame, json::Json, AppResult, ApplicationError};
+
+use self::{
+    frame::{accept_key, write_frame, Frame, Message, MessageReader, Opcode},
+    session::Session,
+};
+
+use super::{
+    http::{
+        request::{read_request, Request},
+        response::{write_response, Response, Status},
+    },
+    hub::{Hub, Recipient},
+    address_env, shutdown_signal, Runner,
+};
+
+pub mod frame;
+pub mod session;
+
+const DEFAULT_ADDRESS: &str = "0.0.0.0:8081";
+const TICK: Duration = Duration::from_secs(1);
+const CLIENT: &str = include_str!("../../../data/web/index.html");
+
+/// Plays the game in real time over WebSockets.
+///
+/// `GET /` serves a small browser client, and `GET /ws` upgrades to a
+/// WebSocket that speaks the JSON commands and events of `Session`. Events
+/// are pushed to every connection of the players they concern, and players
+/// in a running game get a `tick` with the time so far every second.
+pub struct WebSocketRunner {
+    address: SocketAddr,
+    listener: Option<TcpListener>,
+    tick: Duration,
+}
+
+impl WebSocketRunner {
+    pub fn new() -> AppResult<Self> {
+        Ok(Self::with_address(address_env("WORD_GAME_WS_ADDR", DEFAULT_ADDRESS)?))
+    }
+
+    pub fn with_address(address: SocketAddr) -> Self {
+        Self {
+            address,
+            listener: None,
+            tick: TICK,
+        }
+    }
+
+    /// Sends timer ticks every `tick` instead of every second.
+    pub fn with_tick_interval(mut self, tick: Duration) -> Self {
+        self.tick = tick;
+        self
+    }
+
+    /// Starts listening, returning the address in use, which tells which
+    /// port was picked for port 0. `run_until` binds by itself otherwise.
+    pub async fn bind(&mut self) -> AppResult<SocketAddr> {
+        let listener = TcpListener::bind(self.address).await?;
+        let address = listener.local_addr()?;
+        self.listener = Some(listener);
+        Ok(address)
+    }
+
+    /// Serves players until `shutdown` resolves.
+    pub async fn run_until<F: Future<Output = ()>>(&mut self, game: Arc<Mutex<WordGame>>, shutdown: F) -> AppResult<()> {
+        if self.listener.is_none() {
+            self.bind().await?;
+        }
+        let listener = self
+            .listener
+            .take()
+            .ok_or_else(|| ApplicationError::transport("Not listening"))?;
+        println!("Serving the word game on http://{}", listener.local_addr()?);
+        let hub = Arc::new(Mutex::new(Hub::new()));
+        tokio::pin!(shutdown);
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    println!("Shutting down...");
+                    break;
+                }
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        tokio::spawn(handle_connection(stream, game.clone(), hub.clone(), self.tick));
+                    }
+                    Err(e) => println!("Could not accept a connection: {}", e),
+                }
+            }
+        }
+
+        game.lock().unwrap().flush()?;
+
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Runner for WebSocketRunner {
+    async fn run(&mut self, game: Arc<Mutex<WordGame>>) -> AppResult<()> {
+        self.run_until(game, shutdown_signal()).await
+    }
+}
+
+async fn handle_connection(stream: TcpStream, game: Arc<Mutex<WordGame>>, hub: Arc<Mutex<Hub<Json>>>, tick: Duration) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    let request = match read_request(&mut reader).await {
+        Ok(Some(request)) => request,
+        Ok(None) => return,
+        Err(status) => {
+            let _ = Response::refuse(status, status.reason()).write_to(&mut writer).await;
+            return;
+        }
+    };
+
+    let result = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") | ("GET", "/index.html") => {
+            write_response(&mut writer, Status::Ok, "text/html; charset=utf-8", CLIENT.as_bytes()).await
+        }
+        ("GET", "/ws") => match upgrade_key(&request) {
+            Some(key) => match accept(&mut writer, key).await {
+                Ok(()) => {
+                    play(reader, writer, game, hub, tick).await;
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            },
+            None => {
+                Response::refuse(Status::BadRequest, "Expected a WebSocket upgrade")
+                    .write_to(&mut writer)
+                    .await
+            }
+        },
+        _ => {
+            Response::refuse(Status::NotFound, &format!("There is nothing at {}", request.path))
+                .write_to(&mut writer)
+                .await
+        }
+    };
+    if let Err(e) = result {
+        println!("Could not answer a connection: {}", e);
+    }
+}
+
+/// The client's `Sec-WebSocket-Key`, if the request asks for a WebSocket.
+fn upgrade_key(request: &Request) -> Option<&str> {
+    let upgrade = request.header("Upgrade")?;
+    let connection = request.header("Connection")?;
+    let wants_upgrade = connection.split(',').any(|c| c.trim().eq_ignore_ascii_case("upgrade"));
+    if upgrade.eq_ignore_ascii_case("websocket") && wants_upgrade {
+        request.header("Sec-WebSocket-Key")
+    } else {
+        None
+    }
+}
+
+async fn accept<W: AsyncWrite + Unpin>(writer: &mut W, key: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    );
+    writer.write_all(response.as_bytes()).await?;
+    writer.flush().await
+}
+
+/// Relays commands to the game and events back until either side closes
+/// the WebSocket.
+async fn play<R, W>(reader: R, mut writer: W, game: Arc<Mutex<WordGame>>, hub: Arc<Mutex<Hub<Json>>>, tick: Duration)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin,
+{
+    // Reading a message can't be cancelled halfway, so it gets a task of
+    // its own instead of a branch in the select below.
+    let (incoming_sender, mut incoming) = unbounded_channel();
+    let mut reader = MessageReader::new(reader, true);
+    tokio::spawn(async move {
+        loop {
+            let message = reader.read().await;
+            let last = !matches!(message, Ok(Message::Text(_) | Message::Binary(_) | Message::Ping(_) | Message::Pong(_)));
+            if incoming_sender.send(message).is_err() || last {
+                break;
+            }
+        }
+    });
+
+    let (events, mut outgoing) = unbounded_channel::<Json>();
+    let mut session = Session::new();
+    let mut subscription: Option<(String, u64)> = None;
+    let mut ticks = tokio::time::interval(tick);
+
+    loop {
+        let frame = tokio::select! {
+            message = incoming.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let deliveries = {
+                        let mut word_game = game.lock().unwrap();
+                        session.handle(&text, &mut word_game)
+                    };
+                    let mut hub = hub.lock().unwrap();
+                    if session.player() != subscription.as_ref().map(|(player, _)| player.as_str()) {
+                        if let Some((player, id)) = subscription.take() {
+                            hub.unsubscribe(&player, id);
+                        }
+                        subscription = session
+                            .player()
+                            .map(|player| (player.to_string(), hub.subscribe(player, events.clone())));
+                    }
+                    for (recipient, event) in deliveries {
+                        match recipient {
+                            Recipient::Connection => {
+                                let _ = events.send(event);
+                            }
+                            Recipient::Player(player) => hub.send(&player, &event),
+                        }
+                    }
+                    None
+                }
+                Some(Ok(Message::Binary(_))) => Some(Frame::text(
+                    &Json::object(vec![
+                        ("type", "error".into()),
+                        ("error", "bad_request".into()),
+                        ("message", "Commands must be sent as text".into()),
+                    ])
+                    .to_string(),
+                )),
+                Some(Ok(Message::Ping(payload))) => Some(Frame::control(Opcode::Pong, &payload)),
+                Some(Ok(Message::Pong(_))) => None,
+                Some(Ok(Message::Close)) | Some(Err(_)) | None => {
+                    let _ = write_frame(&mut writer, &Frame::control(Opcode::Close, &[]), None).await;
+                    break;
+                }
+            },
+            Some(event) = outgoing.recv() => Some(Frame::text(&event.to_string())),
+            _ = ticks.tick() => {
+                let event = session.tick(&game.lock().unwrap());
+                event.map(|event| Frame::text(&event.to_string()))
+            }
+        };
+
+        if let Some(frame) = frame {
+            if write_frame(&mut writer, &frame, None).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    if let Some((player, id)) = subscription {
+        hub.lock().unwrap().unsubscribe(&player, id);
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{
+    game::{Game, GuessOutcome, WordGame},
+    json::Json,
+    locale::Locale,
+    runner::{
+        http::api::{game_json, player_json},
+        hub::Recipient,
+    },
+    time::format::{DurationFormat, DurationStyle},
+    AppResult, ApplicationError,
+};
+
+pub type Delivery = (Recipient, Json);
+
+/// One browser's conversation with the game.
+///
+/// Clients send JSON commands with a `type`, starting with
+/// `{"type": "hello", "player", "secret"?, "display_name"?, "locale"?}` to
+/// say who they are. The first hello creates the player and its `welcome`
+/// carries a `secret`; coming back as that player takes the same secret.
+/// After that they
+/// may send `new_game` (with an optional `locale`), `join` (with a
+/// `game` code), `matchmake`, `leave`, `guess` (with the `guess`) and
+/// `state`. Everything else they learn from pushed events.
+#[derive(Debug, Default)]
+pub struct Session {
+    player: Option<String>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The player the connection speaks for, once it has said hello.
+    pub fn player(&self) -> Option<&str> {
+        self.player.as_deref()
+    }
+
+    /// Runs a command, returning the events it caused.
+    pub fn handle(&mut self, text: &str, word_game: &mut WordGame) -> Vec<Delivery> {
+        let command = match text.parse::<Json>() {
+            Ok(command) => command,
+            Err(e) => return vec![refusal("bad_request", &format!("Commands must be JSON: {}", e))],
+        };
+        let kind = command.get("type").and_then(Json::as_str).unwrap_or_default();
+        let field = |name: &str| command.get(name).and_then(Json::as_str);
+
+        if kind == "hello" {
+            return match field("player").map(str::trim).filter(|p| !p.is_empty()) {
+                Some(username) => self.hello(
+                    username,
+                    field("secret"),
+                    field("display_name"),
+                    field("locale"),
+                    word_game,
+                ),
+                None => vec![refusal("bad_request", "\"player\" is required")],
+            };
+        }
+        let username = match self.player.clone() {
+            Some(username) => username,
+            None => return vec![refusal("hello_required", "Say hello with your player name first")],
+        };
+
+        let result = match kind {
+            "new_game" => new_game(&username, field("locale"), word_game),
+            "join" => match field("game") {
+                Some(game_id) => join(&username, game_id, word_game),
+                None => return vec![refusal("bad_request", "\"game\" is required")],
+            },
+            "matchmake" => matchmake(&username, word_game),
+            "leave" => leave(&username, word_game),
+            "guess" => match field("guess") {
+                Some(guess) => guess_word(&username, guess, word_game),
+                None => return vec![refusal("bad_request", "\"guess\" is required")],
+            },
+            "state" => current_game_event(&username, word_game).map(|event| vec![(Recipient::Connection, event)]),
+            _ => return vec![refusal("unknown_command", &format!("\"{}\" is not a command", kind))],
+        };
+        result.unwrap_or_else(|e| vec![error_event(&e, word_game.language_for(&username))])
+    }
+
+    /// How long the player's game has been running, while it has two
+    /// players and isn't over.
+    pub fn tick(&self, word_game: &WordGame) -> Option<Json> {
+        let game = word_game.current_game_for(self.player.as_deref()?).ok()??;
+        if !game.is_full() || game.is_complete() {
+            return None;
+        }
+        let board = word_game.board(&game);
+        let clock = DurationFormat::new(DurationStyle::Compact).with_max_units(2).with_separator(" ");
+        Some(Json::object(vec![
+            ("type", "tick".into()),
+            ("game", game.id.as_str().into()),
+            ("elapsed", board.duration.format(&DurationFormat::new(DurationStyle::Iso8601)).into()),
+            ("elapsed_text", board.duration.format(&clock).into()),
+        ]))
+    }
+
+    fn hello(
+        &mut self,
+        username: &str,
+        secret: Option<&str>,
+        display_name: Option<&str>,
+        locale: Option<&str>,
+        word_game: &mut WordGame,
+    ) -> Vec<Delivery> {
+        let result = (|| {
+            let secret = if word_game.has_player(username) {
+                let secret = secret.ok_or(ApplicationError::Unauthorized)?;
+                if word_game.authenticate(secret)?.username != username {
+                    return Err(ApplicationError::Unauthorized);
+                }
+                secret.to_string()
+            } else {
+                let locale = locale.map(str::parse::<Locale>).transpose()?;
+                word_game.create_player(username, display_name.unwrap_or_default())?;
+                if let Some(locale) = locale {
+                    word_game.set_player_locales(username, &[locale])?;
+                    word_game.set_player_language(username, locale)?;
+                }
+                word_game.issue_secret(username)?
+            };
+            let player = word_game.get_player(username)?;
+            let welcome = Json::object(vec![
+                ("type", "welcome".into()),
+                ("player", player_json(&player)),
+                ("secret", secret.into()),
+            ]);
+            Ok(vec![
+                (Recipient::Connection, welcome),
+                (Recipient::Connection, current_game_event(username, word_game)?),
+            ])
+        })();
+        match result {
+            Ok(deliveries) => {
+                self.player = Some(username.into());
+                deliveries
+            }
+            Err(e) => vec![error_event(&e, word_game.language_for(username))],
+        }
+    }
+}
+
+fn new_game(username: &str, locale: Option<&str>, word_game: &mut WordGame) -> AppResult<Vec<Delivery>> {
+    let player = word_game.get_player(username)?;
+    let locale = match locale {
+        Some(locale) => locale.parse()?,
+        None => player.preferred_locale(),
+    };
+    let game = word_game.start_game(username, locale)?;
+    Ok(vec![(Recipient::Player(username.into()), game_event(username, &game, word_game))])
+}
+
+fn join(username: &str, game_id: &str, word_game: &mut WordGame) -> AppResult<Vec<Delivery>> {
+    word_game.join_game(username, game_id)?;
+    joined(username, &word_game.get_game(game_id)?, word_game)
+}
+
+fn matchmake(username: &str, word_game: &mut WordGame) -> AppResult<Vec<Delivery>> {
+    let game = word_game.matchmake(username)?;
+    joined(username, &game, word_game)
+}
+
+/// The game for the player who joined, and the news for their partner.
+fn joined(username: &str, game: &Game, word_game: &WordGame) -> AppResult<Vec<Delivery>> {
+    let mut deliveries = vec![(Recipient::Player(username.into()), game_event(username, game, word_game))];
+    if let Some(partner) = game.partner_of(username) {
+        let player = word_game.get_player(username)?;
+        deliveries.push((
+            Recipient::Player(partner.into()),
+            Json::object(vec![("type", "partner_joined".into()), ("player", player_json(&player))]),
+        ));
+        deliveries.push((Recipient::Player(partner.into()), game_event(partner, game, word_game)));
+    }
+    Ok(deliveries)
+}
+
+fn leave(username: &str, word_game: &mut WordGame) -> AppResult<Vec<Delivery>> {
+    let game = word_game.current_game_for(username)?.ok_or(ApplicationError::NoCurrentGame)?;
+    let partner = game.partner_of(username).map(String::from);
+    word_game.leave_game(username, &game.id)?;
+
+    let mut deliveries = vec![(
+        Recipient::Player(username.into()),
+        Json::object(vec![("type", "left".into()), ("game", game.id.as_str().into())]),
+    )];
+    if let Some(partner) = partner {
+        deliveries.push((
+            Recipient::Player(partner),
+            Json::object(vec![("type", "partner_left".into()), ("player", username.into())]),
+        ));
+    }
+    Ok(deliveries)
+}
+
+fn guess_word(username: &str, guess: &str, word_game: &mut WordGame) -> AppResult<Vec<Delivery>> {
+    let outcome = word_game.submit_guess(username, guess)?;
+    let game = word_game.current_game_for(username)?.ok_or(ApplicationError::NoCurrentGame)?;
+    let partner = game.partner_of(username).map(String::from);
+    let to_both = |event: Json| -> Vec<Delivery> {
+        std::iter::once(username.to_string())
+            .chain(partner.clone())
+            .map(|player| (Recipient::Player(player), event.clone()))
+            .collect()
+    };
+
+    let deliveries = match outcome {
+        GuessOutcome::Waiting { round } => {
+            let mut deliveries = vec![(
+                Recipient::Player(username.into()),
+                Json::object(vec![("type", "guess_accepted".into()), ("round", round.into())]),
+            )];
+            if let Some(partner) = &partner {
+                deliveries.push((
+                    Recipient::Player(partner.clone()),
+                    Json::object(vec![("type", "partner_guessed".into()), ("round", round.into())]),
+                ));
+            }
+            deliveries
+        }
+        GuessOutcome::Mismatched { round } => to_both(revealed(&game, round, false)),
+        GuessOutcome::Matched { round } => {
+            let mut deliveries = to_both(revealed(&game, round, true));
+            let board = word_game.board(&game);
+            deliveries.extend(to_both(Json::object(vec![
+                ("type", "game_won".into()),
+                ("game", game.id.as_str().into()),
+                ("round", round.into()),
+                ("duration", board.duration.format(&DurationFormat::new(DurationStyle::Iso8601)).into()),
+                ("duration_text", board.duration_text().into()),
+            ])));
+            deliveries
+        }
+    };
+    Ok(deliveries)
+}
+
+/// Both guesses of a finished round.
+fn revealed(game: &Game, round: usize, matched: bool) -> Json {
+    let (g1, g2) = game.guesses().get(round - 1).cloned().unwrap_or_default();
+    Json::object(vec![
+        ("type", "round_revealed".into()),
+        ("game", game.id.as_str().into()),
+        ("round", round.into()),
+        ("player_1", g1.into()),
+        ("player_2", g2.into()),
+        ("matched", matched.into()),
+    ])
+}
+
+fn game_event(username: &str, game: &Game, word_game: &WordGame) -> Json {
+    Json::object(vec![("type", "game".into()), ("game", game_json(word_game, game, Some(username)))])
+}
+
+/// The player's current game, or a `null` game when they have none.
+fn current_game_event(username: &str, word_game: &WordGame) -> AppResult<Json> {
+    Ok(match word_game.current_game_for(username)? {
+        Some(game) => game_event(username, &game, word_game),
+        None => Json::object(vec![("type", "game".into()), ("game", Json::Null)]),
+    })
+}
+
+fn error_event(e: &ApplicationError, locale: Locale) -> Delivery {
+    refusal(e.code(), &e.user_message(locale))
+}
+
+/// An error that only concerns this connection.
+fn refusal(code: &str, message: &str) -> Delivery {
+    (
+        Recipient::Connection,
+        Json::object(vec![("type", "error".into()), ("error", code.into()), ("message", message.into())]),
+    )
+}
@@ -0,0 +1,426 @@
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{prelude::*, Duration};
+
+use xmpp_parsers::{
+    data_forms::{DataForm, DataFormType, Field, FieldType},
+    disco::{DiscoInfoResult, DiscoItemsResult, Feature, Identity, Item},
+    iq::{Iq, IqType},
+    ns::{DATA_FORMS, DISCO_INFO},
+    BareJid, Element, Jid,
+};
+
+use crate::{
+    game::{GuessOutcome, WordGame},
+    locale::{messages, Locale},
+    runner::commands,
+    AppResult,
+};
+
+/// XEP-0050 namespace, also used as the disco node listing the commands.
+pub const COMMANDS: &str = "http://jabber.org/protocol/commands";
+/// How long a client has to submit a command's form before its session
+/// is forgotten.
+const SESSION_TIMEOUT_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    StartGame,
+    JoinGame,
+    SubmitGuess,
+    Stats,
+}
+
+const ALL_COMMANDS: [Command; 4] = [
+    Command::StartGame,
+    Command::JoinGame,
+    Command::SubmitGuess,
+    Command::Stats,
+];
+
+impl Command {
+    fn from_node(node: &str) -> Option<Self> {
+        ALL_COMMANDS.iter().copied().find(|c| c.node() == node)
+    }
+
+    fn node(&self) -> &'static str {
+        match self {
+            Command::StartGame => "start-game",
+            Command::JoinGame => "join-game",
+            Command::SubmitGuess => "submit-guess",
+            Command::Stats => "stats",
+        }
+    }
+
+    fn name(&self, language: Locale) -> String {
+        let key = match self {
+            Command::StartGame => "adhoc.command.start",
+            Command::JoinGame => "adhoc.command.join",
+            Command::SubmitGuess => "adhoc.command.guess",
+            Command::Stats => "adhoc.command.stats",
+        };
+        messages::text(language, key, &[])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    Execute,
+    Next,
+    Complete,
+    Cancel,
+}
+
+enum Status {
+    Executing,
+    Completed,
+    Canceled,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Executing => "executing",
+            Status::Completed => "completed",
+            Status::Canceled => "canceled",
+        }
+    }
+}
+
+enum NoteType {
+    Info,
+    Error,
+}
+
+/// A response to a command execution, built into the `<command/>` payload.
+struct Response {
+    status: Status,
+    form: Option<DataForm>,
+    note: Option<(NoteType, String)>,
+}
+
+impl Response {
+    fn completed(note: &str) -> Self {
+        Self {
+            status: Status::Completed,
+            form: None,
+            note: Some((NoteType::Info, note.into())),
+        }
+    }
+
+    fn failed(note: &str) -> Self {
+        Self {
+            status: Status::Completed,
+            form: None,
+            note: Some((NoteType::Error, note.into())),
+        }
+    }
+
+    fn form(form: DataForm) -> Self {
+        Self {
+            status: Status::Executing,
+            form: Some(form),
+            note: None,
+        }
+    }
+}
+
+/// Serves the XEP-0050 ad-hoc commands so clients can play through forms.
+///
+/// Multi-stage commands (join and guess) first return a form and keep a
+/// session until the client submits or cancels it. Sessions left open for
+/// longer than ten minutes by the game's clock are dropped.
+pub struct AdHocCommands {
+    // Session id -> requester JID and when the session was opened
+    sessions: HashMap<String, (Jid, DateTime<Utc>)>,
+}
+
+impl AdHocCommands {
+    pub fn new() -> Self {
+        Self {
+            sessions: HashMap::new(),
+        }
+    }
+
+    pub fn handles_disco_node(node: Option<&str>) -> bool {
+        match node {
+            Some(COMMANDS) => true,
+            Some(node) => Command::from_node(node).is_some(),
+            None => false,
+        }
+    }
+
+    /// The commands, named in the requester's `language`.
+    pub fn disco_items(&self, own_jid: &Jid, language: Locale) -> DiscoItemsResult {
+        DiscoItemsResult {
+            node: Some(COMMANDS.into()),
+            items: ALL_COMMANDS
+                .iter()
+                .map(|c| Item {
+                    jid: own_jid.clone(),
+                    node: Some(c.node().into()),
+                    name: Some(c.name(language)),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn disco_info(&self, node: &str, language: Locale) -> DiscoInfoResult {
+        let identity = match Command::from_node(node) {
+            Some(command) => Identity::new("automation", "command-node", language.code(), command.name(language)),
+            None => {
+                let name = messages::text(language, "adhoc.command_list", &[]);
+                Identity::new("automation", "command-list", language.code(), name)
+            }
+        };
+        DiscoInfoResult {
+            node: Some(node.into()),
+            identities: vec![identity],
+            features: vec![
+                Feature::new(COMMANDS),
+                Feature::new(DATA_FORMS),
+                Feature::new(DISCO_INFO),
+            ],
+            extensions: vec![],
+        }
+    }
+
+    pub fn execute(&mut self, iq: &Iq, payload: &Element, game: &Arc<Mutex<WordGame>>) -> Element {
+        let from = match &iq.from {
+            Some(from) => from.clone(),
+            None => return make_result(iq, None),
+        };
+        let node = payload.attr("node").unwrap_or_default().to_string();
+        let command = match Command::from_node(&node) {
+            Some(command) => command,
+            None => {
+                let language = game.lock().unwrap().language_for(&format!("{}", BareJid::from(from.clone())));
+                let note = messages::text(language, "adhoc.unknown_command", &[]);
+                return make_result(iq, Some(make_command(&node, None, Response::failed(&note))));
+            }
+        };
+        let action = match payload.attr("action") {
+            Some("next") => Action::Next,
+            Some("complete") => Action::Complete,
+            Some("cancel") => Action::Cancel,
+            _ => Action::Execute,
+        };
+        let now = game.lock().unwrap().now();
+        let timeout = Duration::minutes(SESSION_TIMEOUT_MINUTES);
+        self.sessions.retain(|_, (_, opened)| now - *opened < timeout);
+        let session_id = payload
+            .attr("sessionid")
+            .filter(|id| matches!(self.sessions.get(*id), Some((jid, _)) if *jid == from))
+            .map(String::from);
+        let submitted = payload
+            .get_child("x", DATA_FORMS)
+            .and_then(|x| DataForm::try_from(x.clone()).ok())
+            .filter(|form| form.type_ == DataFormType::Submit);
+
+        if action == Action::Cancel {
+            if let Some(id) = &session_id {
+                self.sessions.remove(id);
+            }
+            let response = Response {
+                status: Status::Canceled,
+                form: None,
+                note: None,
+            };
+            return make_result(iq, Some(make_command(&node, session_id.as_deref(), response)));
+        }
+
+        let username = format!("{}", BareJid::from(from.clone()));
+        let mut word_game = game.lock().unwrap();
+        let language = word_game.language_for(&username);
+        let response = match (command, submitted, &session_id) {
+            (Command::StartGame, _, _) => start_game(&mut word_game, &username, &from, language),
+            (Command::Stats, _, _) => stats(&word_game, &username, language),
+            (Command::JoinGame, Some(form), Some(_)) => join_game(&mut word_game, &username, &from, &form, language),
+            (Command::SubmitGuess, Some(form), Some(_)) => submit_guess(&mut word_game, &username, &form, language),
+            (Command::JoinGame, _, _) => Ok(Response::form(join_game_form(language))),
+            (Command::SubmitGuess, _, _) => Ok(Response::form(submit_guess_form(language))),
+        };
+        drop(word_game);
+        let response = response.unwrap_or_else(|e| {
+            println!("Error executing command {}: {:?}", node, e);
+            Response::failed(&e.user_message(language))
+        });
+
+        let session_id = match response.status {
+            Status::Executing => {
+                let id = session_id.unwrap_or_else(|| game.lock().unwrap().next_id());
+                self.sessions.insert(id.clone(), (from, now));
+                Some(id)
+            }
+            _ => {
+                if let Some(id) = &session_id {
+                    self.sessions.remove(id);
+                }
+                session_id
+            }
+        };
+
+        make_result(iq, Some(make_command(&node, session_id.as_deref(), response)))
+    }
+}
+
+impl Default for AdHocCommands {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A newcomer's profile is named after the node of their JID.
+fn display_name(username: &str, from: &Jid) -> String {
+    from.clone().node().unwrap_or_else(|| username.into())
+}
+
+fn start_game(word_game: &mut WordGame, username: &str, from: &Jid, language: Locale) -> AppResult<Response> {
+    commands::ensure_player(word_game, username, &display_name(username, from))?;
+    let locale = word_game.get_player(username)?.preferred_locale();
+    let game = word_game.start_game(username, locale)?;
+    let note = messages::text(language, "adhoc.created", &[("code", &game.id)]);
+    Ok(Response::completed(&note))
+}
+
+fn join_game(word_game: &mut WordGame, username: &str, from: &Jid, form: &DataForm, language: Locale) -> AppResult<Response> {
+    let game_id = form_value(form, "game_id");
+    match commands::join_by_code(word_game, username, &display_name(username, from), game_id.as_deref())? {
+        Ok(_) => Ok(Response::completed(&messages::text(language, "adhoc.joined", &[]))),
+        Err(refusal) => Ok(Response::failed(&refusal)),
+    }
+}
+
+fn submit_guess(word_game: &mut WordGame, username: &str, form: &DataForm, language: Locale) -> AppResult<Response> {
+    let guess = match form_value(form, "guess") {
+        Some(guess) => guess,
+        None => return Ok(Response::failed(&messages::text(language, "adhoc.need_guess", &[]))),
+    };
+    if !word_game.has_player(username) {
+        return Ok(Response::failed(&messages::text(language, "adhoc.no_player", &[])));
+    }
+    let note = match word_game.submit_guess(username, &guess) {
+        Ok(GuessOutcome::Waiting { round }) => messages::text(language, "guess.waiting", &[("round", &round)]),
+        Ok(GuessOutcome::Mismatched { round }) => messages::text(language, "adhoc.mismatch", &[("round", &round)]),
+        Ok(GuessOutcome::Matched { round }) => messages::text(language, "adhoc.matched", &[("round", &round)]),
+        Err(e) => {
+            println!("Could not submit guess for {}: {:?}", username, e);
+            return Ok(Response::failed(&e.user_message(language)));
+        }
+    };
+    Ok(Response::completed(&note))
+}
+
+fn stats(word_game: &WordGame, username: &str, language: Locale) -> AppResult<Response> {
+    let text = |key: &str| messages::text(language, key, &[]);
+    let stats = word_game.statistics(username)?;
+    let average = stats
+        .average_rounds_to_win()
+        .map_or("-".into(), |avg| format!("{:.1}", avg));
+    let form = DataForm {
+        type_: DataFormType::Result_,
+        form_type: None,
+        title: Some(text("stats.title")),
+        instructions: None,
+        fields: vec![
+            make_field("games_played", &text("stats.games_played"), FieldType::TextSingle, &stats.games_played.to_string()),
+            make_field("games_won", &text("stats.games_won"), FieldType::TextSingle, &stats.games_won.to_string()),
+            make_field("assisted_wins", &text("stats.assisted_wins"), FieldType::TextSingle, &stats.assisted_wins.to_string()),
+            make_field("average_rounds", &text("stats.average_rounds"), FieldType::TextSingle, &average),
+        ],
+    };
+    Ok(Response {
+        status: Status::Completed,
+        form: Some(form),
+        note: None,
+    })
+}
+
+fn join_game_form(language: Locale) -> DataForm {
+    let text = |key: &str| messages::text(language, key, &[]);
+    DataForm {
+        type_: DataFormType::Form,
+        form_type: None,
+        title: Some(text("form.join.title")),
+        instructions: Some(text("form.join.instructions")),
+        fields: vec![required(make_field("game_id", &text("form.join.game_code"), FieldType::TextSingle, ""))],
+    }
+}
+
+fn submit_guess_form(language: Locale) -> DataForm {
+    let text = |key: &str| messages::text(language, key, &[]);
+    DataForm {
+        type_: DataFormType::Form,
+        form_type: None,
+        title: Some(text("form.guess.title")),
+        instructions: Some(text("form.guess.instructions")),
+        fields: vec![required(make_field("guess", &text("form.guess.guess"), FieldType::TextPrivate, ""))],
+    }
+}
+
+fn make_field(var: &str, label: &str, type_: FieldType, value: &str) -> Field {
+    Field {
+        var: var.into(),
+        type_,
+        label: Some(label.into()),
+        required: false,
+        options: vec![],
+        values: if value.is_empty() { vec![] } else { vec![value.into()] },
+        media: vec![],
+    }
+}
+
+fn required(mut field: Field) -> Field {
+    field.required = true;
+    field
+}
+
+fn form_value(form: &DataForm, var: &str) -> Option<String> {
+    form.fields
+        .iter()
+        .find(|f| f.var == var)
+        .and_then(|f| f.values.first())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn make_command(node: &str, session_id: Option<&str>, response: Response) -> Element {
+    let actions = match response.status {
+        Status::Executing => Some(
+            Element::builder("actions", COMMANDS)
+                .attr("execute", "complete")
+                .append(Element::builder("complete", COMMANDS)),
+        ),
+        _ => None,
+    };
+    let note = response.note.map(|(type_, text)| {
+        let type_ = match type_ {
+            NoteType::Info => "info",
+            NoteType::Error => "error",
+        };
+        Element::builder("note", COMMANDS).attr("type", type_).append(text)
+    });
+
+    Element::builder("command", COMMANDS)
+        .attr("node", node)
+        .attr("sessionid", session_id)
+        .attr("status", response.status.as_str())
+        .append_all(actions)
+        .append_all(response.form.map(Element::from))
+        .append_all(note)
+        .build()
+}
+
+fn make_result(iq: &Iq, payload: Option<Element>) -> Element {
+    let iq = Iq {
+        from: None,
+        to: iq.from.clone(),
+        id: iq.id.clone(),
+        payload: IqType::Result(payload),
+    };
+    iq.into()
+}
@@ -0,0 +1,93 @@
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{prelude::*, Duration};
+
+use xmpp_parsers::{
+    chatstates::ChatState,
+    message::{Body, Message},
+    Element, Jid,
+};
+
+use crate::{
+    game::WordGame,
+    locale::{messages, Locale},
+};
+
+const RELAY_INTERVAL_SECONDS: i64 = 30;
+
+/// Relays XEP-0085 chat states between the two players in a game.
+///
+/// When a player starts composing before guessing, their partner is told
+/// that they are thinking. Notifications are throttled per player so a
+/// burst of composing/paused states only produces one message.
+pub struct ChatStateRelay {
+    // Username -> when their partner was last notified
+    last_relayed: HashMap<String, DateTime<Utc>>,
+}
+
+impl ChatStateRelay {
+    pub fn new() -> Self {
+        Self {
+            last_relayed: HashMap::new(),
+        }
+    }
+
+    /// Returns the username of the partner to notify, if any.
+    pub fn handle(&mut self, username: &str, state: ChatState, game: &Arc<Mutex<WordGame>>) -> Option<String> {
+        if state != ChatState::Composing {
+            return None;
+        }
+
+        let (current, now) = {
+            let word_game = game.lock().unwrap();
+            (word_game.current_game_for(username).ok().flatten()?, word_game.now())
+        };
+        if current.is_complete() || !current.is_full() || current.has_guessed_this_round(username) {
+            return None;
+        }
+        let partner = current.partner_of(username)?.to_string();
+        if self.recently_relayed(username, now) {
+            return None;
+        }
+
+        self.last_relayed.insert(username.into(), now);
+        Some(partner)
+    }
+
+    fn recently_relayed(&self, username: &str, now: DateTime<Utc>) -> bool {
+        self.last_relayed
+            .get(username)
+            .is_some_and(|last| now - *last < Duration::seconds(RELAY_INTERVAL_SECONDS))
+    }
+}
+
+impl Default for ChatStateRelay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn chat_state(message: &Message) -> Option<ChatState> {
+    message
+        .payloads
+        .iter()
+        .find_map(|p| ChatState::try_from(p.clone()).ok())
+}
+
+pub fn make_chat_state(to: Jid, state: ChatState) -> Element {
+    let mut message = Message::new(Some(to));
+    message.payloads.push(state.into());
+    message.into()
+}
+
+pub fn make_partner_thinking(to: Jid, language: Locale) -> Element {
+    let mut message = Message::new(Some(to));
+    let body = messages::text(language, "xmpp.partner_thinking", &[]);
+    message.bodies.insert(String::new(), Body(body));
+    message.payloads.push(ChatState::Active.into());
+    message.into()
+}
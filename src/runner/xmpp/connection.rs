@@ -0,0 +1,61 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use tokio_xmpp::{AsyncClient, Event};
+use xmpp_parsers::Element;
+
+use crate::AppResult;
+
+/// A stream of XMPP events paired with a sink for outgoing stanzas.
+///
+/// The runner only talks to the network through this trait, so it can be
+/// driven by a real `AsyncClient` or by an in-process fake in tests.
+#[async_trait(?Send)]
+pub trait XmppConnection {
+    /// Waits for the next event, or `None` once the stream has ended.
+    async fn next_event(&mut self) -> Option<Event>;
+    async fn send_stanza(&mut self, stanza: Element) -> AppResult<()>;
+    async fn send_end(&mut self) -> AppResult<()>;
+}
+
+/// Opens a new connection each time the runner (re)connects.
+pub trait Connector {
+    fn connect(&mut self) -> AppResult<Box<dyn XmppConnection>>;
+}
+
+#[async_trait(?Send)]
+impl XmppConnection for AsyncClient {
+    async fn next_event(&mut self) -> Option<Event> {
+        self.next().await
+    }
+
+    async fn send_stanza(&mut self, stanza: Element) -> AppResult<()> {
+        Ok(AsyncClient::send_stanza(self, stanza).await?)
+    }
+
+    async fn send_end(&mut self) -> AppResult<()> {
+        Ok(AsyncClient::send_end(self).await?)
+    }
+}
+
+/// Connects to a real server with a JID and password.
+pub struct ClientConnector {
+    jid: String,
+    password: String,
+}
+
+impl ClientConnector {
+    pub fn new(jid: &str, password: &str) -> Self {
+        Self {
+            jid: jid.into(),
+            password: password.into(),
+        }
+    }
+}
+
+impl Connector for ClientConnector {
+    fn connect(&mut self) -> AppResult<Box<dyn XmppConnection>> {
+        let mut client = AsyncClient::new(&self.jid, self.password.clone())?;
+        client.set_reconnect(false);
+        Ok(Box::new(client))
+    }
+}
@@ -0,0 +1,182 @@
+use std::{convert::TryFrom, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    oneshot, Mutex,
+};
+use tokio_xmpp::{Error as XmppError, Event};
+use xmpp_parsers::{iq::Iq, message::Message, presence::Presence, Element, Jid};
+
+use crate::{AppResult, ApplicationError};
+
+use super::connection::{Connector, XmppConnection};
+
+const STANZA_TIMEOUT: Duration = Duration::from_secs(5);
+
+enum FakeEvent {
+    Stanza(Element),
+    Disconnect,
+}
+
+/// The scripted side of an in-process fake XMPP server.
+///
+/// Tests inject stanzas as if they came from other entities and read back
+/// whatever the runner sent. Every connection made through the paired
+/// `FakeConnector` comes online at `bound_jid` and shares this script.
+pub struct FakeServer {
+    events: UnboundedSender<FakeEvent>,
+    outgoing: UnboundedReceiver<Element>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+/// Hands out connections to a `FakeServer`.
+pub struct FakeConnector {
+    bound_jid: Jid,
+    events: Arc<Mutex<UnboundedReceiver<FakeEvent>>>,
+    outgoing: UnboundedSender<Element>,
+}
+
+struct FakeConnection {
+    bound_jid: Jid,
+    events: Arc<Mutex<UnboundedReceiver<FakeEvent>>>,
+    outgoing: UnboundedSender<Element>,
+    online: bool,
+    closed: bool,
+}
+
+impl FakeServer {
+    /// Creates the server script, its connector and a future that resolves
+    /// when the script calls `shutdown`, for use with `XmppRunner::run_until`.
+    pub fn new(bound_jid: Jid) -> (Self, FakeConnector, impl std::future::Future<Output = ()>) {
+        let (events_tx, events_rx) = unbounded_channel();
+        let (outgoing_tx, outgoing_rx) = unbounded_channel();
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let server = Self {
+            events: events_tx,
+            outgoing: outgoing_rx,
+            shutdown: Some(shutdown_tx),
+        };
+        let connector = FakeConnector {
+            bound_jid,
+            events: Arc::new(Mutex::new(events_rx)),
+            outgoing: outgoing_tx,
+        };
+        let shutdown = async {
+            let _ = shutdown_rx.await;
+        };
+        (server, connector, shutdown)
+    }
+
+    pub fn inject<E: Into<Element>>(&self, stanza: E) {
+        let _ = self.events.send(FakeEvent::Stanza(stanza.into()));
+    }
+
+    /// Drops the current connection. The runner will reconnect.
+    pub fn disconnect(&self) {
+        let _ = self.events.send(FakeEvent::Disconnect);
+    }
+
+    /// Asks the runner to stop, as a shutdown signal would.
+    pub fn shutdown(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+
+    /// Waits for the next stanza the runner sends, giving up after a few
+    /// seconds.
+    pub async fn next_stanza(&mut self) -> Option<Element> {
+        tokio::time::timeout(STANZA_TIMEOUT, self.outgoing.recv())
+            .await
+            .ok()
+            .flatten()
+    }
+
+    pub async fn next_message(&mut self) -> Option<Message> {
+        loop {
+            let stanza = self.next_stanza().await?;
+            if let Ok(message) = Message::try_from(stanza) {
+                return Some(message);
+            }
+        }
+    }
+
+    pub async fn next_presence(&mut self) -> Option<Presence> {
+        loop {
+            let stanza = self.next_stanza().await?;
+            if let Ok(presence) = Presence::try_from(stanza) {
+                return Some(presence);
+            }
+        }
+    }
+
+    pub async fn next_iq(&mut self) -> Option<Iq> {
+        loop {
+            let stanza = self.next_stanza().await?;
+            if let Ok(iq) = Iq::try_from(stanza) {
+                return Some(iq);
+            }
+        }
+    }
+
+    /// Returns a stanza that has already been sent, without waiting.
+    pub fn try_next_stanza(&mut self) -> Option<Element> {
+        self.outgoing.try_recv().ok()
+    }
+}
+
+impl Connector for FakeConnector {
+    fn connect(&mut self) -> AppResult<Box<dyn XmppConnection>> {
+        Ok(Box::new(FakeConnection {
+            bound_jid: self.bound_jid.clone(),
+            events: self.events.clone(),
+            outgoing: self.outgoing.clone(),
+            online: false,
+            closed: false,
+        }))
+    }
+}
+
+#[async_trait(?Send)]
+impl XmppConnection for FakeConnection {
+    async fn next_event(&mut self) -> Option<Event> {
+        if self.closed {
+            return None;
+        }
+        if !self.online {
+            self.online = true;
+            return Some(Event::Online {
+                bound_jid: self.bound_jid.clone(),
+                resumed: false,
+            });
+        }
+
+        let mut events = self.events.lock().await;
+        match events.recv().await {
+            Some(FakeEvent::Stanza(stanza)) => Some(Event::Stanza(stanza)),
+            Some(FakeEvent::Disconnect) => {
+                self.closed = true;
+                Some(Event::Disconnected(XmppError::Disconnected))
+            }
+            None => {
+                self.closed = true;
+                None
+            }
+        }
+    }
+
+    async fn send_stanza(&mut self, stanza: Element) -> AppResult<()> {
+        if self.closed {
+            return Err(ApplicationError::transport("Connection closed"));
+        }
+        self.outgoing
+            .send(stanza)
+            .map_err(|_| ApplicationError::transport("Fake server is gone"))
+    }
+
+    async fn send_end(&mut self) -> AppResult<()> {
+        self.closed = true;
+        Ok(())
+    }
+}
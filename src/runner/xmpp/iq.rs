@@ -0,0 +1,146 @@
+use std::{
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+};
+
+use xmpp_parsers::{
+    disco::{DiscoInfoQuery, DiscoInfoResult, DiscoItemsQuery, DiscoItemsResult, Feature, Identity},
+    iq::{Iq, IqType},
+    ns::{CHATSTATES, DISCO_INFO, DISCO_ITEMS, MESSAGE_CORRECT, PING, RECEIPTS, VERSION, XHTML_IM},
+    ping::Ping,
+    stanza_error::{DefinedCondition, ErrorType, StanzaError},
+    version::{VersionQuery, VersionResult},
+    BareJid, Element,
+};
+
+use crate::{game::WordGame, locale::Locale};
+
+use super::ad_hoc::{AdHocCommands, COMMANDS};
+
+const BOT_NAME: &str = "Word Game";
+
+/// Answers an IQ request addressed to the bot.
+///
+/// Every `get` and `set` gets exactly one reply, falling back to a
+/// `service-unavailable` error as RFC 6120 requires. Results and errors
+/// sent to us are never answered.
+pub fn handle_iq(iq: &Iq, commands: &mut AdHocCommands, game: &Arc<Mutex<WordGame>>) -> Option<Element> {
+    match &iq.payload {
+        IqType::Get(element) => Some(handle_get(iq, element, commands, game)),
+        IqType::Set(element) => Some(handle_set(iq, element, commands, game)),
+        IqType::Result(_) | IqType::Error(_) => None,
+    }
+}
+
+fn handle_get(iq: &Iq, element: &Element, commands: &AdHocCommands, game: &Arc<Mutex<WordGame>>) -> Element {
+    let language = || match &iq.from {
+        Some(from) => game.lock().unwrap().language_for(&format!("{}", BareJid::from(from.clone()))),
+        None => Locale::default(),
+    };
+
+    if let Ok(query) = DiscoInfoQuery::try_from(element.clone()) {
+        return match query.node.as_deref() {
+            None => make_result(iq, service_discovery()),
+            Some(node) if AdHocCommands::handles_disco_node(Some(node)) => {
+                make_result(iq, commands.disco_info(node, language()))
+            }
+            Some(_) => make_error(iq, ErrorType::Cancel, DefinedCondition::ItemNotFound),
+        };
+    }
+
+    if let Ok(query) = DiscoItemsQuery::try_from(element.clone()) {
+        return match query.node.as_deref() {
+            None => make_result(iq, DiscoItemsResult { node: None, items: vec![] }),
+            Some(COMMANDS) => {
+                let own_jid = iq.to.clone().or_else(|| iq.from.clone());
+                match own_jid {
+                    Some(own_jid) => make_result(iq, commands.disco_items(&own_jid, language())),
+                    None => make_error(iq, ErrorType::Modify, DefinedCondition::BadRequest),
+                }
+            }
+            Some(_) => make_error(iq, ErrorType::Cancel, DefinedCondition::ItemNotFound),
+        };
+    }
+
+    if VersionQuery::try_from(element.clone()).is_ok() {
+        let version = VersionResult {
+            name: BOT_NAME.into(),
+            version: env!("CARGO_PKG_VERSION").into(),
+            os: Some(std::env::consts::OS.into()),
+        };
+        return make_result(iq, version);
+    }
+
+    if Ping::try_from(element.clone()).is_ok() {
+        return make_empty_result(iq);
+    }
+
+    println!("Unsupported Iq get: {:?}", element);
+    make_error(iq, ErrorType::Cancel, DefinedCondition::ServiceUnavailable)
+}
+
+fn handle_set(iq: &Iq, element: &Element, commands: &mut AdHocCommands, game: &Arc<Mutex<WordGame>>) -> Element {
+    if element.is("command", COMMANDS) {
+        println!("Command from {:?}: {:?}", iq.from, element.attr("node"));
+        return commands.execute(iq, element, game);
+    }
+
+    println!("Unsupported Iq set: {:?}", element);
+    make_error(iq, ErrorType::Cancel, DefinedCondition::ServiceUnavailable)
+}
+
+fn service_discovery() -> DiscoInfoResult {
+    DiscoInfoResult {
+        node: None,
+        identities: vec![Identity::new("client", "bot", "en", BOT_NAME)],
+        features: vec![
+            Feature::new(CHATSTATES),
+            Feature::new(COMMANDS),
+            Feature::new(DISCO_INFO),
+            Feature::new(DISCO_ITEMS),
+            Feature::new(MESSAGE_CORRECT),
+            Feature::new(PING),
+            Feature::new(RECEIPTS),
+            Feature::new(VERSION),
+            Feature::new(XHTML_IM),
+        ],
+        extensions: vec![],
+    }
+}
+
+fn make_result<P: Into<Element>>(iq: &Iq, payload: P) -> Element {
+    Iq {
+        from: None,
+        to: iq.from.clone(),
+        id: iq.id.clone(),
+        payload: IqType::Result(Some(payload.into())),
+    }
+    .into()
+}
+
+fn make_empty_result(iq: &Iq) -> Element {
+    Iq {
+        from: None,
+        to: iq.from.clone(),
+        id: iq.id.clone(),
+        payload: IqType::Result(None),
+    }
+    .into()
+}
+
+fn make_error(iq: &Iq, type_: ErrorType, condition: DefinedCondition) -> Element {
+    let error = StanzaError {
+        type_,
+        by: None,
+        defined_condition: condition,
+        texts: Default::default(),
+        other: None,
+    };
+    Iq {
+        from: None,
+        to: iq.from.clone(),
+        id: iq.id.clone(),
+        payload: IqType::Error(error),
+    }
+    .into()
+}
@@ -0,0 +1,319 @@
+use std::{
+    convert::TryFrom,
+    future::Future,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+
+use tokio_xmpp::Event;
+use xmpp_parsers::{
+    chatstates::ChatState,
+    iq::Iq,
+    message::{Body, Message, MessageType},
+    presence::{Presence, Show as PresenceShow, Type as PresenceType},
+    receipts::Received,
+    Element, Jid, BareJid, JidParseError,
+};
+
+use crate::{
+    game::WordGame,
+    locale::{messages, Locale},
+    time::duration::FormattedDuration,
+    AppResult, ApplicationError,
+};
+
+use self::{
+    ad_hoc::AdHocCommands,
+    chat_states::{chat_state, make_chat_state, make_partner_thinking, ChatStateRelay},
+    connection::{ClientConnector, Connector, XmppConnection},
+    iq::handle_iq,
+    muc::MucRoom,
+};
+
+use super::{backoff::Backoff, commands, required_env, shutdown_signal, Runner};
+
+pub mod ad_hoc;
+pub mod chat_states;
+pub mod connection;
+#[cfg(any(test, feature = "test-util"))]
+pub mod fake;
+pub mod iq;
+pub mod muc;
+pub mod xhtml;
+
+const DEFAULT_MUC_NICK: &str = "WordGame";
+
+pub struct XmppRunner {
+    connector: Box<dyn Connector>,
+    running: bool,
+    state: BotState,
+}
+
+/// Everything the bot keeps between stanzas, preserved across reconnects.
+struct BotState {
+    room: Option<MucRoom>,
+    commands: AdHocCommands,
+    chat_states: ChatStateRelay,
+}
+
+impl XmppRunner {
+    pub fn new() -> AppResult<Self> {
+        let jid = required_env("WORD_GAME_XMPP_JID")?;
+        parse_jid::<Jid>("WORD_GAME_XMPP_JID", &jid)?;
+        let password = required_env("WORD_GAME_XMPP_PASSWORD")?;
+        let nick = std::env::var("WORD_GAME_XMPP_NICK").unwrap_or_else(|_| DEFAULT_MUC_NICK.into());
+        let room = match std::env::var("WORD_GAME_XMPP_MUC") {
+            Ok(room) => Some(MucRoom::new(parse_jid("WORD_GAME_XMPP_MUC", &room)?, &nick)),
+            Err(_) => None,
+        };
+        Ok(Self::with_connector(Box::new(ClientConnector::new(&jid, &password)), room))
+    }
+
+    pub fn with_connector(connector: Box<dyn Connector>, room: Option<MucRoom>) -> Self {
+        Self {
+            connector,
+            running: true,
+            state: BotState {
+                room,
+                commands: AdHocCommands::new(),
+                chat_states: ChatStateRelay::new(),
+            },
+        }
+    }
+
+    /// Runs the bot until `shutdown` resolves, reconnecting whenever the
+    /// connection drops.
+    pub async fn run_until<F: Future<Output = ()>>(&mut self, game: Arc<Mutex<WordGame>>, shutdown: F) -> AppResult<()> {
+        let mut backoff = Backoff::new();
+        tokio::pin!(shutdown);
+        self.running = true;
+
+        while self.running {
+            let mut client = self.connector.connect()?;
+
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown => {
+                        println!("Shutting down...");
+                        self.running = false;
+                        break;
+                    }
+                    event = client.next_event() => match event {
+                        Some(Event::Online { bound_jid, .. }) => {
+                            backoff.reset();
+                            handle_online(&bound_jid, client.as_mut(), self.state.room.as_ref()).await;
+                        }
+                        Some(Event::Stanza(s)) => {
+                            handle_stanza(s, client.as_mut(), game.clone(), &mut self.state).await;
+                        }
+                        Some(Event::Disconnected(e)) => {
+                            println!("Disconnected: {}", e);
+                        }
+                        None => break,
+                    }
+                }
+            }
+
+            if !self.running {
+                if let Err(e) = client.send_end().await {
+                    println!("Could not close the connection cleanly: {:?}", e);
+                }
+                break;
+            }
+
+            let delay = backoff.next_delay();
+            println!("Reconnecting in {}...", FormattedDuration::from(delay));
+            tokio::select! {
+                _ = &mut shutdown => {
+                    println!("Shutting down...");
+                    self.running = false;
+                }
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+
+        game.lock().unwrap().flush()?;
+
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Runner for XmppRunner {
+    async fn run(&mut self, game: Arc<Mutex<WordGame>>) -> AppResult<()> {
+        self.run_until(game, shutdown_signal()).await
+    }
+}
+
+/// Parses the JID in the setting `name`.
+fn parse_jid<T: FromStr<Err = JidParseError>>(name: &str, value: &str) -> AppResult<T> {
+    value
+        .parse()
+        .map_err(|e| ApplicationError::config(&format!("Expected {} to be a JID", name)).caused_by(e))
+}
+
+async fn send(client: &mut dyn XmppConnection, stanza: Element) {
+    if let Err(e) = client.send_stanza(stanza).await {
+        println!("Could not send stanza: {:?}", e);
+    }
+}
+
+async fn handle_online(bound_jid: &Jid, client: &mut dyn XmppConnection, room: Option<&MucRoom>) {
+    println!("Online at {}", bound_jid);
+    let presence = make_presence();
+    send(client, presence).await;
+
+    if let Some(room) = room {
+        println!("Joining room {}", room.jid());
+        send(client, room.join_presence()).await;
+    }
+}
+
+async fn handle_stanza(
+    stanza: Element,
+    client: &mut dyn XmppConnection,
+    game: Arc<Mutex<WordGame>>,
+    state: &mut BotState,
+) {
+    if let Ok(presence) = Presence::try_from(stanza.clone()) {
+        if let Some(room) = state.room.as_mut().filter(|r| presence.from.as_ref().is_some_and(|f| r.is_from_room(f))) {
+            room.handle_presence(&presence);
+            return;
+        }
+        match (&presence.from, &presence.type_) {
+            (Some(ref from), PresenceType::Subscribe) => {
+                println!("Got subscribe presence from: {}", &from);
+                let subscribed = allow_presence_subscribe(from.clone());
+                send(client, subscribed).await;
+            }
+            (Some(ref from), PresenceType::Subscribed) => {
+                println!("Got subscribed presence from: {}", &from);
+            }
+            _ => {}
+        }
+    } else if let Ok(message) = Message::try_from(stanza.clone()) {
+        if let (Some(from), Some(incoming)) = (&message.from, chat_state(&message)) {
+            if message.bodies.is_empty() {
+                if let Some(notice) = relay_chat_state(from, incoming, &game, state) {
+                    send(client, notice).await;
+                }
+                return;
+            }
+        }
+
+        if let Some(room) = state.room.as_mut().filter(|r| message.from.as_ref().is_some_and(|f| r.is_from_room(f))) {
+            if let (Some(from), MessageType::Chat) = (&message.from, &message.type_) {
+                send(client, make_chat_state(from.clone(), ChatState::Composing)).await;
+            }
+            for reply in room.handle_message(&message, &game) {
+                send(client, reply).await;
+            }
+            return;
+        }
+
+        match (
+            message.id,
+            message.from,
+            message.bodies.get(""),
+            message.payloads,
+        ) {
+            (Some(id), Some(from), Some(body), payloads)
+                if message.type_ != MessageType::Error =>
+            {
+                handle_ack(payloads, &from, id, client).await;
+                send(client, make_chat_state(from.clone(), ChatState::Composing)).await;
+
+                let username = format!("{}", BareJid::from(from.clone()));
+                let reply = {
+                    let mut word_game = game.lock().unwrap();
+                    if body.0.starts_with("/status") {
+                        commands::status(&word_game, &username, &username)
+                    } else if body.0.starts_with("/language") {
+                        let display_name = from.clone().node().unwrap_or_else(|| username.clone());
+                        commands::change_language(&mut word_game, &username, &display_name, &body.0)
+                    } else {
+                        let language = word_game.language_for(&username);
+                        let greeting = messages::text(language, "xmpp.greeting", &[("name", &username)]);
+                        format!("{}\n{}", greeting, list_commands(language))
+                    }
+                };
+                send(client, make_reply(from.clone(), &reply)).await;
+            }
+            _ => {}
+        }
+    } else if let Ok(iq) = Iq::try_from(stanza.clone()) {
+        if let Some(response) = handle_iq(&iq, &mut state.commands, &game) {
+            send(client, response).await;
+        }
+    } else {
+        println!("Unhandled stanza: {:?}", stanza);
+    }
+}
+
+fn relay_chat_state(from: &Jid, chat_state: ChatState, game: &Arc<Mutex<WordGame>>, state: &mut BotState) -> Option<Element> {
+    let room = state.room.as_ref().filter(|r| r.is_from_room(from));
+    let username = match (room, from) {
+        (Some(room), Jid::Full(from)) => room.username_for(&from.resource)?.to_string(),
+        (Some(_), Jid::Bare(_)) => return None,
+        (None, _) => format!("{}", BareJid::from(from.clone())),
+    };
+
+    let partner = state.chat_states.handle(&username, chat_state, game)?;
+    let to = match room {
+        Some(room) => room.address_for(&partner)?,
+        None => partner.parse::<Jid>().ok()?,
+    };
+    let language = game.lock().unwrap().language_for(&partner);
+    Some(make_partner_thinking(to, language))
+}
+
+async fn handle_ack(payloads: Vec<Element>, from: &Jid, id: String, client: &mut dyn XmppConnection) {
+    if should_ack(payloads) {
+        let receipt = make_receipt(from.clone(), &id);
+        send(client, receipt).await;
+    }
+}
+
+fn make_presence() -> Element {
+    let mut presence = Presence::new(PresenceType::None);
+    presence.show = Some(PresenceShow::Chat);
+    presence
+        .statuses
+        .insert(String::from("en"), String::from("Echoing messages."));
+    presence.into()
+}
+
+fn allow_presence_subscribe(to: Jid) -> Element {
+    let presence = Presence::new(PresenceType::Subscribed)
+        .with_show(PresenceShow::Chat)
+        .with_to(to);
+    presence.into()
+}
+
+fn make_reply(to: Jid, body: &str) -> Element {
+    let mut message = Message::new(Some(to));
+    message.bodies.insert(String::new(), Body(body.to_owned()));
+    message.payloads.push(ChatState::Active.into());
+    message.into()
+}
+
+fn should_ack(payloads: Vec<Element>) -> bool {
+    payloads.iter().any(|element| {
+        element.name() == "request" && element.ns() == "urn:xmpp:receipts"
+    })
+}
+
+fn make_receipt(to: Jid, id: &str) -> Element {
+    let mut message = Message::new(Some(to));
+    let receipt = Received { id: id.to_owned() };
+    message.payloads.push(receipt.into());
+
+    message.into()
+}
+
+fn list_commands(language: Locale) -> String {
+    let commands_list = [("status", "command.status"), ("language <code>", "command.language")];
+    commands::command_list(language, "xmpp.commands", '/', &commands_list)
+}
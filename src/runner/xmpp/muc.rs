@@ -0,0 +1,394 @@
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+};
+
+use xmpp_parsers::{
+    chatstates::ChatState,
+    message::{Body, Message, MessageType},
+    message_correct::Replace,
+    muc::{
+        muc::History,
+        user::{MucUser, Status},
+        Muc,
+    },
+    presence::{Presence, Type as PresenceType},
+    BareJid, Element, Jid,
+};
+
+use crate::{
+    game::{board::Board, Game, GuessCorrection, WordGame},
+    locale::{messages, Locale},
+    runner::commands::{self, Guessed},
+    AppResult, ApplicationError,
+};
+
+use super::xhtml::make_board_message;
+
+/// A Multi-User Chat room the bot has joined to host group games.
+///
+/// Anyone in the room can start, join or leave the room's game with
+/// groupchat commands. Guesses are sent to the bot as private messages, and the
+/// reveals are posted back to the room for everyone to see.
+pub struct MucRoom {
+    jid: BareJid,
+    nick: String,
+    // Room nickname -> player username
+    occupants: HashMap<String, String>,
+    game_id: Option<String>,
+}
+
+impl MucRoom {
+    pub fn new(jid: BareJid, nick: &str) -> Self {
+        Self {
+            jid,
+            nick: nick.into(),
+            occupants: HashMap::new(),
+            game_id: None,
+        }
+    }
+
+    pub fn jid(&self) -> &BareJid {
+        &self.jid
+    }
+
+    pub fn is_from_room(&self, from: &Jid) -> bool {
+        match from {
+            Jid::Bare(from) => from == &self.jid,
+            Jid::Full(from) => from.node == self.jid.node && from.domain == self.jid.domain,
+        }
+    }
+
+    pub fn join_presence(&self) -> Element {
+        let mut presence = Presence::new(PresenceType::None)
+            .with_to(self.jid.clone().with_resource(self.nick.clone()));
+        presence.add_payload(Muc::new().with_history(History::new().with_maxstanzas(0)));
+        presence.into()
+    }
+
+    pub fn handle_presence(&mut self, presence: &Presence) {
+        let nick = match &presence.from {
+            Some(Jid::Full(from)) => from.resource.clone(),
+            _ => return,
+        };
+        if nick == self.nick {
+            return;
+        }
+
+        let muc_user = presence
+            .payloads
+            .iter()
+            .find_map(|p| MucUser::try_from(p.clone()).ok());
+
+        if presence.type_ == PresenceType::Unavailable {
+            let username = self.occupants.remove(&nick);
+            let new_nick = muc_user.as_ref().and_then(|user| {
+                if user.status.contains(&Status::NewNick) {
+                    user.items.iter().find_map(|item| item.nick.clone())
+                } else {
+                    None
+                }
+            });
+            if let (Some(username), Some(new_nick)) = (username, new_nick) {
+                println!("{} is now known as {} in {}", nick, new_nick, self.jid);
+                self.occupants.insert(new_nick, username);
+            }
+            return;
+        }
+
+        if self.occupants.contains_key(&nick) {
+            return;
+        }
+
+        let real_jid = muc_user
+            .iter()
+            .flat_map(|user| user.items.iter())
+            .find_map(|item| item.jid.clone());
+        let username = match real_jid {
+            Some(jid) => format!("{}", BareJid::from(Jid::Full(jid))),
+            None => format!("{}/{}", self.jid, nick),
+        };
+        println!("{} joined {} as {}", nick, self.jid, username);
+        self.occupants.insert(nick, username);
+    }
+
+    pub fn handle_message(&mut self, message: &Message, game: &Arc<Mutex<WordGame>>) -> Vec<Element> {
+        let nick = match &message.from {
+            Some(Jid::Full(from)) => from.resource.clone(),
+            _ => return vec![],
+        };
+        let body = match message.bodies.get("") {
+            Some(body) => body.0.trim().to_string(),
+            None => return vec![],
+        };
+        if nick == self.nick {
+            return vec![];
+        }
+
+        let correction = message
+            .payloads
+            .iter()
+            .find_map(|p| Replace::try_from(p.clone()).ok());
+
+        let result = match (&message.type_, correction) {
+            (MessageType::Groupchat, _) => self.handle_groupchat(&nick, &body, game),
+            (MessageType::Chat, Some(correction)) => {
+                self.handle_correction(&nick, &correction.id, message.id.as_deref(), &body, game)
+            }
+            (MessageType::Chat, None) => self.handle_private(&nick, &body, message.id.as_deref(), game),
+            _ => Ok(vec![]),
+        };
+
+        result.unwrap_or_else(|e| {
+            println!("Error handling message in {}: {:?}", self.jid, e);
+            let language = self.language_for(&nick, game);
+            vec![self.make_private(&nick, &e.user_message(language))]
+        })
+    }
+
+    fn handle_groupchat(&mut self, nick: &str, body: &str, game: &Arc<Mutex<WordGame>>) -> AppResult<Vec<Element>> {
+        let username = match self.occupants.get(nick) {
+            Some(username) => username.clone(),
+            None => return Ok(vec![]),
+        };
+        let mut word_game = game.lock().unwrap();
+        let language = self.room_language(&word_game);
+        let text = |key: &str, args: &[(&str, &dyn std::fmt::Display)]| messages::text(language, key, args);
+
+        if body.starts_with("/start") {
+            if let Some(game_id) = &self.game_id {
+                if !word_game.is_game_complete(game_id)? {
+                    return Ok(vec![self.make_groupchat(&text("room.game_in_progress", &[]))]);
+                }
+            }
+            let new_game = match commands::start(&mut word_game, &username, nick, body)? {
+                Ok(game) => game,
+                Err(refusal) => return Ok(vec![self.make_groupchat(&refusal)]),
+            };
+            let locale = new_game.locale();
+            self.game_id = Some(new_game.id);
+
+            let reply = messages::text(locale, "room.started", &[("name", &nick), ("language", &locale)]);
+            Ok(vec![self.make_groupchat(&reply)])
+        } else if body.starts_with("/join") {
+            let current = match &self.game_id {
+                Some(game_id) if !word_game.is_game_complete(game_id)? => word_game.get_game(game_id)?,
+                _ => return Ok(vec![self.make_groupchat(&text("room.no_game", &[]))]),
+            };
+            if let Err(refusal) = commands::join_hosted(&mut word_game, &username, nick, body, &current)? {
+                return Ok(vec![self.make_groupchat(&refusal)]);
+            }
+
+            let partner = current
+                .player_1_username()
+                .or_else(|| current.player_2_username())
+                .map_or("???".into(), |p| self.nick_for(p));
+            let reply = text("room.playing", &[("player_1", &partner), ("player_2", &nick)]);
+            Ok(vec![self.make_groupchat(&reply)])
+        } else if body.starts_with("/leave") {
+            let current = match &self.game_id {
+                Some(game_id) => word_game.get_game(game_id)?,
+                None => return Ok(vec![self.make_groupchat(&text("room.no_game", &[]))]),
+            };
+            if current.is_complete() {
+                return Ok(vec![self.make_groupchat(&text("room.no_game", &[]))]);
+            }
+            if current.player_1_username() != Some(username.as_str())
+                && current.player_2_username() != Some(username.as_str())
+            {
+                return Ok(vec![self.make_groupchat(&text("room.not_in_game", &[("name", &nick)]))]);
+            }
+            word_game.leave_game(&username, &current.id)?;
+
+            // With nobody left the game can't go on, so the room is free
+            // for a new one.
+            let after = word_game.get_game(&current.id)?;
+            let key = if after.player_1_username().is_none() && after.player_2_username().is_none() {
+                self.game_id = None;
+                "room.abandoned"
+            } else {
+                "room.left"
+            };
+            Ok(vec![self.make_groupchat(&text(key, &[("name", &nick)]))])
+        } else if body.starts_with("/board") {
+            match &self.game_id {
+                Some(game_id) => {
+                    let board = self.board_for(&word_game, &word_game.get_game(game_id)?);
+                    Ok(vec![self.make_groupchat_board(None, &board)])
+                }
+                None => Ok(vec![self.make_groupchat(&text("room.no_game_yet", &[]))]),
+            }
+        } else if body.starts_with("/help") {
+            Ok(vec![self.make_groupchat(&list_room_commands(language))])
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    fn handle_private(&mut self, nick: &str, body: &str, id: Option<&str>, game: &Arc<Mutex<WordGame>>) -> AppResult<Vec<Element>> {
+        let username = match self.occupants.get(nick) {
+            Some(username) => username.clone(),
+            None => return Ok(vec![]),
+        };
+        let mut word_game = game.lock().unwrap();
+        let language = word_game.language_for(&username);
+        let text = |key: &str, args: &[(&str, &dyn std::fmt::Display)]| messages::text(language, key, args);
+
+        if body.starts_with("/hint") {
+            let reply = commands::hint(&mut word_game, &username).unwrap_or_else(|e| e.user_message(language));
+            return Ok(vec![self.make_private(nick, &reply)]);
+        }
+        if body.starts_with("/language") {
+            let reply = commands::change_language(&mut word_game, &username, nick, body);
+            return Ok(vec![self.make_private(nick, &reply)]);
+        }
+        if body.is_empty() || body.starts_with('/') {
+            let reply = format!("{}\n{}", text("room.guess_help", &[]), list_room_commands(language));
+            return Ok(vec![self.make_private(nick, &reply)]);
+        }
+
+        let game_id = match &self.game_id {
+            Some(game_id) => game_id.clone(),
+            None => return Ok(vec![self.make_private(nick, &text("room.no_game_private", &[]))]),
+        };
+        let before = word_game.get_game(&game_id)?;
+        let is_playing = before.player_1_username() == Some(username.as_str())
+            || before.player_2_username() == Some(username.as_str());
+        if before.is_complete() || !is_playing {
+            return Ok(vec![self.make_private(nick, &text("room.not_playing", &[]))]);
+        }
+
+        let (reply, round, after) = match commands::guess(&mut word_game, &username, &before, body, id)? {
+            Guessed::Refused(reply) | Guessed::Waiting(reply) => return Ok(vec![self.make_private(nick, &reply)]),
+            Guessed::NeedPartner(_) => return Ok(vec![self.make_private(nick, &text("room.need_partner", &[]))]),
+            Guessed::Revealed { reply, round, game } => (reply, round, game),
+        };
+        let board = self.board_for(&word_game, &after);
+        let (reveal, outcome) = commands::reveal(&after, round, &board);
+        let mut stanzas = vec![self.make_private(nick, &reply), self.make_groupchat(&reveal)];
+        if after.is_complete() {
+            stanzas.push(self.make_groupchat_board(Some(&outcome), &board));
+        } else {
+            stanzas.push(self.make_groupchat(&outcome));
+        }
+
+        Ok(stanzas)
+    }
+
+    fn handle_correction(
+        &mut self,
+        nick: &str,
+        corrected_id: &str,
+        id: Option<&str>,
+        body: &str,
+        game: &Arc<Mutex<WordGame>>,
+    ) -> AppResult<Vec<Element>> {
+        let username = match self.occupants.get(nick) {
+            Some(username) => username.clone(),
+            None => return Ok(vec![]),
+        };
+        let mut word_game = game.lock().unwrap();
+        let language = word_game.language_for(&username);
+        let current = word_game.current_game_for(&username)?;
+        let round = match current.as_ref().and_then(|g| g.guess_round_for(&username, corrected_id)) {
+            Some(round) => round,
+            None => return Ok(vec![self.make_private(nick, &messages::text(language, "correction.only_last", &[]))]),
+        };
+        if body.is_empty() || body.starts_with('/') {
+            return Ok(vec![self.make_private(nick, &messages::text(language, "correction.need_word", &[]))]);
+        }
+
+        let locale = current.map(|g| g.locale()).unwrap_or_default();
+        let reply = match word_game.correct_guess(&username, round, body) {
+            Err(e @ ApplicationError::InvalidGuess(_)) => e.user_message(locale),
+            Err(e) => return Err(e),
+            Ok(GuessCorrection::Replaced) => {
+                if let Some(id) = id {
+                    word_game.record_guess_message(&username, round, id)?;
+                }
+                messages::text(language, "correction.updated", &[("round", &round)])
+            }
+            Ok(GuessCorrection::Locked) => messages::text(language, "correction.locked", &[("round", &round)]),
+        };
+        Ok(vec![self.make_private(nick, &reply)])
+    }
+
+    /// Group messages are in the language of the room's game.
+    fn room_language(&self, word_game: &WordGame) -> Locale {
+        self.game_id
+            .as_ref()
+            .and_then(|game_id| word_game.get_game(game_id).ok())
+            .map(|game| game.locale())
+            .unwrap_or_default()
+    }
+
+    fn language_for(&self, nick: &str, game: &Arc<Mutex<WordGame>>) -> Locale {
+        match self.occupants.get(nick) {
+            Some(username) => game.lock().unwrap().language_for(username),
+            None => Locale::default(),
+        }
+    }
+
+    pub fn username_for(&self, nick: &str) -> Option<&str> {
+        self.occupants.get(nick).map(|u| u.as_str())
+    }
+
+    /// Where to privately reach a player, preferring their room nickname.
+    pub fn address_for(&self, username: &str) -> Option<Jid> {
+        let nick = self
+            .occupants
+            .iter()
+            .find(|(_, u)| u.as_str() == username)
+            .map(|(nick, _)| nick)?;
+        Some(Jid::Full(self.jid.clone().with_resource(nick.clone())))
+    }
+
+    fn nick_for(&self, username: &str) -> String {
+        self.occupants
+            .iter()
+            .find(|(_, u)| u.as_str() == username)
+            .map_or(username.into(), |(nick, _)| nick.clone())
+    }
+
+    fn board_for(&self, word_game: &WordGame, game: &Game) -> Board {
+        let p1 = game.player_1_username().map_or("???".into(), |p| self.nick_for(p));
+        let p2 = game.player_2_username().map_or("???".into(), |p| self.nick_for(p));
+        word_game.board(game).with_names(&p1, &p2)
+    }
+
+    fn make_groupchat_board(&self, intro: Option<&str>, board: &Board) -> Element {
+        let mut message = make_board_message(Jid::Bare(self.jid.clone()), intro, board);
+        message.type_ = MessageType::Groupchat;
+        message.into()
+    }
+
+    fn make_groupchat(&self, body: &str) -> Element {
+        let mut message = Message::new(Some(Jid::Bare(self.jid.clone())));
+        message.type_ = MessageType::Groupchat;
+        message.bodies.insert(String::new(), Body(body.to_owned()));
+        message.into()
+    }
+
+    fn make_private(&self, nick: &str, body: &str) -> Element {
+        let to = Jid::Full(self.jid.clone().with_resource(nick));
+        let mut message = Message::new(Some(to));
+        message.bodies.insert(String::new(), Body(body.to_owned()));
+        message.payloads.push(MucUser { status: vec![], items: vec![] }.into());
+        message.payloads.push(ChatState::Active.into());
+        message.into()
+    }
+}
+
+fn list_room_commands(language: Locale) -> String {
+    let commands_list = [
+        ("start [language]", "command.start"),
+        ("join [language]", "command.join"),
+        ("leave", "command.leave"),
+        ("board", "command.board"),
+        ("hint", "command.hint"),
+        ("language <code>", "command.language"),
+        ("help", "command.help"),
+    ];
+    commands::command_list(language, "room.commands", '/', &commands_list)
+}
@@ -0,0 +1,61 @@
+use xmpp_parsers::{
+    message::{Body, Message},
+    ns::{XHTML, XHTML_IM},
+    Element, Jid,
+};
+
+use crate::game::board::Board;
+
+const NBSP: &str = "\u{a0}";
+
+/// Builds a message showing the board as XHTML-IM with a plain-text body
+/// for clients that don't render it.
+///
+/// XHTML-IM has no tables, so each row is a line of `<code>` with
+/// non-breaking spaces keeping the columns aligned.
+pub fn make_board_message(to: Jid, intro: Option<&str>, board: &Board) -> Message {
+    let text = match intro {
+        Some(intro) => format!("{}\n{}", intro, board),
+        None => format!("{}", board),
+    };
+    let mut message = Message::new(Some(to));
+    message.bodies.insert(String::new(), Body(text));
+    message.payloads.push(make_board_html(intro, board));
+    message
+}
+
+fn make_board_html(intro: Option<&str>, board: &Board) -> Element {
+    let intro = intro.map(|intro| Element::builder("p", XHTML).append(intro));
+
+    let mut table = Element::builder("p", XHTML);
+    for (i, row) in board.rows().into_iter().enumerate() {
+        let matched = i > 0 && board.rounds[i - 1].matched;
+        let mut line = Element::builder("code", XHTML);
+        for (column, cell) in row.iter().enumerate() {
+            if column > 0 {
+                line = line.append(NBSP.repeat(2));
+            }
+            let word = cell.trim_end();
+            let padding = NBSP.repeat(cell.chars().count() - word.chars().count());
+            line = if i == 0 || (matched && column > 0) {
+                line.append(Element::builder("strong", XHTML).append(word))
+            } else {
+                line.append(word)
+            };
+            line = line.append(padding);
+        }
+        if i > 0 {
+            table = table.append(Element::builder("br", XHTML));
+        }
+        table = table.append(line);
+    }
+
+    let duration = Element::builder("p", XHTML)
+        .append(Element::builder("em", XHTML).append(board.duration_line()));
+
+    let body = Element::builder("body", XHTML)
+        .append_all(intro)
+        .append(table)
+        .append(duration);
+    Element::builder("html", XHTML_IM).append(body).build()
+}
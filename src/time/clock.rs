@@ -0,0 +1,69 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{prelude::*, Duration as ChronoDuration};
+
+use crate::trait_enum;
+
+pub enum ClockType {
+    System,
+    /// A clock that stands still at the given time until it is moved.
+    Manual(DateTime<Utc>),
+}
+
+/// Where the game gets the current time from.
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock for tests and replays that only moves when told to.
+///
+/// Clones share the same time, so a test can keep one to move the clock
+/// after handing the other to a `WordGame`.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl ManualClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    pub fn advance(&self, duration: ChronoDuration) {
+        let mut now = self.now.lock().unwrap();
+        *now = *now + duration;
+    }
+
+    pub fn set(&self, time: DateTime<Utc>) {
+        *self.now.lock().unwrap() = time;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+trait_enum!(Clock, ClockEnum, SystemClock, ManualClock);
+
+pub struct ClockFactory;
+
+impl ClockFactory {
+    pub fn create(clock_type: ClockType) -> ClockEnum {
+        match clock_type {
+            ClockType::System => ClockEnum::SystemClock(SystemClock),
+            ClockType::Manual(start) => ClockEnum::ManualClock(ManualClock::new(start)),
+        }
+    }
+}
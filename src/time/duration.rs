@@ -1,10 +1,39 @@
-use chrono::Duration as ChronoDuration;
-use std::{convert::TryInto, fmt::Display, time::Duration as StdDuration};
+use chrono::{prelude::*, Duration as ChronoDuration};
+use std::{
+    convert::TryFrom,
+    fmt::Display,
+    str::FromStr,
+    time::Duration as StdDuration,
+};
+
+use crate::ApplicationError;
+
+use super::format::{components, DurationFormat, Unit};
+
+pub(crate) const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Unit names accepted when parsing, with their length in nanoseconds.
+/// Years and months use the same approximate lengths as `Display`.
+const UNITS: [(&[&str], u128); 10] = [
+    (&["year", "years", "yr", "yrs", "y"], 31_557_600 * NANOS_PER_SEC),
+    (&["month", "months", "mo"], 2_630_016 * NANOS_PER_SEC),
+    (&["week", "weeks", "wk", "w"], 604_800 * NANOS_PER_SEC),
+    (&["day", "days", "d"], 86_400 * NANOS_PER_SEC),
+    (&["hour", "hours", "hr", "hrs", "h"], 3_600 * NANOS_PER_SEC),
+    (&["minute", "minutes", "min", "mins", "m"], 60 * NANOS_PER_SEC),
+    (&["second", "seconds", "sec", "secs", "s"], NANOS_PER_SEC),
+    (&["millisecond", "milliseconds", "msec", "ms"], 1_000_000),
+    (&["microsecond", "microseconds", "usec", "us", "µs"], 1_000),
+    (&["nanosecond", "nanoseconds", "nsec", "ns"], 1),
+];
 
 #[derive(Debug, Clone)]
 pub enum FormattedDuration {
     FormattedChronoDuration(ChronoDuration),
     FormattedStdDuration(StdDuration),
+    /// The time between two instants, with years, months and days counted
+    /// on the calendar instead of by their average length.
+    FormattedCalendarDuration { start: DateTime<Utc>, end: DateTime<Utc> },
 }
 
 impl From<ChronoDuration> for FormattedDuration {
@@ -19,63 +48,191 @@ impl From<StdDuration> for FormattedDuration {
     }
 }
 
-impl Display for FormattedDuration {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let secs = match self {
-            FormattedDuration::FormattedChronoDuration(d) => d.num_seconds(),
-            FormattedDuration::FormattedStdDuration(d) => d.as_secs().try_into().expect("Could not convert seconds to i64"),
-        };
-
-        let nanos = match self {
-            FormattedDuration::FormattedChronoDuration(d) => d.num_nanoseconds().unwrap(),
-            FormattedDuration::FormattedStdDuration(d) => d.subsec_nanos().into(),
-        };
-
-        if secs == 0 && nanos == 0 {
-            return write!(f, "0s")
+impl FormattedDuration {
+    /// The calendar time from `start` to `end`, like a game's start and end
+    /// timestamps. Negative when `end` comes first.
+    pub fn between(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        FormattedDuration::FormattedCalendarDuration { start, end }
+    }
+
+    pub fn format(&self, format: &DurationFormat) -> String {
+        let (negative, parts) = self.parts();
+        format.format(negative, &parts)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.parts().0
+    }
+
+    /// Whether the duration is negative, and the count of each unit in it.
+    fn parts(&self) -> (bool, Vec<(Unit, u128)>) {
+        match self {
+            FormattedDuration::FormattedChronoDuration(d) => {
+                let (negative, nanos) = chrono_nanos(d);
+                (negative, components(nanos))
+            }
+            FormattedDuration::FormattedStdDuration(d) => (false, components(d.as_nanos())),
+            FormattedDuration::FormattedCalendarDuration { start, end } if end < start => (true, calendar_parts(*end, *start)),
+            FormattedDuration::FormattedCalendarDuration { start, end } => (false, calendar_parts(*start, *end)),
+        }
+    }
+}
+
+/// Whether `d` is negative, and its length in nanoseconds. Unlike
+/// `num_nanoseconds`, this can't overflow.
+fn chrono_nanos(d: &ChronoDuration) -> (bool, u128) {
+    let secs = d.num_seconds();
+    let subsec = (*d - ChronoDuration::seconds(secs)).num_nanoseconds().unwrap_or(0);
+    let nanos = secs as i128 * NANOS_PER_SEC as i128 + subsec as i128;
+    (nanos < 0, nanos.unsigned_abs())
+}
+
+/// Whole calendar years and months from `start`, then days and smaller
+/// units for what is left up to `end`. `start` must not be after `end`.
+fn calendar_parts(start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<(Unit, u128)> {
+    let mut months = (end.year() - start.year()) * 12 + end.month() as i32 - start.month() as i32;
+    let mut anchor = add_months(start, months);
+    if anchor > end {
+        months -= 1;
+        anchor = add_months(start, months);
+    }
+
+    // Average month lengths don't apply here, so days are counted directly.
+    let (_, rest) = chrono_nanos(&(end - anchor));
+    let mut parts = components(rest % Unit::Day.nanos());
+    for (unit, count) in parts.iter_mut() {
+        match unit {
+            Unit::Year => *count = (months / 12) as u128,
+            Unit::Month => *count = (months % 12) as u128,
+            Unit::Day => *count = rest / Unit::Day.nanos(),
+            _ => {}
+        }
+    }
+    parts
+}
+
+/// `date` moved on by `months`, on the same day of the month or the last
+/// day of a shorter month.
+fn add_months(date: DateTime<Utc>, months: i32) -> DateTime<Utc> {
+    let month0 = date.month0() as i32 + months;
+    let year = date.year() + month0.div_euclid(12);
+    let month = month0.rem_euclid(12) as u32 + 1;
+    let day = (1..=date.day())
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+        .expect("Every month has a first day");
+    DateTime::from_utc(day.and_time(date.time()), Utc)
+}
+
+impl FromStr for FormattedDuration {
+    type Err = ApplicationError;
+
+    /// Parses what `Display` writes, like `1h5m3s` or `2years3days`, as well
+    /// as spaced and spelled-out forms like `2 min 30 sec` or `1.5h`. The
+    /// result is a std duration, or a chrono one when it starts with `-`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ApplicationError::InvalidDuration(s.into());
+        let input = s.trim().to_lowercase();
+        if let Some(positive) = input.strip_prefix('-') {
+            let duration = match positive.parse::<FormattedDuration>() {
+                Ok(FormattedDuration::FormattedStdDuration(d)) if !positive.starts_with('-') => d,
+                _ => return Err(invalid()),
+            };
+            let duration = ChronoDuration::from_std(duration).map_err(|_| invalid())?;
+            return Ok((-duration).into());
+        }
+        if input == "0" {
+            return Ok(StdDuration::from_secs(0).into());
+        }
+
+        let mut rest = input.as_str();
+        let mut total: u128 = 0;
+        let mut parsed_any = false;
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+            if rest.is_empty() {
+                break;
+            }
+
+            let number_len = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+            let (number, after) = rest.split_at(number_len);
+            let after = after.trim_start();
+            let unit_len = after.find(|c: char| !c.is_alphabetic()).unwrap_or(after.len());
+            let (unit, after) = after.split_at(unit_len);
+
+            let unit_nanos = UNITS
+                .iter()
+                .find(|(names, _)| names.contains(&unit))
+                .map(|(_, nanos)| *nanos)
+                .ok_or_else(invalid)?;
+            let nanos = scale(number, unit_nanos).ok_or_else(invalid)?;
+            total = total.checked_add(nanos).ok_or_else(invalid)?;
+            parsed_any = true;
+            rest = after;
         }
 
-        let years = secs / 31_557_600; //365.25 days
-        let ydays = secs % 31_557_600;
-        let months = ydays / 2_630_016;
-        let mdays = ydays % 2_630_016;
-        let days = mdays / 86400;
-        let day_secs = mdays % 86400;
-        let hours = day_secs / 3600;
-        let minutes = day_secs % 3600 / 60;
-        let seconds = day_secs % 60;
-        let millis = nanos / 1_000_000;
-        let micros = nanos / 1000 % 1000;
-        let nanosec = nanos % 1000;
-
-        format_plural(f, years, "year")?;
-        format_plural(f, months, "month")?;
-        format_plural(f, days, "day")?;
-        format_item(f, hours, "h")?;
-        format_item(f, minutes, "m")?;
-        format_item(f, seconds, "s")?;
-        format_item(f, millis, "ms")?;
-        format_item(f, micros, "us")?;
-        format_item(f, nanosec, "ns")
+        if !parsed_any {
+            return Err(invalid());
+        }
+        let secs = u64::try_from(total / NANOS_PER_SEC).map_err(|_| invalid())?;
+        Ok(StdDuration::new(secs, (total % NANOS_PER_SEC) as u32).into())
     }
 }
 
-fn format_plural(f: &mut std::fmt::Formatter<'_>, item: i64, name: &str) -> std::fmt::Result {
-    if item > 0 {
-        if item > 1 {
-            write!(f, "{}{}s", item, name)
-        } else {
-            write!(f, "{}{}", item, name)
+/// `number` units of `unit_nanos` each, where `number` may have a decimal
+/// fraction. Digits finer than a nanosecond are dropped.
+fn scale(number: &str, unit_nanos: u128) -> Option<u128> {
+    let (whole, fraction) = match number.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (number, ""),
+    };
+    if (whole.is_empty() && fraction.is_empty()) || fraction.contains('.') {
+        return None;
+    }
+
+    let whole: u128 = if whole.is_empty() { 0 } else { whole.parse().ok()? };
+    let mut nanos = whole.checked_mul(unit_nanos)?;
+    let mut place = unit_nanos;
+    for digit in fraction.chars() {
+        place /= 10;
+        nanos = nanos.checked_add(digit.to_digit(10)? as u128 * place)?;
+    }
+    Some(nanos)
+}
+
+impl TryFrom<FormattedDuration> for StdDuration {
+    type Error = ApplicationError;
+
+    fn try_from(duration: FormattedDuration) -> Result<Self, Self::Error> {
+        match duration {
+            FormattedDuration::FormattedStdDuration(d) => Ok(d),
+            FormattedDuration::FormattedChronoDuration(d) => d
+                .to_std()
+                .map_err(|_| ApplicationError::InvalidDuration(FormattedDuration::from(d).to_string())),
+            FormattedDuration::FormattedCalendarDuration { start, end } => (end - start)
+                .to_std()
+                .map_err(|_| ApplicationError::InvalidDuration(FormattedDuration::between(start, end).to_string())),
         }
-    } else {
-        Ok(())
     }
 }
 
-fn format_item (f: &mut std::fmt::Formatter<'_>, item: i64, name: &str) -> std::fmt::Result {
-    if item > 0 {
-        write!(f, "{}{}", item, name)
-    } else {
-        Ok(())
+impl TryFrom<FormattedDuration> for ChronoDuration {
+    type Error = ApplicationError;
+
+    fn try_from(duration: FormattedDuration) -> Result<Self, Self::Error> {
+        match duration {
+            FormattedDuration::FormattedChronoDuration(d) => Ok(d),
+            FormattedDuration::FormattedStdDuration(d) => ChronoDuration::from_std(d)
+                .map_err(|_| ApplicationError::InvalidDuration(FormattedDuration::from(d).to_string())),
+            FormattedDuration::FormattedCalendarDuration { start, end } => Ok(end - start),
+        }
+    }
+}
+
+impl Display for FormattedDuration {
+    /// Writes the duration in the compact style, like `1h5m3s`, with a
+    /// leading `-` when negative.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.format(&DurationFormat::default()))
     }
 }
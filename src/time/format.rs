@@ -0,0 +1,280 @@
+use crate::locale::{messages, Locale};
+
+use super::duration::NANOS_PER_SEC;
+
+/// The units a duration is broken into, largest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    Millisecond,
+    Microsecond,
+    Nanosecond,
+}
+
+pub const ALL_UNITS: [Unit; 9] = [
+    Unit::Year,
+    Unit::Month,
+    Unit::Day,
+    Unit::Hour,
+    Unit::Minute,
+    Unit::Second,
+    Unit::Millisecond,
+    Unit::Microsecond,
+    Unit::Nanosecond,
+];
+
+impl Unit {
+    /// Length in nanoseconds. Years are 365.25 days and months a twelfth
+    /// of that.
+    pub fn nanos(&self) -> u128 {
+        match self {
+            Unit::Year => 31_557_600 * NANOS_PER_SEC,
+            Unit::Month => 2_630_016 * NANOS_PER_SEC,
+            Unit::Day => 86_400 * NANOS_PER_SEC,
+            Unit::Hour => 3_600 * NANOS_PER_SEC,
+            Unit::Minute => 60 * NANOS_PER_SEC,
+            Unit::Second => NANOS_PER_SEC,
+            Unit::Millisecond => 1_000_000,
+            Unit::Microsecond => 1_000,
+            Unit::Nanosecond => 1,
+        }
+    }
+
+    fn symbol(&self, count: u128) -> &'static str {
+        match self {
+            Unit::Year if count == 1 => "year",
+            Unit::Year => "years",
+            Unit::Month if count == 1 => "month",
+            Unit::Month => "months",
+            Unit::Day if count == 1 => "day",
+            Unit::Day => "days",
+            Unit::Hour => "h",
+            Unit::Minute => "m",
+            Unit::Second => "s",
+            Unit::Millisecond => "ms",
+            Unit::Microsecond => "us",
+            Unit::Nanosecond => "ns",
+        }
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            Unit::Year => "year",
+            Unit::Month => "month",
+            Unit::Day => "day",
+            Unit::Hour => "hour",
+            Unit::Minute => "minute",
+            Unit::Second => "second",
+            Unit::Millisecond => "millisecond",
+            Unit::Microsecond => "microsecond",
+            Unit::Nanosecond => "nanosecond",
+        }
+    }
+
+    /// The unit's name for `count` of it, like "3 minutes".
+    fn words(&self, count: u128, locale: Locale) -> String {
+        let number = if count == 1 { "one" } else { "other" };
+        let key = format!("duration.{}.{}", self.key(), number);
+        messages::text(locale, &key, &[("count", &count)])
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationStyle {
+    /// Unit symbols run together, like "3m12s". This is what `Display` uses.
+    Compact,
+    /// Spelled-out units, like "3 minutes, 12 seconds".
+    Long,
+    /// The largest unit, rounded, like "about 3 minutes", or "in about 3
+    /// minutes" when negative.
+    Humanized,
+    /// The long form as time passed, like "3 minutes ago", or still to come
+    /// when negative.
+    Relative,
+    /// An ISO-8601 duration, like "PT3M12S". Ignores the other options.
+    Iso8601,
+}
+
+/// How to write a `FormattedDuration`.
+#[derive(Debug, Clone)]
+pub struct DurationFormat {
+    style: DurationStyle,
+    max_units: Option<usize>,
+    separator: Option<String>,
+    locale: Locale,
+}
+
+impl DurationFormat {
+    pub fn new(style: DurationStyle) -> Self {
+        Self {
+            style,
+            max_units: None,
+            separator: None,
+            locale: Locale::default(),
+        }
+    }
+
+    /// Keeps only the largest `max_units` non-zero units; the rest is cut off.
+    pub fn with_max_units(mut self, max_units: usize) -> Self {
+        self.max_units = Some(max_units.max(1));
+        self
+    }
+
+    /// Puts `separator` between units instead of the style's own.
+    pub fn with_separator(mut self, separator: &str) -> Self {
+        self.separator = Some(separator.into());
+        self
+    }
+
+    /// Names units in `locale`. Compact and ISO-8601 output is the same in
+    /// every locale.
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    pub fn style(&self) -> DurationStyle {
+        self.style
+    }
+
+    /// Writes a duration made of `parts`, which is in the past unless
+    /// `negative`.
+    pub fn format(&self, negative: bool, parts: &[(Unit, u128)]) -> String {
+        let negative = negative && parts.iter().any(|(_, count)| *count > 0);
+        match self.style {
+            DurationStyle::Compact => {
+                let text = self.join(parts, "", |unit, count| format!("{}{}", count, unit.symbol(count)));
+                let text = if text.is_empty() { "0s".into() } else { text };
+                if negative {
+                    format!("-{}", text)
+                } else {
+                    text
+                }
+            }
+            DurationStyle::Long => {
+                let text = self.long(parts);
+                if negative {
+                    format!("-{}", text)
+                } else {
+                    text
+                }
+            }
+            DurationStyle::Humanized => {
+                let text = self.humanized(parts);
+                if negative {
+                    messages::text(self.locale, "duration.from_now", &[("duration", &text)])
+                } else {
+                    text
+                }
+            }
+            DurationStyle::Relative => {
+                let key = if negative { "duration.from_now" } else { "duration.ago" };
+                messages::text(self.locale, key, &[("duration", &self.long(parts))])
+            }
+            DurationStyle::Iso8601 => iso8601(negative, parts),
+        }
+    }
+
+    fn long(&self, parts: &[(Unit, u128)]) -> String {
+        let text = self.join(parts, ", ", |unit, count| unit.words(count, self.locale));
+        if text.is_empty() {
+            Unit::Second.words(0, self.locale)
+        } else {
+            text
+        }
+    }
+
+    /// Rounds to the largest unit, using average lengths for what comes
+    /// after it. Rounding up to a whole next unit carries into it, so
+    /// 59m45s is about 1 hour rather than about 60 minutes.
+    fn humanized(&self, parts: &[(Unit, u128)]) -> String {
+        let largest = parts.iter().position(|(_, count)| *count > 0).filter(|i| *i <= 5);
+        match largest {
+            Some(mut i) => {
+                let mut nanos: u128 = parts[i..].iter().map(|(unit, count)| unit.nanos() * count).sum();
+                let mut count = (nanos + parts[i].0.nanos() / 2) / parts[i].0.nanos();
+                while i > 0 && count * parts[i].0.nanos() >= parts[i - 1].0.nanos() {
+                    nanos = count * parts[i].0.nanos();
+                    i -= 1;
+                    count = (nanos + parts[i].0.nanos() / 2) / parts[i].0.nanos();
+                }
+                let unit = parts[i].0;
+                messages::text(self.locale, "duration.about", &[("duration", &unit.words(count, self.locale))])
+            }
+            None => messages::text(self.locale, "duration.less_than_second", &[]),
+        }
+    }
+
+    fn join(&self, parts: &[(Unit, u128)], separator: &str, write: impl Fn(Unit, u128) -> String) -> String {
+        let separator = self.separator.as_deref().unwrap_or(separator);
+        parts
+            .iter()
+            .filter(|(_, count)| *count > 0)
+            .take(self.max_units.unwrap_or(ALL_UNITS.len()))
+            .map(|(unit, count)| write(*unit, *count))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+}
+
+impl Default for DurationFormat {
+    fn default() -> Self {
+        Self::new(DurationStyle::Compact)
+    }
+}
+
+/// Breaks `nanos` into a count of every unit, largest first.
+pub fn components(nanos: u128) -> Vec<(Unit, u128)> {
+    let mut rest = nanos;
+    ALL_UNITS
+        .iter()
+        .map(|unit| {
+            let count = rest / unit.nanos();
+            rest %= unit.nanos();
+            (*unit, count)
+        })
+        .collect()
+}
+
+fn iso8601(negative: bool, parts: &[(Unit, u128)]) -> String {
+    let count = |unit: Unit| parts.iter().find(|(u, _)| *u == unit).map_or(0, |(_, c)| *c);
+
+    let mut date = String::new();
+    for (unit, designator) in [(Unit::Year, 'Y'), (Unit::Month, 'M'), (Unit::Day, 'D')] {
+        if count(unit) > 0 {
+            date.push_str(&format!("{}{}", count(unit), designator));
+        }
+    }
+
+    let mut time = String::new();
+    for (unit, designator) in [(Unit::Hour, 'H'), (Unit::Minute, 'M')] {
+        if count(unit) > 0 {
+            time.push_str(&format!("{}{}", count(unit), designator));
+        }
+    }
+    let subsec = count(Unit::Millisecond) * 1_000_000 + count(Unit::Microsecond) * 1_000 + count(Unit::Nanosecond);
+    if count(Unit::Second) > 0 || subsec > 0 {
+        let fraction = format!("{:09}", subsec);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            time.push_str(&format!("{}S", count(Unit::Second)));
+        } else {
+            time.push_str(&format!("{}.{}S", count(Unit::Second), fraction));
+        }
+    }
+
+    if date.is_empty() && time.is_empty() {
+        time.push_str("0S");
+    }
+    let sign = if negative { "-" } else { "" };
+    if time.is_empty() {
+        format!("{}P{}", sign, date)
+    } else {
+        format!("{}P{}T{}", sign, date, time)
+    }
+}
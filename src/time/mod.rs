@@ -1 +1,3 @@
-pub mod duration;
\ No newline at end of file
+pub mod clock;
+pub mod duration;
+pub mod format;
@@ -0,0 +1,89 @@
+use std::io::Write;
+
+use word_game::{
+    association::{
+        cooccurrence::CooccurrenceModel, AssociationModel, AssociationModelEnum, AssociationModelFactory,
+        AssociationModelType,
+    },
+    locale::Locale,
+};
+
+const TABLE: &str = "
+# fruit
+apple fruit red pie
+cherry fruit red pie
+fruit apple   # listed both ways
+";
+
+#[test]
+fn relations_are_symmetric_and_weighted_by_co_occurrence() {
+    let model = CooccurrenceModel::new(Locale::English, TABLE);
+    assert_eq!(model.similarity("red", "apple"), model.similarity("apple", "red"));
+    assert!(model.similarity("apple", "fruit") > model.similarity("apple", "red"));
+    assert!(model.similarity("fruit", "red") > 0.0, "siblings co-occur");
+    assert_eq!(model.similarity("apple", "kiwi"), 0.0);
+    assert_eq!(model.related("APPLE")[0].0, "fruit");
+}
+
+#[test]
+fn folds_words_like_the_games_locale() {
+    let turkish = CooccurrenceModel::new(Locale::Turkish, "ılık sıcak\nistanbul şehir");
+    assert!(turkish.similarity("ILIK", "SICAK") > 0.0);
+    assert_eq!(turkish.related("İSTANBUL")[0].0, "şehir");
+    assert!(turkish.related("ISTANBUL").is_empty(), "dotless I is another letter");
+
+    // Words come back spelled as listed, not folded.
+    let german = CooccurrenceModel::new(Locale::German, "süß zucker");
+    assert_eq!(german.related("SÜSS")[0].0, "zucker");
+    assert_eq!(german.related("Zucker")[0].0, "süß");
+}
+
+#[test]
+fn finds_words_related_to_both() {
+    let model = CooccurrenceModel::new(Locale::English, TABLE);
+    let both: Vec<_> = model.related_to_both("apple", "cherry").into_iter().map(|(w, _)| w).collect();
+    assert_eq!(both, vec!["fruit", "pie", "red"]);
+    assert!(model.related_to_both("apple", "kiwi").is_empty());
+}
+
+#[test]
+fn ranks_candidates_against_context() {
+    let model = CooccurrenceModel::new(Locale::English, TABLE);
+    let ranked = model.rank(&["kiwi", "red", "fruit"], &["apple", "cherry"]);
+    let words: Vec<_> = ranked.iter().map(|(w, _)| *w).collect();
+    assert_eq!(words, vec!["fruit", "red", "kiwi"]);
+    assert_eq!(ranked[2].1, 0.0);
+}
+
+#[test]
+fn loads_alternative_tables_from_disk() {
+    let path = std::env::temp_dir().join(format!("word-game-associations-{}.txt", std::process::id()));
+    std::fs::File::create(&path).unwrap().write_all(b"sun moon star\n").unwrap();
+
+    let model: AssociationModelEnum = AssociationModelFactory::create(AssociationModelType::File {
+        locale: Locale::German,
+        path: path.to_string_lossy().into(),
+    })
+    .unwrap();
+    assert_eq!(model.vocabulary(), vec!["moon", "star", "sun"]);
+    assert_eq!(model.locale(), Locale::German);
+    std::fs::remove_file(&path).unwrap();
+
+    let missing = AssociationModelType::File {
+        locale: Locale::English,
+        path: "/no/such/table".into(),
+    };
+    assert!(AssociationModelFactory::create(missing).is_err());
+}
+
+#[test]
+fn bundled_tables_cover_the_basics() {
+    let model = AssociationModelFactory::create(AssociationModelType::Bundled(Locale::English)).unwrap();
+    assert!(model.vocabulary().len() > 100);
+    assert!(model.similarity("banana", "yellow") > 0.0);
+
+    let german = AssociationModelFactory::create(AssociationModelType::Bundled(Locale::German)).unwrap();
+    assert!(german.similarity("banane", "gelb") > 0.0);
+    let turkish = AssociationModelFactory::create(AssociationModelType::Bundled(Locale::Turkish)).unwrap();
+    assert!(turkish.similarity("limon", "sarı") > 0.0);
+}
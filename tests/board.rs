@@ -0,0 +1,72 @@
+
+use word_game::{game::board::BoardGuess, locale::Locale};
+
+use common::word_game;
+
+mod common;
+
+#[test]
+fn aligns_columns_and_hides_unrevealed_guesses() {
+    let mut word_game = word_game();
+    word_game.create_player("alice", "Alice").unwrap();
+    word_game.create_player("bob", "Bob").unwrap();
+    let game = word_game.create_game().unwrap();
+    word_game.join_game("alice", &game.id).unwrap();
+    word_game.join_game("bob", &game.id).unwrap();
+    word_game.submit_guess("alice", "watermelon").unwrap();
+    word_game.submit_guess("bob", "fig").unwrap();
+    word_game.submit_guess("alice", "fruit").unwrap();
+
+    let board = word_game.board(&word_game.get_game(&game.id).unwrap());
+    assert_eq!(board.rounds[0].player_2_guess, BoardGuess::Revealed("fig".into()));
+    assert_eq!(board.rounds[1].player_1_guess, BoardGuess::Hidden);
+    assert_eq!(board.rounds[1].player_2_guess, BoardGuess::Missing);
+
+    let text = format!("{}", board);
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines[0], "Round  alice       bob");
+    assert_eq!(lines[1], "1      watermelon  fig");
+    assert_eq!(lines[2], "2      (ready)     ...");
+    assert!(!text.contains("fruit"));
+}
+
+#[test]
+fn marks_the_winning_round() {
+    let mut word_game = word_game();
+    word_game.create_player("alice", "Alice").unwrap();
+    word_game.create_player("bob", "Bob").unwrap();
+    let game = word_game.create_game().unwrap();
+    word_game.join_game("alice", &game.id).unwrap();
+    word_game.join_game("bob", &game.id).unwrap();
+    word_game.submit_guess("alice", "Fruit").unwrap();
+    word_game.submit_guess("bob", "fruit").unwrap();
+
+    let board = word_game.board(&word_game.get_game(&game.id).unwrap()).with_names("Alice", "Bob");
+    assert!(board.complete);
+    assert!(board.rounds[0].matched);
+    let text = format!("{}", board);
+    assert!(text.contains("1      Fruit  fruit  <- match!"));
+}
+
+#[test]
+fn speaks_the_games_language() {
+    let mut word_game = word_game();
+    for username in ["anna", "ben"] {
+        word_game.create_player(username, username).unwrap();
+        word_game.set_player_locales(username, &[Locale::German]).unwrap();
+    }
+    let game = word_game.create_game_in(Locale::German).unwrap();
+    word_game.join_game("anna", &game.id).unwrap();
+    word_game.join_game("ben", &game.id).unwrap();
+    word_game.submit_guess("anna", "baum").unwrap();
+    word_game.submit_guess("ben", "apfel").unwrap();
+    word_game.submit_guess("anna", "apfel").unwrap();
+    word_game.submit_guess("ben", "apfel").unwrap();
+
+    let text = format!("{}", word_game.board(&word_game.get_game(&game.id).unwrap()));
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines[0], "Runde  anna   ben");
+    assert_eq!(lines[1].trim_end(), "1      baum   apfel");
+    assert_eq!(lines[2], "2      apfel  apfel  <- Treffer!");
+    assert!(lines[3].starts_with("Dauer: "));
+}
@@ -0,0 +1,109 @@
+
+use word_game::{
+    association::{cooccurrence::CooccurrenceModel, AssociationModelEnum, AssociationModelFactory, AssociationModelType},
+    bot::{BotPlayer, BotStrategyFactory, Difficulty},
+    dictionary::{DictionaryConfig, DictionaryFactory, DictionaryType},
+    locale::Locale,
+};
+
+use common::word_game;
+
+mod common;
+
+fn bundled() -> AssociationModelEnum {
+    AssociationModelFactory::create(AssociationModelType::Bundled(Locale::English)).unwrap()
+}
+
+#[test]
+fn hard_bot_guesses_between_the_last_two_words() {
+    let model = bundled();
+    let mut strategy = BotStrategyFactory::create(Difficulty::Hard, bundled());
+    let history = [("banana".to_string(), "lemon".to_string())];
+
+    for _ in 0..10 {
+        let guess = strategy.guess(&history).unwrap();
+        assert!(model.similarity("banana", &guess) > 0.0, "{}", guess);
+        assert!(model.similarity("lemon", &guess) > 0.0, "{}", guess);
+    }
+}
+
+#[test]
+fn bots_never_repeat_a_word() {
+    for difficulty in [Difficulty::Easy, Difficulty::Hard] {
+        let mut strategy = BotStrategyFactory::create(difficulty, bundled());
+        let history = [
+            ("apple".to_string(), "banana".to_string()),
+            ("fruit".to_string(), "yellow".to_string()),
+        ];
+        for _ in 0..10 {
+            let guess = strategy.guess(&history).unwrap();
+            assert!(history.iter().all(|(a, b)| a != &guess && b != &guess), "{}", guess);
+        }
+    }
+}
+
+#[test]
+fn bot_only_sees_revealed_rounds() {
+    let mut word_game = word_game();
+    word_game.create_player("alice", "Alice").unwrap();
+    word_game.create_player("computer", "Computer").unwrap();
+    let game = word_game.create_game().unwrap();
+    word_game.join_game("alice", &game.id).unwrap();
+    word_game.join_game("computer", &game.id).unwrap();
+    let mut bot = BotPlayer::new("computer", Difficulty::Hard, Locale::English).unwrap();
+    let model = bundled();
+
+    word_game.submit_guess("alice", "apple").unwrap();
+    word_game.submit_guess(bot.username(), "banana").unwrap();
+
+    // Alice's guess for round 2 is still hidden. Had the bot seen it, it
+    // would go for words around "milk" instead of the revealed round.
+    word_game.submit_guess("alice", "milk").unwrap();
+    for _ in 0..10 {
+        let guess = bot.guess(&word_game.get_game(&game.id).unwrap());
+        assert!(model.similarity("apple", &guess) > 0.0, "{}", guess);
+        assert_eq!(model.similarity("milk", &guess), 0.0, "{}", guess);
+    }
+}
+
+#[test]
+fn bots_play_in_the_games_language() {
+    let dictionary = DictionaryFactory::create(DictionaryType::Bundled(Locale::German), DictionaryConfig::default());
+    let mut word_game = word_game().with_dictionary(dictionary.unwrap());
+    for username in ["anna", "computer"] {
+        word_game.create_player(username, username).unwrap();
+        word_game.set_player_locales(username, &[Locale::German]).unwrap();
+    }
+    let game = word_game.create_game_in(Locale::German).unwrap();
+    word_game.join_game("anna", &game.id).unwrap();
+    word_game.join_game("computer", &game.id).unwrap();
+    let mut bot = BotPlayer::new("computer", Difficulty::Easy, Locale::German).unwrap();
+
+    // The German dictionary turns away any word the bot didn't get from
+    // the German table.
+    for guess in ["apfel", "meer", "winter"] {
+        word_game.submit_guess("anna", guess).unwrap();
+        let answer = bot.guess(&word_game.get_game(&game.id).unwrap());
+        word_game.submit_guess(bot.username(), &answer).unwrap();
+        if word_game.is_game_complete(&game.id).unwrap() {
+            break;
+        }
+    }
+}
+
+#[test]
+fn falls_back_to_words_from_its_own_dictionary() {
+    // A table of English words, none of which a Turkish game accepts.
+    let model = AssociationModelEnum::CooccurrenceModel(CooccurrenceModel::new(Locale::Turkish, "banana apple lemon"));
+    let dictionary = || DictionaryFactory::create(DictionaryType::Bundled(Locale::Turkish), DictionaryConfig::default()).unwrap();
+    let mut bot = BotPlayer::with_model("computer", Difficulty::Hard, model, dictionary());
+
+    let mut word_game = word_game();
+    word_game.create_player("computer", "Computer").unwrap();
+    word_game.set_player_locales("computer", &[Locale::Turkish]).unwrap();
+    let game = word_game.start_game("computer", Locale::Turkish).unwrap();
+    for _ in 0..10 {
+        let guess = bot.guess(&game);
+        assert!(dictionary().contains(&guess), "{}", guess);
+    }
+}
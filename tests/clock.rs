@@ -0,0 +1,42 @@
+use chrono::Duration;
+use word_game::time::clock::ManualClock;
+
+use common::{start, word_game_at};
+
+mod common;
+
+#[test]
+fn games_get_sequential_ids() {
+    let clock = ManualClock::new(start());
+    let mut word_game = word_game_at(&clock);
+    assert_eq!(word_game.create_game().unwrap().id, "game-1");
+    assert_eq!(word_game.create_game().unwrap().id, "game-2");
+    assert_eq!(word_game.next_id(), "game-3");
+}
+
+#[test]
+fn games_are_timed_by_the_clock() {
+    let clock = ManualClock::new(start());
+    let mut word_game = word_game_at(&clock);
+    word_game.create_player("alice", "Alice").unwrap();
+    word_game.create_player("bob", "Bob").unwrap();
+    let game = word_game.create_game().unwrap();
+    word_game.join_game("alice", &game.id).unwrap();
+    word_game.join_game("bob", &game.id).unwrap();
+    assert_eq!(game.start_time(), start().timestamp());
+
+    clock.advance(Duration::seconds(75));
+    word_game.submit_guess("alice", "apple").unwrap();
+    word_game.submit_guess("bob", "pear").unwrap();
+    let board = word_game.board(&word_game.get_game(&game.id).unwrap());
+    assert_eq!(board.duration.to_string(), "1m15s");
+
+    clock.advance(Duration::seconds(45));
+    word_game.submit_guess("alice", "fruit").unwrap();
+    word_game.submit_guess("bob", "fruit").unwrap();
+    let game = word_game.get_game(&game.id).unwrap();
+    assert_eq!(game.end_time(), Some(start().timestamp() + 120));
+
+    clock.advance(Duration::hours(1));
+    assert_eq!(word_game.board(&game).duration.to_string(), "2m", "finished games stop the clock");
+}
@@ -0,0 +1,70 @@
+//! Games and harness shared by the tests.
+
+// Each test binary compiles its own copy and uses only part of it.
+#![allow(dead_code)]
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use chrono::prelude::*;
+use tokio::sync::oneshot;
+use word_game::{
+    database::{DatabaseFactory, DatabaseType},
+    game::WordGame,
+    id::{IdGeneratorFactory, IdGeneratorType},
+    time::clock::{ClockEnum, ManualClock},
+    AppResult,
+};
+
+/// Resolves when the test script is done, for a runner's `run_until`.
+pub type Shutdown = Pin<Box<dyn Future<Output = ()>>>;
+
+/// An in-memory game whose codes run `game-1`, `game-2`, ...
+pub fn word_game() -> WordGame {
+    let database = DatabaseFactory::create(DatabaseType::InMemory);
+    WordGame::new(Arc::new(Mutex::new(database)))
+        .with_id_generator(IdGeneratorFactory::create(IdGeneratorType::Sequential("game".into())))
+}
+
+/// Like `word_game`, with time kept by `clock`.
+pub fn word_game_at(clock: &ManualClock) -> WordGame {
+    word_game().with_clock(ClockEnum::from(clock.clone()))
+}
+
+/// `word_game`, shared the way runners take it.
+pub fn new_game() -> Arc<Mutex<WordGame>> {
+    Arc::new(Mutex::new(word_game()))
+}
+
+/// `word_game_at`, shared the way runners take it.
+pub fn new_game_at(clock: &ManualClock) -> Arc<Mutex<WordGame>> {
+    Arc::new(Mutex::new(word_game_at(clock)))
+}
+
+/// When the games on a manual clock start.
+pub fn start() -> DateTime<Utc> {
+    Utc.ymd(2024, 5, 1).and_hms(12, 0, 0)
+}
+
+/// Runs the runner started by `serve` until `script` is done, then checks
+/// that it stopped without an error.
+pub async fn serve_during<S, R, Fut>(serve: S, script: Fut)
+where
+    S: FnOnce(Shutdown) -> R,
+    R: Future<Output = AppResult<()>>,
+    Fut: Future<Output = ()>,
+{
+    let (stop, stopped) = oneshot::channel::<()>();
+    let script = async move {
+        script.await;
+        stop.send(()).unwrap();
+    };
+    let shutdown = Box::pin(async {
+        let _ = stopped.await;
+    });
+    let (result, _) = tokio::join!(serve(shutdown), script);
+    result.unwrap();
+}
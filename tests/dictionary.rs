@@ -0,0 +1,87 @@
+use std::sync::{Arc, Mutex};
+
+use word_game::{
+    database::{DatabaseFactory, DatabaseType},
+    dictionary::{
+        edit_distance, word_list::WordListDictionary, Dictionary, DictionaryConfig, DictionaryFactory,
+        DictionaryType, InvalidGuess,
+    },
+    game::{GuessCorrection, WordGame},
+    locale::Locale,
+    ApplicationError,
+};
+
+const WORDS: &str = "apple\nample\napply\n# comment\nice cream\nice\ncream\nmaple\n";
+
+fn dictionary(config: DictionaryConfig) -> WordListDictionary {
+    WordListDictionary::new(Locale::English, WORDS, config)
+}
+
+#[test]
+fn measures_edit_distance() {
+    assert_eq!(edit_distance("apple", "apple"), 0);
+    assert_eq!(edit_distance("aple", "apple"), 1);
+    assert_eq!(edit_distance("appel", "apple"), 1, "swapped letters count once");
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "abc"), 3);
+}
+
+#[test]
+fn rejects_blank_phrases_and_non_words() {
+    let dictionary = dictionary(DictionaryConfig::default());
+    assert_eq!(dictionary.validate("  Apple ").unwrap(), "Apple");
+    assert!(matches!(dictionary.validate("   "), Err(ApplicationError::InvalidGuess(InvalidGuess::Empty))));
+    assert!(matches!(dictionary.validate("ice cream"), Err(ApplicationError::InvalidGuess(InvalidGuess::NotASingleWord))));
+
+    let error = dictionary.validate("aple").unwrap_err();
+    assert!(matches!(error, ApplicationError::InvalidGuess(InvalidGuess::NotAWord { .. })));
+    let message = error.user_message(Locale::English);
+    assert!(message.contains("Did you mean ample, apple, maple?"), "{}", message);
+    assert!(!dictionary.validate("xqzt").unwrap_err().user_message(Locale::English).contains("Did you mean"));
+}
+
+#[test]
+fn allows_phrases_of_known_words_when_configured() {
+    let config = DictionaryConfig {
+        allow_phrases: true,
+        max_suggestions: 1,
+    };
+    let dictionary = dictionary(config);
+    assert_eq!(dictionary.validate("ice  cream").unwrap(), "ice  cream");
+    let error = dictionary.validate("ice craem").unwrap_err();
+    let message = error.user_message(Locale::English);
+    assert!(message.ends_with("Did you mean cream?"), "{}", message);
+}
+
+#[test]
+fn loads_bundled_word_lists_by_language() {
+    let english = DictionaryFactory::create(DictionaryType::Bundled(Locale::English), DictionaryConfig::default()).unwrap();
+    assert_eq!(english.locale(), Locale::English);
+    assert!(english.contains("Banana"));
+    let turkish = DictionaryFactory::create(DictionaryType::Bundled(Locale::Turkish), DictionaryConfig::default()).unwrap();
+    assert!(turkish.contains("elma"));
+    assert!(!turkish.contains("banana"));
+}
+
+#[test]
+fn word_game_validates_guesses() {
+    let database = DatabaseFactory::create(DatabaseType::InMemory);
+    let english = DictionaryFactory::create(DictionaryType::Bundled(Locale::English), DictionaryConfig::default()).unwrap();
+    let mut word_game = WordGame::new(Arc::new(Mutex::new(database))).with_dictionary(english);
+    word_game.create_player("alice", "Alice").unwrap();
+    word_game.create_player("bob", "Bob").unwrap();
+    let game = word_game.create_game().unwrap();
+    word_game.join_game("alice", &game.id).unwrap();
+    word_game.join_game("bob", &game.id).unwrap();
+
+    let not_a_word = |result: Result<(), ApplicationError>| matches!(result, Err(ApplicationError::InvalidGuess(InvalidGuess::NotAWord { .. })));
+    assert!(not_a_word(word_game.submit_guess("alice", "bananna").map(|_| ())));
+    assert!(matches!(word_game.submit_guess("alice", ""), Err(ApplicationError::InvalidGuess(InvalidGuess::Empty))));
+    word_game.submit_guess("alice", " banana ").unwrap();
+    assert!(not_a_word(word_game.correct_guess("alice", 1, "lemonn").map(|_| ())));
+    assert_eq!(word_game.get_guesses(&game.id).unwrap()[0].0.as_deref(), Some("banana"));
+
+    // Once the round is sealed, that is what a correction hears, misspelt or not.
+    word_game.submit_guess("bob", "apple").unwrap();
+    assert_eq!(word_game.correct_guess("alice", 1, "lemonn").unwrap(), GuessCorrection::Locked);
+}
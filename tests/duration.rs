@@ -0,0 +1,182 @@
+use std::{convert::TryFrom, time::Duration as StdDuration};
+
+use chrono::{prelude::*, Duration as ChronoDuration};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use word_game::{
+    locale::Locale,
+    time::{
+        duration::FormattedDuration,
+        format::{DurationFormat, DurationStyle},
+    },
+    ApplicationError,
+};
+
+fn parse(s: &str) -> StdDuration {
+    let duration: FormattedDuration = s.parse().unwrap_or_else(|e| panic!("{}: {}", s, e));
+    StdDuration::try_from(duration).unwrap()
+}
+
+#[test]
+fn parses_display_format() {
+    assert_eq!(parse("0s"), StdDuration::from_secs(0));
+    assert_eq!(parse("1h5m3s"), StdDuration::from_secs(3903));
+    assert_eq!(parse("2days3h"), StdDuration::from_secs(2 * 86_400 + 3 * 3600));
+    assert_eq!(parse("1year"), StdDuration::from_secs(31_557_600));
+    assert_eq!(parse("3s12ms5us7ns"), StdDuration::new(3, 12_005_007));
+}
+
+#[test]
+fn parses_common_variants() {
+    assert_eq!(parse("45s"), StdDuration::from_secs(45));
+    assert_eq!(parse("2m30s"), parse("2 min 30 sec"));
+    assert_eq!(parse("2 minutes, 30 seconds"), StdDuration::from_secs(150));
+    assert_eq!(parse("1.5h"), StdDuration::from_secs(5400));
+    assert_eq!(parse(".5s"), StdDuration::from_millis(500));
+    assert_eq!(parse(" 1 Week "), StdDuration::from_secs(604_800));
+    assert_eq!(parse("0"), StdDuration::from_secs(0));
+}
+
+#[test]
+fn rejects_malformed_durations() {
+    for input in ["", "s", "12", "5 parsecs", "1..5h", "--3s", "-", "1h 2", "99999999999999999999999years"] {
+        let result = input.parse::<FormattedDuration>();
+        assert!(matches!(result, Err(ApplicationError::InvalidDuration(_))), "{:?} parsed", input);
+    }
+}
+
+#[test]
+fn converts_to_std_and_chrono() {
+    let duration: FormattedDuration = "1m30s".parse().unwrap();
+    assert_eq!(ChronoDuration::try_from(duration).unwrap(), ChronoDuration::seconds(90));
+    let negative = FormattedDuration::from(ChronoDuration::seconds(-5));
+    assert!(StdDuration::try_from(negative).is_err());
+}
+
+#[test]
+fn display_and_parse_round_trip() {
+    let mut rng = StdRng::seed_from_u64(42);
+    for _ in 0..2000 {
+        let secs = match rng.gen_range(0..3) {
+            0 => rng.gen_range(0..120),
+            1 => rng.gen_range(0..86_400 * 3),
+            _ => rng.gen_range(0..31_557_600 * 50),
+        };
+        let nanos = if rng.gen_bool(0.5) { 0 } else { rng.gen_range(0..1_000_000_000) };
+        let duration = StdDuration::new(secs, nanos);
+        let text = FormattedDuration::from(duration).to_string();
+        assert_eq!(parse(&text), duration, "{}", text);
+        assert_eq!(FormattedDuration::from(parse(&text)).to_string(), text);
+    }
+}
+
+fn game_length() -> FormattedDuration {
+    StdDuration::new(192, 456_789_012).into()
+}
+
+#[test]
+fn limits_units() {
+    let format = DurationFormat::new(DurationStyle::Compact).with_max_units(2).with_separator(" ");
+    assert_eq!(game_length().format(&format), "3m 12s");
+    assert_eq!(game_length().format(&DurationFormat::default()), "3m12s456ms789us12ns");
+    assert_eq!(FormattedDuration::from(StdDuration::from_secs(0)).format(&format), "0s");
+}
+
+#[test]
+fn writes_long_and_humanized_forms() {
+    let long = DurationFormat::new(DurationStyle::Long).with_max_units(2);
+    assert_eq!(game_length().format(&long), "3 minutes, 12 seconds");
+    assert_eq!(FormattedDuration::from(StdDuration::from_secs(61)).format(&long), "1 minute, 1 second");
+
+    let humanized = DurationFormat::new(DurationStyle::Humanized);
+    assert_eq!(game_length().format(&humanized), "about 3 minutes");
+    assert_eq!(FormattedDuration::from(StdDuration::from_secs(150)).format(&humanized), "about 3 minutes");
+    assert_eq!(FormattedDuration::from(StdDuration::from_millis(20)).format(&humanized), "less than a second");
+}
+
+#[test]
+fn humanized_rounding_carries_into_the_next_unit() {
+    let humanized = DurationFormat::new(DurationStyle::Humanized);
+    let about = |secs: u64| FormattedDuration::from(StdDuration::from_secs(secs)).format(&humanized);
+    assert_eq!(about(59), "about 59 seconds");
+    assert_eq!(about(59 * 60 + 29), "about 59 minutes");
+    assert_eq!(about(59 * 60 + 30), "about 1 hour");
+    assert_eq!(about(59 * 60 + 45), "about 1 hour");
+    assert_eq!(about(23 * 3600 + 29 * 60), "about 23 hours");
+    assert_eq!(about(23 * 3600 + 50 * 60), "about 1 day");
+    assert_eq!(about(300 * 86_400), "about 10 months");
+    assert_eq!(about(364 * 86_400), "about 1 year");
+
+    let ahead = FormattedDuration::from(ChronoDuration::minutes(-5));
+    assert_eq!(ahead.format(&humanized), "in about 5 minutes");
+}
+
+#[test]
+fn writes_relative_times() {
+    let relative = DurationFormat::new(DurationStyle::Relative).with_max_units(1);
+    assert_eq!(game_length().format(&relative), "3 minutes ago");
+    let ahead = FormattedDuration::from(ChronoDuration::minutes(-5));
+    assert_eq!(ahead.format(&relative), "in 5 minutes");
+}
+
+#[test]
+fn writes_iso_8601() {
+    let iso = DurationFormat::new(DurationStyle::Iso8601);
+    assert_eq!(game_length().format(&iso), "PT3M12.456789012S");
+    assert_eq!(FormattedDuration::from(StdDuration::from_secs(0)).format(&iso), "PT0S");
+    assert_eq!(FormattedDuration::from(StdDuration::from_secs(86_400 * 2 + 3600)).format(&iso), "P2DT1H");
+    assert_eq!(FormattedDuration::from(ChronoDuration::milliseconds(-1500)).format(&iso), "-PT1.5S");
+}
+
+#[test]
+fn names_units_in_the_locale() {
+    let long = DurationFormat::new(DurationStyle::Long).with_max_units(2);
+    assert_eq!(game_length().format(&long.clone().with_locale(Locale::German)), "3 Minuten, 12 Sekunden");
+    assert_eq!(game_length().format(&long.with_locale(Locale::Turkish)), "3 dakika, 12 saniye");
+    let relative = DurationFormat::new(DurationStyle::Relative).with_max_units(1).with_locale(Locale::Turkish);
+    assert_eq!(game_length().format(&relative), "3 dakika önce");
+}
+
+#[test]
+fn splits_chrono_durations_into_sub_second_parts() {
+    let duration = ChronoDuration::seconds(192) + ChronoDuration::milliseconds(456);
+    assert_eq!(FormattedDuration::from(duration).to_string(), "3m12s456ms");
+    assert_eq!(FormattedDuration::from(ChronoDuration::seconds(90)).to_string(), "1m30s");
+}
+
+#[test]
+fn handles_negative_and_huge_durations() {
+    let negative = FormattedDuration::from(ChronoDuration::milliseconds(-90_500));
+    assert!(negative.is_negative());
+    assert_eq!(negative.to_string(), "-1m30s500ms");
+    let parsed: FormattedDuration = negative.to_string().parse().unwrap();
+    assert_eq!(ChronoDuration::try_from(parsed).unwrap(), ChronoDuration::milliseconds(-90_500));
+
+    for huge in [ChronoDuration::max_value(), ChronoDuration::min_value()] {
+        let text = FormattedDuration::from(huge).to_string();
+        assert!(text.contains("years"), "{}", text);
+    }
+    let text = FormattedDuration::from(StdDuration::new(u64::MAX, 999_999_999)).to_string();
+    assert!(text.ends_with("999ns"), "{}", text);
+}
+
+#[test]
+fn counts_years_and_months_on_the_calendar() {
+    let at = |y, m, d, h| Utc.ymd(y, m, d).and_hms(h, 0, 0);
+
+    let february = FormattedDuration::between(at(2023, 2, 1, 0), at(2023, 3, 1, 0));
+    assert_eq!(february.to_string(), "1month");
+    let approximate = FormattedDuration::from(at(2023, 3, 1, 0) - at(2023, 2, 1, 0));
+    assert_eq!(approximate.to_string(), "28days");
+
+    let clamped = FormattedDuration::between(at(2024, 1, 31, 0), at(2024, 3, 1, 12));
+    assert_eq!(clamped.to_string(), "1month1day12h", "Jan 31 + 1 month is Feb 29 in a leap year");
+    let long_month = FormattedDuration::between(at(2023, 3, 1, 0), at(2023, 3, 31, 23));
+    assert_eq!(long_month.to_string(), "30days23h");
+    let years = FormattedDuration::between(at(2020, 2, 29, 0), at(2023, 5, 1, 6));
+    assert_eq!(years.to_string(), "3years2months2days6h");
+
+    let backwards = FormattedDuration::between(at(2023, 3, 1, 0), at(2023, 2, 1, 0));
+    assert!(backwards.is_negative());
+    assert_eq!(backwards.to_string(), "-1month");
+    assert_eq!(ChronoDuration::try_from(backwards).unwrap(), ChronoDuration::days(-28));
+}
@@ -0,0 +1,126 @@
+use std::{
+    error::Error,
+    io,
+};
+
+use word_game::{
+    dictionary::{DictionaryConfig, DictionaryFactory, DictionaryType},
+    error::Entity,
+    locale::Locale,
+    ApplicationError,
+};
+use xmpp_parsers::Jid;
+
+use common::word_game;
+
+mod common;
+
+#[test]
+fn game_rules_have_their_own_variants() {
+    let mut word_game = word_game();
+    for (username, name) in [("alice", "Alice"), ("bob", "Bob"), ("carol", "Carol")] {
+        word_game.create_player(username, name).unwrap();
+    }
+
+    assert!(matches!(word_game.submit_guess("alice", "apple"), Err(ApplicationError::NoCurrentGame)));
+    assert!(matches!(
+        word_game.get_player("dave"),
+        Err(ApplicationError::NotFound { entity: Entity::Player, .. })
+    ));
+    assert!(matches!(
+        word_game.join_game("alice", "missing"),
+        Err(ApplicationError::NotFound { entity: Entity::Game, .. })
+    ));
+
+    let game = word_game.create_game().unwrap();
+    word_game.join_game("alice", &game.id).unwrap();
+    word_game.join_game("bob", &game.id).unwrap();
+    assert!(matches!(word_game.join_game("carol", &game.id), Err(ApplicationError::GameFull)));
+    assert!(matches!(word_game.correct_guess("alice", 1, "pear"), Err(ApplicationError::NoGuess { round: 1 })));
+
+    word_game.submit_guess("alice", "apple").unwrap();
+    assert!(matches!(word_game.submit_guess("alice", "pear"), Err(ApplicationError::AlreadyGuessed)));
+}
+
+#[test]
+fn players_hold_one_seat_at_a_time() {
+    let mut word_game = word_game();
+    for (username, name) in [("alice", "Alice"), ("bob", "Bob"), ("carol", "Carol")] {
+        word_game.create_player(username, name).unwrap();
+    }
+
+    let game = word_game.create_game().unwrap();
+    word_game.join_game("alice", &game.id).unwrap();
+    assert!(matches!(word_game.join_game("alice", &game.id), Err(ApplicationError::AlreadyJoined)));
+
+    let other = word_game.create_game().unwrap();
+    match word_game.join_game("alice", &other.id) {
+        Err(ApplicationError::InAnotherGame { game_id }) => assert_eq!(game_id, game.id),
+        other => panic!("Expected InAnotherGame, got {:?}", other),
+    }
+    assert_eq!(word_game.current_game_for("alice").unwrap().unwrap().id, game.id);
+
+    word_game.join_game("bob", &game.id).unwrap();
+    word_game.submit_guess("alice", "apple").unwrap();
+    word_game.submit_guess("bob", "apple").unwrap();
+    assert!(matches!(word_game.join_game("carol", &game.id), Err(ApplicationError::GameComplete)));
+
+    // Once their game is over, a player is free to join the next one.
+    word_game.join_game("alice", &other.id).unwrap();
+}
+
+#[test]
+fn refused_starts_leave_no_game_behind() {
+    let mut word_game = word_game();
+    word_game.create_player("alice", "Alice").unwrap();
+    word_game.create_player("bob", "Bob").unwrap();
+
+    let game = word_game.start_game("alice", Locale::English).unwrap();
+    assert_eq!(game.player_1_username(), Some("alice"));
+    assert!(matches!(word_game.start_game("alice", Locale::English), Err(ApplicationError::InAnotherGame { .. })));
+    assert!(matches!(word_game.start_game("bob", Locale::German), Err(ApplicationError::LanguageMismatch(_))));
+    assert!(word_game.get_game("game-2").is_err());
+
+    // Bob is matched with Alice rather than with a game nobody is in.
+    assert_eq!(word_game.matchmake("bob").unwrap().id, game.id);
+}
+
+#[test]
+fn keeps_the_underlying_error_as_source() {
+    let error = ApplicationError::config("Could not read words.txt")
+        .caused_by(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+    assert_eq!(error.to_string(), "Configuration error: Could not read words.txt");
+    assert_eq!(error.source().unwrap().to_string(), "no such file");
+
+    let missing = DictionaryFactory::create(
+        DictionaryType::File {
+            locale: Locale::English,
+            path: "/nonexistent/words.txt".into(),
+        },
+        DictionaryConfig::default(),
+    );
+    assert!(matches!(missing, Err(ApplicationError::Config { source: Some(_), .. })));
+
+    // A bad JID at runtime came from a peer, not from the settings.
+    let error: ApplicationError = "@example.com".parse::<Jid>().unwrap_err().into();
+    assert_eq!(error.code(), "transport");
+    assert!(error.source().is_some());
+
+    let error: ApplicationError = io::Error::new(io::ErrorKind::BrokenPipe, "closed").into();
+    assert!(error.source().is_some());
+    assert!(ApplicationError::GameFull.source().is_none());
+}
+
+#[test]
+fn players_see_localized_messages_and_no_internals() {
+    let full = ApplicationError::GameFull;
+    assert_eq!(full.user_message(Locale::English), "Sorry, that game is full.");
+    assert_ne!(full.user_message(Locale::German), full.user_message(Locale::English));
+
+    let mismatch = ApplicationError::LanguageMismatch(Locale::German);
+    assert!(mismatch.user_message(Locale::English).contains("Deutsch"));
+
+    let transport = ApplicationError::transport("stream reset by peer");
+    assert!(!transport.user_message(Locale::English).contains("stream reset"));
+    assert!(transport.to_string().contains("stream reset"));
+}
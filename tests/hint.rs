@@ -0,0 +1,92 @@
+
+use word_game::{
+    association::cooccurrence::CooccurrenceModel,
+    game::{hint::Hint, WordGame},
+    locale::Locale,
+    ApplicationError,
+};
+
+use common::word_game;
+
+mod common;
+
+const TABLE: &str = "
+apple fruit red
+cherry fruit red
+fruit food sweet
+";
+
+/// Alice and Bob playing a game with hints from `TABLE`.
+fn game_in_progress() -> (WordGame, String) {
+    let mut word_game = word_game()
+        .with_association_model(CooccurrenceModel::new(Locale::English, TABLE).into());
+    word_game.create_player("alice", "Alice").unwrap();
+    word_game.create_player("bob", "Bob").unwrap();
+    let game = word_game.create_game().unwrap();
+    word_game.join_game("alice", &game.id).unwrap();
+    word_game.join_game("bob", &game.id).unwrap();
+    (word_game, game.id)
+}
+
+#[test]
+fn hints_need_a_finished_round() {
+    let (mut word_game, _) = game_in_progress();
+    assert!(word_game.request_hint("alice").is_err());
+    word_game.submit_guess("alice", "apple").unwrap();
+    assert!(word_game.request_hint("alice").is_err());
+}
+
+#[test]
+fn hints_point_towards_a_bridge_without_naming_it() {
+    let (mut word_game, game_id) = game_in_progress();
+    word_game.submit_guess("alice", "Apple").unwrap();
+    word_game.submit_guess("bob", "cherry").unwrap();
+
+    let first = word_game.request_hint("alice").unwrap();
+    assert!(matches!(&first, Hint::Category(category) if category != "fruit" && category != "red"));
+    assert_eq!(word_game.request_hint("alice").unwrap(), Hint::Pattern("f _ _ _ _".into()));
+
+    let game = word_game.get_game(&game_id).unwrap();
+    assert_eq!(game.hints().len(), 2);
+    assert_eq!(game.hints()[0].round, 2);
+    assert_eq!(game.hints()[0].username, "alice");
+}
+
+#[test]
+fn statistics_separate_assisted_wins() {
+    let (mut word_game, _) = game_in_progress();
+    word_game.submit_guess("alice", "apple").unwrap();
+    word_game.submit_guess("bob", "cherry").unwrap();
+    word_game.request_hint("bob").unwrap();
+    word_game.submit_guess("alice", "fruit").unwrap();
+    word_game.submit_guess("bob", "fruit").unwrap();
+    assert!(word_game.request_hint("bob").is_err());
+
+    let stats = word_game.statistics("alice").unwrap();
+    assert_eq!(stats.games_won, 1);
+    assert_eq!(stats.assisted_wins, 1);
+    assert!(stats.text(Locale::English).contains("Games won: 1\nWins with hints: 1"));
+}
+
+#[test]
+fn hints_come_from_the_model_for_the_games_language() {
+    let mut word_game = word_game()
+        .with_association_model(CooccurrenceModel::new(Locale::English, TABLE).into());
+    for username in ["anna", "ben"] {
+        word_game.create_player(username, username).unwrap();
+        word_game.set_player_locales(username, &[Locale::German]).unwrap();
+    }
+    let game = word_game.create_game_in(Locale::German).unwrap();
+    word_game.join_game("anna", &game.id).unwrap();
+    word_game.join_game("ben", &game.id).unwrap();
+    word_game.submit_guess("anna", "apple").unwrap();
+    word_game.submit_guess("ben", "cherry").unwrap();
+
+    // The English table knows both words, but not in a German game.
+    assert!(matches!(word_game.request_hint("anna"), Err(ApplicationError::NoHint)));
+
+    let mut word_game = word_game.with_association_model(CooccurrenceModel::bundled(Locale::German).into());
+    word_game.submit_guess("anna", "apfel").unwrap();
+    word_game.submit_guess("ben", "kirsche").unwrap();
+    assert!(matches!(word_game.request_hint("anna").unwrap(), Hint::Category(_)));
+}
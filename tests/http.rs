@@ -0,0 +1,237 @@
+use std::net::SocketAddr;
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use word_game::{json::Json, runner::http::HttpRunner};
+
+use common::new_game;
+
+mod common;
+
+/// Sends one request and returns the status code and parsed body.
+async fn send(address: SocketAddr, method: &str, path: &str, body: Option<&str>, headers: &[(&str, &str)]) -> (u16, Json) {
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    let body = body.unwrap_or("");
+    let mut request = format!("{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n", method, path, body.len());
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+    request.push_str(body);
+    stream.write_all(request.as_bytes()).await.unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, body.parse().unwrap())
+}
+
+async fn get(address: SocketAddr, path: &str) -> (u16, Json) {
+    send(address, "GET", path, None, &[]).await
+}
+
+async fn post(address: SocketAddr, path: &str, body: &str) -> (u16, Json) {
+    send(address, "POST", path, Some(body), &[]).await
+}
+
+async fn get_as(address: SocketAddr, token: &str, path: &str) -> (u16, Json) {
+    let authorization = format!("Bearer {}", token);
+    send(address, "GET", path, None, &[("Authorization", &authorization)]).await
+}
+
+async fn post_as(address: SocketAddr, token: &str, path: &str, body: &str) -> (u16, Json) {
+    let authorization = format!("Bearer {}", token);
+    send(address, "POST", path, Some(body), &[("Authorization", &authorization)]).await
+}
+
+/// Creates a player and returns their token.
+async fn sign_up(address: SocketAddr, username: &str) -> String {
+    let (status, player) = post(address, "/players", &format!(r#"{{"username": "{}"}}"#, username)).await;
+    assert_eq!(status, 201);
+    player.get("token").unwrap().as_str().unwrap().to_string()
+}
+
+fn field<'a>(json: &'a Json, path: &[&str]) -> &'a Json {
+    path.iter().fold(json, |json, key| json.get(key).unwrap())
+}
+
+/// Runs the API on a free port while `script` talks to it.
+async fn with_server<F, Fut>(script: F)
+where
+    F: FnOnce(SocketAddr) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let mut runner = HttpRunner::with_address("127.0.0.1:0".parse().unwrap());
+    let address = runner.bind().await.unwrap();
+    common::serve_during(|shutdown| runner.run_until(new_game(), shutdown), script(address)).await;
+}
+
+#[tokio::test]
+async fn plays_a_whole_game() {
+    with_server(|address| async move {
+        let (status, player) = post(address, "/players", r#"{"username": "alice", "display_name": "Alice"}"#).await;
+        assert_eq!(status, 201);
+        assert_eq!(player.get("display_name").unwrap().as_str(), Some("Alice"));
+        let alice = player.get("token").unwrap().as_str().unwrap().to_string();
+        let bob = sign_up(address, "bob").await;
+
+        let (status, game) = post_as(address, &alice, "/games", "{}").await;
+        assert_eq!(status, 201);
+        assert_eq!(game.get("id").unwrap().as_str(), Some("game-1"));
+        let (status, _) = post_as(address, &bob, "/games/game-1/join", "").await;
+        assert_eq!(status, 200);
+
+        let (status, outcome) = post_as(address, &alice, "/games/game-1/guesses", r#"{"guess": "cat"}"#).await;
+        assert_eq!(status, 200);
+        assert_eq!(outcome.get("outcome").unwrap().as_str(), Some("waiting"));
+
+        let (_, outcome) = post_as(address, &bob, "/games/game-1/guesses", r#"{"guess": "dog"}"#).await;
+        assert_eq!(outcome.get("outcome").unwrap().as_str(), Some("mismatched"));
+        post_as(address, &alice, "/games/game-1/guesses", r#"{"guess": "pet"}"#).await;
+        let (_, outcome) = post_as(address, &bob, "/games/game-1/guesses", r#"{"guess": "Pet"}"#).await;
+        assert_eq!(outcome.get("outcome").unwrap().as_str(), Some("matched"));
+        assert_eq!(outcome.get("round").unwrap().as_u64(), Some(2));
+
+        let (_, game) = get(address, "/games/game-1").await;
+        assert_eq!(game.get("complete").unwrap().as_bool(), Some(true));
+        assert!(!game.get("ended_at").unwrap().is_null());
+
+        let (status, statistics) = get(address, "/players/bob/statistics").await;
+        assert_eq!(status, 200);
+        assert_eq!(statistics.get("games_won").unwrap().as_u64(), Some(1));
+        assert_eq!(statistics.get("average_rounds_to_win").unwrap().as_f64(), Some(2.0));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn leaving_frees_the_seat() {
+    with_server(|address| async move {
+        let alice = sign_up(address, "alice").await;
+        let bob = sign_up(address, "bob").await;
+        let carol = sign_up(address, "carol").await;
+        post_as(address, &alice, "/games", "").await;
+        post_as(address, &bob, "/games/game-1/join", "").await;
+
+        // A refused start leaves no game behind.
+        let (status, error) = post_as(address, &bob, "/games", "").await;
+        assert_eq!(status, 409);
+        assert_eq!(error.get("error").unwrap().as_str(), Some("in_another_game"));
+        let (status, _) = get(address, "/games/game-2").await;
+        assert_eq!(status, 404);
+
+        let (status, error) = post_as(address, &carol, "/games/game-1/join", "").await;
+        assert_eq!(status, 409);
+        assert_eq!(error.get("error").unwrap().as_str(), Some("game_full"));
+
+        let (status, _) = post_as(address, &bob, "/games/game-1/leave", "").await;
+        assert_eq!(status, 200);
+        let (_, player) = get(address, "/players/bob").await;
+        assert!(player.get("current_game").unwrap().is_null());
+        let (status, _) = post_as(address, &carol, "/games/game-1/join", "").await;
+        assert_eq!(status, 200);
+
+        let (status, error) = post_as(address, &bob, "/games/game-1/leave", "").await;
+        assert_eq!(status, 409);
+        assert_eq!(error.get("error").unwrap().as_str(), Some("not_in_game"));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn maps_errors_to_status_codes() {
+    with_server(|address| async move {
+        let (status, error) = get(address, "/players/nobody").await;
+        assert_eq!(status, 404);
+        assert_eq!(error.get("error").unwrap().as_str(), Some("not_found"));
+
+        let alice = sign_up(address, "alice").await;
+        let (status, error) = post(address, "/players", r#"{"username": "alice"}"#).await;
+        assert_eq!(status, 409);
+        assert_eq!(error.get("error").unwrap().as_str(), Some("already_exists"));
+
+        let (status, error) = post(address, "/players", r#"{"username": "bob", "locales": ["xx"]}"#).await;
+        assert_eq!(status, 422);
+        assert_eq!(error.get("error").unwrap().as_str(), Some("unknown_language"));
+
+        post_as(address, &alice, "/games", "").await;
+        let (status, error) = post_as(address, &alice, "/games/game-1/guesses", r#"{"guess": "  "}"#).await;
+        assert_eq!(status, 422);
+        assert_eq!(error.get("error").unwrap().as_str(), Some("invalid_guess"));
+
+        let (status, _) = post(address, "/players", "{not json").await;
+        assert_eq!(status, 400);
+        let (status, _) = post_as(address, &alice, "/games/game-1/guesses", "{}").await;
+        assert_eq!(status, 400);
+        let (status, _) = get(address, "/nowhere").await;
+        assert_eq!(status, 404);
+        let (status, error) = send(address, "DELETE", "/players/alice", None, &[]).await;
+        assert_eq!(status, 405);
+        assert_eq!(error.get("error").unwrap().as_str(), Some("method_not_allowed"));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn explains_errors_in_the_requested_language() {
+    with_server(|address| async move {
+        let (status, error) = send(address, "GET", "/games/missing", None, &[("Accept-Language", "de-DE,de;q=0.9")]).await;
+        assert_eq!(status, 404);
+        assert_eq!(
+            error.get("message").unwrap().as_str(),
+            Some("Ich konnte kein Spiel mit diesem Code finden.")
+        );
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn only_shows_a_hidden_guess_to_its_own_seat() {
+    with_server(|address| async move {
+        let alice = sign_up(address, "alice").await;
+        let bob = sign_up(address, "bob").await;
+        post_as(address, &alice, "/games", "").await;
+        post_as(address, &bob, "/games/game-1/join", "").await;
+        post_as(address, &alice, "/games/game-1/guesses", r#"{"guess": "cat"}"#).await;
+
+        let first_round = |game: &Json| field(game, &["rounds"]).as_array().unwrap()[0].clone();
+        let (_, game) = get_as(address, &alice, "/games/game-1").await;
+        assert_eq!(field(&first_round(&game), &["player_1", "word"]).as_str(), Some("cat"));
+
+        // Asking as Alice by name no longer works; only her token shows her guess.
+        for (status, game) in [
+            get_as(address, &bob, "/games/game-1").await,
+            get_as(address, &bob, "/games/game-1?player=alice").await,
+            get(address, "/games/game-1").await,
+        ] {
+            assert_eq!(status, 200);
+            let round = first_round(&game);
+            assert!(field(&round, &["player_1", "word"]).is_null());
+            assert_eq!(field(&round, &["player_1", "ready"]).as_bool(), Some(true));
+        }
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn refuses_to_act_without_a_valid_token() {
+    with_server(|address| async move {
+        let alice = sign_up(address, "alice").await;
+        sign_up(address, "bob").await;
+        post_as(address, &alice, "/games", "").await;
+
+        let (status, error) = post(address, "/games/game-1/join", r#"{"player": "bob"}"#).await;
+        assert_eq!(status, 401);
+        assert_eq!(error.get("error").unwrap().as_str(), Some("unauthorized"));
+        let (status, _) = post_as(address, "not-a-token", "/games/game-1/join", "").await;
+        assert_eq!(status, 401);
+        let (status, _) = get_as(address, "not-a-token", "/games/game-1").await;
+        assert_eq!(status, 401);
+        let (status, _) = send(address, "POST", "/games/game-1/guesses", Some(r#"{"guess": "cat"}"#), &[("Authorization", &alice)]).await;
+        assert_eq!(status, 401);
+    })
+    .await;
+}
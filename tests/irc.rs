@@ -0,0 +1,198 @@
+use std::time::Duration;
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpListener,
+    },
+    time::timeout,
+};
+use word_game::runner::irc::{message::Message, IrcRunner};
+
+use common::new_game;
+
+mod common;
+
+/// Just enough of an IRC server to talk to one bot.
+struct Server {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl Server {
+    async fn accept(listener: &TcpListener) -> Self {
+        let (stream, _) = timeout(Duration::from_secs(5), listener.accept())
+            .await
+            .expect("Timed out waiting for the bot to connect")
+            .unwrap();
+        let (reader, writer) = stream.into_split();
+        Self {
+            lines: BufReader::new(reader).lines(),
+            writer,
+        }
+    }
+
+    /// Accepts the bot and lets it register and join `#games`.
+    async fn welcome(listener: &TcpListener) -> Self {
+        let mut server = Self::accept(listener).await;
+        server.expect("NICK WordGame").await;
+        server.expect("USER WordGame").await;
+        server.send(":irc.test 001 WordGame :Welcome to the test network").await;
+        server.expect("JOIN #games").await;
+        server
+    }
+
+    async fn send(&mut self, line: &str) {
+        self.writer.write_all(format!("{}\r\n", line).as_bytes()).await.unwrap();
+    }
+
+    /// A private message to the bot from `nick`.
+    async fn say(&mut self, nick: &str, text: &str) {
+        self.send(&format!(":{}!{}@users.irc.test PRIVMSG WordGame :{}", nick, nick, text)).await;
+    }
+
+    /// Reads until the bot sends a line starting with `start`, skipping
+    /// anything else.
+    async fn expect(&mut self, start: &str) -> Message {
+        loop {
+            let line = timeout(Duration::from_secs(5), self.lines.next_line())
+                .await
+                .unwrap_or_else(|_| panic!("Timed out waiting for {:?}", start))
+                .unwrap()
+                .unwrap_or_else(|| panic!("The bot hung up before sending {:?}", start));
+            if line.starts_with(start) {
+                return Message::parse(&line).unwrap();
+            }
+        }
+    }
+}
+
+async fn with_bot<F, Fut>(script: F)
+where
+    F: FnOnce(TcpListener) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut runner = IrcRunner::with_server(&listener.local_addr().unwrap().to_string(), "WordGame", "#games");
+    common::serve_during(|shutdown| runner.run_until(new_game(), shutdown), script(listener)).await;
+}
+
+#[test]
+fn parses_and_writes_lines() {
+    let message = Message::parse("@time=2024-05-01T12:00:00Z :alice!a@host PRIVMSG WordGame :/join game-1\r\n").unwrap();
+    assert_eq!(message.nick(), Some("alice"));
+    assert_eq!(message.command, "PRIVMSG");
+    assert_eq!(message.params, vec!["WordGame", "/join game-1"]);
+    assert_eq!(message.to_string(), ":alice!a@host PRIVMSG WordGame :/join game-1");
+    assert_eq!(Message::new("JOIN", &["#games"]).to_string(), "JOIN #games");
+
+    let long = "word ".repeat(200);
+    let lines = Message::privmsg("#games", &format!("first\n\n{}", long));
+    assert_eq!(lines[0].to_string(), "PRIVMSG #games first");
+    assert!(lines.len() > 2);
+    assert!(lines.iter().all(|line| line.to_string().len() < 510));
+}
+
+#[tokio::test]
+async fn registers_answers_pings_and_reconnects() {
+    with_bot(|listener| async move {
+        let mut server = Server::accept(&listener).await;
+        server.expect("NICK WordGame").await;
+        server.send(":irc.test 433 * WordGame :Nickname is already in use").await;
+        server.expect("NICK WordGame_").await;
+        server.send(":irc.test 001 WordGame_ :Welcome to the test network").await;
+        server.expect("JOIN #games").await;
+
+        server.send("PING :irc.test").await;
+        assert_eq!(server.expect("PONG").await.params, vec!["irc.test"]);
+
+        server.send("ERROR :Closing link").await;
+        let mut server = Server::welcome(&listener).await;
+        // Latin-1 from an old client doesn't bring the bot down.
+        server.writer.write_all(b":carol!carol@users.irc.test PRIVMSG #games :caf\xe9\r\n").await.unwrap();
+        server.say("alice", "/status").await;
+        server.expect("PRIVMSG alice :You have not joined the word game!").await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn plays_a_game_and_announces_it_in_the_channel() {
+    with_bot(|listener| async move {
+        let mut server = Server::welcome(&listener).await;
+        server.say("alice", "/start").await;
+        server.expect("PRIVMSG alice :Game created! Ask your partner to join with this code: game-1").await;
+        server.expect("PRIVMSG #games :alice started a new game").await;
+        server.say("alice", "/start").await;
+        server.expect("PRIVMSG alice :You are still in game game-1.").await;
+        server.say("bob", "/join game-1").await;
+        server.expect("PRIVMSG bob :You joined the game!").await;
+        server.expect("PRIVMSG #games :alice and bob are playing!").await;
+
+        server.say("alice", "cat").await;
+        server.expect("PRIVMSG alice :Got your guess for round 1!").await;
+        server.say("bob", "dog").await;
+        let reveal = server.expect("PRIVMSG #games").await;
+        assert_eq!(reveal.param(1), Some(r#"Round 1: alice said "cat" and bob said "dog"."#));
+        server.expect("PRIVMSG #games :Aww, shucks...").await;
+
+        server.say("alice", "pet").await;
+        server.say("bob", "pet").await;
+        server.expect("PRIVMSG #games :alice and bob, you won!!! Congrats!").await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn follows_players_across_nick_changes() {
+    with_bot(|listener| async move {
+        let mut server = Server::welcome(&listener).await;
+        server.say("alice", "/start").await;
+        server.expect("PRIVMSG alice :Game created!").await;
+        server.send(":alice!alice@users.irc.test NICK :Alicia").await;
+
+        // Someone new under the old nickname isn't handed alice's game.
+        server.say("alice", "/board").await;
+        server.expect("PRIVMSG alice :alice, someone is already playing as you").await;
+
+        server.say("bob", "/join game-1").await;
+        server.expect("PRIVMSG #games :Alicia and bob are playing!").await;
+        server.say("Alicia", "cat").await;
+        server.expect("PRIVMSG Alicia :Got your guess for round 1!").await;
+        server.say("bob", "dog").await;
+        let reveal = server.expect("PRIVMSG #games").await;
+        assert_eq!(reveal.param(1), Some(r#"Round 1: Alicia said "cat" and bob said "dog"."#));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn forgets_nicknames_it_can_no_longer_vouch_for() {
+    with_bot(|listener| async move {
+        let mut server = Server::welcome(&listener).await;
+        server.say("alice", "/start").await;
+        server.expect("PRIVMSG alice :Game created!").await;
+        server.send(":alice!alice@users.irc.test NICK :Alicia").await;
+        server.say("Alicia", "/status").await;
+        server.expect("PRIVMSG Alicia :Hello, Alicia").await;
+
+        // Alicia leaves, and whoever picks up the nickname is someone new.
+        server.send(":Alicia!alice@users.irc.test PART #games").await;
+        server.say("Alicia", "/status").await;
+        server.expect("PRIVMSG Alicia :You have not joined the word game!").await;
+
+        server.say("bob", "/start").await;
+        server.expect("PRIVMSG bob :Game created!").await;
+        server.send(":bob!bob@users.irc.test NICK :Bobby").await;
+        server.say("Bobby", "/status").await;
+        server.expect("PRIVMSG Bobby :Hello, Bobby").await;
+
+        // Nicknames may change hands while the bot is disconnected.
+        server.send("ERROR :Closing link").await;
+        let mut server = Server::welcome(&listener).await;
+        server.say("Bobby", "/status").await;
+        server.expect("PRIVMSG Bobby :You have not joined the word game!").await;
+    })
+    .await;
+}
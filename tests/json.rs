@@ -0,0 +1,31 @@
+use word_game::json::Json;
+
+#[test]
+fn round_trips_documents() {
+    let text = r#"{"name":"Grüße \"du\"","tags":["a\nb",true,null],"count":3,"ratio":-1.5e2}"#;
+    let json: Json = text.parse().unwrap();
+
+    assert_eq!(json.get("name").unwrap().as_str(), Some("Grüße \"du\""));
+    assert_eq!(json.get("count").unwrap().as_u64(), Some(3));
+    assert_eq!(json.get("ratio").unwrap().as_f64(), Some(-150.0));
+    assert_eq!(json.to_string().parse::<Json>().unwrap(), json);
+    assert_eq!(
+        json.to_string(),
+        r#"{"name":"Grüße \"du\"","tags":["a\nb",true,null],"count":3,"ratio":-150}"#
+    );
+}
+
+#[test]
+fn decodes_surrogate_pairs() {
+    let json: Json = r#""🎉""#.parse().unwrap();
+    assert_eq!(json.as_str(), Some("🎉"));
+}
+
+#[test]
+fn rejects_malformed_documents() {
+    for text in ["", "{", r#"{"a" 1}"#, "[1,]", "tru", r#""\ud83c""#, "1 2", r#""tab	inside""#] {
+        assert!(text.parse::<Json>().is_err(), "{:?} should not parse", text);
+    }
+    let deep = "[".repeat(100) + &"]".repeat(100);
+    assert!(deep.parse::<Json>().is_err());
+}
@@ -0,0 +1,112 @@
+
+use word_game::{
+    dictionary::{DictionaryConfig, DictionaryFactory, DictionaryType, InvalidGuess},
+    game::{GuessOutcome, WordGame},
+    locale::Locale,
+    ApplicationError,
+};
+
+use common::word_game;
+
+mod common;
+
+/// A game with the bundled dictionary of every language.
+fn multilingual_game() -> WordGame {
+    let mut word_game = word_game();
+    for locale in Locale::ALL.iter() {
+        let dictionary = DictionaryFactory::create(DictionaryType::Bundled(*locale), DictionaryConfig::default()).unwrap();
+        word_game = word_game.with_dictionary(dictionary);
+    }
+    word_game
+}
+
+#[test]
+fn parses_codes_and_names() {
+    assert_eq!("de".parse::<Locale>().unwrap(), Locale::German);
+    assert_eq!("tr-TR".parse::<Locale>().unwrap(), Locale::Turkish);
+    assert_eq!("English".parse::<Locale>().unwrap(), Locale::English);
+    assert_eq!("Deutsch".parse::<Locale>().unwrap(), Locale::German);
+    assert_eq!("german".parse::<Locale>().unwrap(), Locale::German);
+    assert_eq!("Turkish".parse::<Locale>().unwrap(), Locale::Turkish);
+    assert!("xx".parse::<Locale>().is_err());
+}
+
+#[test]
+fn normalizes_per_language() {
+    assert_eq!(Locale::German.normalize("Straße"), Locale::German.normalize("STRASSE"));
+    assert_eq!(Locale::Turkish.normalize("ILIK"), "ılık");
+    assert_eq!(Locale::Turkish.normalize("İSTANBUL"), "istanbul");
+    assert_ne!(Locale::Turkish.normalize("I"), Locale::Turkish.normalize("i"));
+    assert_eq!(Locale::English.normalize(" I "), "i");
+}
+
+#[test]
+fn dictionaries_use_the_locale_rules() {
+    let german = DictionaryFactory::create(DictionaryType::Bundled(Locale::German), DictionaryConfig::default()).unwrap();
+    assert!(german.contains("STRASSE"));
+    assert!(german.validate("strase").unwrap_err().user_message(Locale::German).contains("straße"));
+
+    let turkish = DictionaryFactory::create(DictionaryType::Bundled(Locale::Turkish), DictionaryConfig::default()).unwrap();
+    assert!(turkish.contains("IŞIK"));
+    assert!(turkish.contains("İSTANBUL"));
+}
+
+#[test]
+fn games_match_and_validate_in_their_locale() {
+    let mut word_game = multilingual_game();
+    word_game.create_player("ayse", "Ayşe").unwrap();
+    word_game.create_player("emre", "Emre").unwrap();
+    word_game.set_player_locales("ayse", &[Locale::Turkish]).unwrap();
+    word_game.set_player_locales("emre", &[Locale::Turkish, Locale::English]).unwrap();
+    let game = word_game.create_game_in(Locale::Turkish).unwrap();
+    word_game.join_game("ayse", &game.id).unwrap();
+    word_game.join_game("emre", &game.id).unwrap();
+
+    assert!(matches!(
+        word_game.submit_guess("ayse", "apple"),
+        Err(ApplicationError::InvalidGuess(InvalidGuess::NotAWord { .. }))
+    ));
+    word_game.submit_guess("ayse", "IŞIK").unwrap();
+    assert_eq!(word_game.submit_guess("emre", "ışık").unwrap(), GuessOutcome::Matched { round: 1 });
+}
+
+#[test]
+fn players_only_join_games_in_their_languages() {
+    let mut word_game = multilingual_game();
+    word_game.create_player("anna", "Anna").unwrap();
+    word_game.create_player("bob", "Bob").unwrap();
+    word_game.set_player_locales("anna", &[Locale::German]).unwrap();
+    let game = word_game.create_game_in(Locale::German).unwrap();
+    word_game.join_game("anna", &game.id).unwrap();
+
+    assert!(matches!(
+        word_game.join_game("bob", &game.id),
+        Err(ApplicationError::LanguageMismatch(Locale::German))
+    ));
+}
+
+#[test]
+fn matchmaking_pairs_players_with_a_shared_language() {
+    let mut word_game = multilingual_game();
+    for (username, locales) in [
+        ("anna", vec![Locale::German]),
+        ("bob", vec![Locale::English]),
+        ("can", vec![Locale::Turkish, Locale::German]),
+        ("dan", vec![Locale::English]),
+    ] {
+        word_game.create_player(username, "").unwrap();
+        word_game.set_player_locales(username, &locales).unwrap();
+    }
+
+    let anna = word_game.matchmake("anna").unwrap();
+    assert_eq!(anna.locale(), Locale::German);
+    let bob = word_game.matchmake("bob").unwrap();
+    assert_ne!(bob.id, anna.id);
+
+    let can = word_game.matchmake("can").unwrap();
+    assert_eq!(can.id, anna.id, "can also plays in German");
+    assert!(can.is_full());
+
+    let dan = word_game.matchmake("dan").unwrap();
+    assert_eq!(dan.id, bob.id);
+}
@@ -0,0 +1,395 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::{
+    io::BufReader,
+    net::{TcpListener, TcpStream},
+    sync::{
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        Mutex as AsyncMutex,
+    },
+    time::timeout,
+};
+use word_game::{
+    game::WordGame,
+    json::Json,
+    runner::{
+        http::{
+            request::read_request,
+            response::{write_response, Status},
+        },
+        matrix::{
+            bot::{Action, MatrixBot},
+            MatrixRunner,
+        },
+    },
+};
+
+use common::new_game;
+
+mod common;
+
+const TOKEN: &str = "secret";
+
+/// What the bot asked the homeserver to do.
+#[derive(Debug)]
+enum Request {
+    Join(String),
+    Send { room: String, transaction: String, content: Json },
+    AccountData { kind: String, content: Json },
+}
+
+struct Shared {
+    syncs: AsyncMutex<UnboundedReceiver<Json>>,
+    requests: UnboundedSender<Request>,
+}
+
+/// A homeserver with just `sync`, `join`, `send` and account data. Tests
+/// queue up what each sync returns and watch what the bot asks for.
+struct MockHomeserver {
+    syncs: UnboundedSender<Json>,
+    requests: UnboundedReceiver<Request>,
+    batches: usize,
+}
+
+impl MockHomeserver {
+    async fn start() -> (String, Self) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (syncs, queued) = unbounded_channel();
+        let (recorded, requests) = unbounded_channel();
+        let shared = Arc::new(Shared {
+            syncs: AsyncMutex::new(queued),
+            requests: recorded,
+        });
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, shared.clone()));
+            }
+        });
+        let homeserver = Self {
+            syncs,
+            requests,
+            batches: 0,
+        };
+        (url, homeserver)
+    }
+
+    /// Has the next sync return `rooms`.
+    fn sync(&mut self, rooms: Json) {
+        self.queue(vec![("rooms", rooms)]);
+    }
+
+    /// Has the next sync return an `m.direct` listing each user's direct
+    /// message room.
+    fn sync_direct(&mut self, direct: &[(&str, &str)]) {
+        let content = direct.iter().map(|(user, room)| (*user, vec![*room].into())).collect();
+        let event = Json::object(vec![("type", "m.direct".into()), ("content", Json::object(content))]);
+        self.queue(vec![("account_data", Json::object(vec![("events", vec![event].into())]))]);
+    }
+
+    fn queue(&mut self, mut fields: Vec<(&str, Json)>) {
+        self.batches += 1;
+        fields.insert(0, ("next_batch", format!("s{}", self.batches).into()));
+        self.syncs.send(Json::object(fields)).unwrap();
+    }
+
+    async fn next_request(&mut self) -> Request {
+        timeout(Duration::from_secs(5), self.requests.recv())
+            .await
+            .expect("Timed out waiting for the bot")
+            .unwrap()
+    }
+
+    /// The body of the next message, which must go to `room`.
+    async fn expect_sent(&mut self, room: &str) -> String {
+        match self.next_request().await {
+            Request::Send { room: to, content, .. } if to == room => {
+                assert_eq!(content.get("msgtype").unwrap().as_str(), Some("m.notice"));
+                content.get("body").unwrap().as_str().unwrap().to_string()
+            }
+            other => panic!("Expected a message to {}, got {:?}", room, other),
+        }
+    }
+
+    /// The content of the next account data update, which must be `kind`.
+    async fn expect_account_data(&mut self, kind: &str) -> Json {
+        match self.next_request().await {
+            Request::AccountData { kind: saved, content } if saved == kind => content,
+            other => panic!("Expected {} account data, got {:?}", kind, other),
+        }
+    }
+}
+
+async fn serve(stream: TcpStream, shared: Arc<Shared>) {
+    let (reader, mut writer) = stream.into_split();
+    let request = match read_request(&mut BufReader::new(reader)).await {
+        Ok(Some(request)) => request,
+        _ => return,
+    };
+    if request.header("Authorization") != Some(&format!("Bearer {}", TOKEN)) {
+        let body = r#"{"errcode": "M_UNKNOWN_TOKEN", "error": "Unknown token"}"#;
+        let _ = write_response(&mut writer, Status::BadRequest, "application/json", body.as_bytes()).await;
+        return;
+    }
+
+    let segments = request.segments();
+    let segments: Vec<&str> = segments.iter().map(|s| s.as_str()).collect();
+    let body = String::from_utf8_lossy(&request.body).to_string();
+    let response = match (request.method.as_str(), &segments[..]) {
+        ("GET", ["_matrix", "client", "v3", "sync"]) => {
+            let wait: u64 = request.query_param("timeout").unwrap().parse().unwrap();
+            let mut syncs = shared.syncs.lock().await;
+            let queued = match syncs.try_recv() {
+                Ok(sync) => Some(sync),
+                Err(_) => timeout(Duration::from_millis(wait), syncs.recv()).await.ok().flatten(),
+            };
+            let since = request.query_param("since").unwrap_or("s0");
+            queued.unwrap_or_else(|| Json::object(vec![("next_batch", since.into())]))
+        }
+        ("POST", ["_matrix", "client", "v3", "join", room]) => {
+            shared.requests.send(Request::Join(room.to_string())).unwrap();
+            Json::object(vec![("room_id", room.to_string().into())])
+        }
+        ("PUT", ["_matrix", "client", "v3", "rooms", room, "send", "m.room.message", transaction]) => {
+            let content = body.parse().unwrap();
+            let (room, transaction) = (room.to_string(), transaction.to_string());
+            shared.requests.send(Request::Send { room, transaction, content }).unwrap();
+            Json::object(vec![("event_id", "$event".into())])
+        }
+        ("PUT", ["_matrix", "client", "v3", "user", _, "account_data", kind]) => {
+            let content = body.parse().unwrap();
+            shared.requests.send(Request::AccountData { kind: kind.to_string(), content }).unwrap();
+            Json::object(vec![])
+        }
+        _ => {
+            let _ = write_response(&mut writer, Status::NotFound, "application/json", b"{}").await;
+            return;
+        }
+    };
+    let _ = write_response(&mut writer, Status::Ok, "application/json", response.to_string().as_bytes()).await;
+}
+
+fn message(sender: &str, body: &str) -> Json {
+    Json::object(vec![
+        ("type", "m.room.message".into()),
+        ("sender", sender.into()),
+        ("content", Json::object(vec![("msgtype", "m.text".into()), ("body", body.into())])),
+    ])
+}
+
+/// A joined room with new `events`.
+fn joined(events: Vec<Json>) -> Json {
+    Json::object(vec![("timeline", Json::object(vec![("events", events.into())]))])
+}
+
+fn rooms(join: Vec<(&str, Json)>) -> Json {
+    Json::object(vec![("join", Json::object(join))])
+}
+
+async fn with_bot<F, Fut>(setup: impl FnOnce(&mut MockHomeserver), script: F)
+where
+    F: FnOnce(MockHomeserver, Arc<Mutex<WordGame>>) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let (url, mut homeserver) = MockHomeserver::start().await;
+    setup(&mut homeserver);
+    let mut runner = MatrixRunner::with_homeserver(&url, "@wordgame:test", TOKEN)
+        .unwrap()
+        .with_sync_timeout(Duration::from_millis(50));
+    let game = new_game();
+    let script = script(homeserver, game.clone());
+    common::serve_during(|shutdown| runner.run_until(game, shutdown), script).await;
+}
+
+#[tokio::test]
+async fn joins_on_invite_and_skips_history() {
+    let setup = |homeserver: &mut MockHomeserver| {
+        let invite = Json::object(vec![(
+            "invite_state",
+            Json::object(vec![(
+                "events",
+                vec![Json::object(vec![
+                    ("type", "m.room.member".into()),
+                    ("state_key", "@wordgame:test".into()),
+                    ("sender", "@alice:test".into()),
+                    ("content", Json::object(vec![("membership", "invite".into()), ("is_direct", true.into())])),
+                ])]
+                .into(),
+            )]),
+        )]);
+        homeserver.sync(Json::object(vec![
+            ("invite", Json::object(vec![("!dm:test", invite)])),
+            ("join", Json::object(vec![("!old:test", joined(vec![message("@carol:test", "!start")]))])),
+        ]));
+    };
+    with_bot(setup, |mut homeserver, _| async move {
+        match homeserver.next_request().await {
+            Request::Join(room) => assert_eq!(room, "!dm:test"),
+            other => panic!("Expected a join, got {:?}", other),
+        }
+        let direct = homeserver.expect_account_data("m.direct").await;
+        assert_eq!(direct.to_string(), r#"{"@alice:test":["!dm:test"]}"#);
+
+        // The invite said the room is a direct message, so the bot answers
+        // everything there.
+        let timeline = Json::object(vec![("events", vec![message("@alice:test", "!help")].into())]);
+        homeserver.sync(rooms(vec![("!dm:test", Json::object(vec![("timeline", timeline)]))]));
+        let help = homeserver.expect_sent("!dm:test").await;
+        assert!(help.starts_with("Commands:"));
+        assert!(help.contains("!join <code>"));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn hosts_a_game_in_a_group_room() {
+    let setup = |homeserver: &mut MockHomeserver| {
+        homeserver.sync_direct(&[("@alice:test", "!alice:test"), ("@bob:test", "!bob:test")])
+    };
+    with_bot(setup, |mut homeserver, game| async move {
+        homeserver.sync(rooms(vec![("!games:test", joined(vec![message("@alice:test", "!start")]))]));
+        let hosted = homeserver.expect_account_data("org.wordgame.hosted").await;
+        assert_eq!(hosted.to_string(), r#"{"!games:test":"game-1"}"#);
+        assert!(homeserver.expect_sent("!games:test").await.starts_with("alice started a new game"));
+        homeserver.sync(rooms(vec![("!games:test", joined(vec![message("@bob:test", "!join"), message("@bob:test", "hello all")]))]));
+        assert!(homeserver.expect_sent("!games:test").await.starts_with("alice and bob are playing!"));
+
+        homeserver.sync(rooms(vec![("!alice:test", joined(vec![message("@alice:test", "cat")]))]));
+        assert!(homeserver.expect_sent("!alice:test").await.starts_with("Got your guess for round 1!"));
+        homeserver.sync(rooms(vec![("!bob:test", joined(vec![message("@bob:test", "dog")]))]));
+        assert_eq!(homeserver.expect_sent("!bob:test").await, "Got your guess for round 1!");
+        assert_eq!(
+            homeserver.expect_sent("!games:test").await,
+            r#"Round 1: alice said "cat" and bob said "dog"."#
+        );
+        assert!(homeserver.expect_sent("!games:test").await.starts_with("Aww, shucks..."));
+
+        homeserver.sync(rooms(vec![
+            ("!alice:test", joined(vec![message("@alice:test", "pet")])),
+            ("!bob:test", joined(vec![message("@bob:test", "pet")])),
+        ]));
+        homeserver.expect_sent("!alice:test").await;
+        homeserver.expect_sent("!bob:test").await;
+        homeserver.expect_sent("!games:test").await;
+        match homeserver.next_request().await {
+            Request::Send { room, content, .. } => {
+                assert_eq!(room, "!games:test");
+                assert!(content.get("body").unwrap().as_str().unwrap().starts_with("alice and bob, you won!!!"));
+                assert!(content.get("formatted_body").unwrap().as_str().unwrap().contains("<pre>"));
+            }
+            other => panic!("Expected the final board, got {:?}", other),
+        }
+
+        assert!(game.lock().unwrap().has_player("@alice:test"));
+        assert_eq!(game.lock().unwrap().statistics("@bob:test").unwrap().games_won, 1);
+
+        // A small room isn't a direct message unless m.direct says so.
+        homeserver.sync(rooms(vec![("!pair:test", joined(vec![message("@alice:test", "!help")]))]));
+        assert!(homeserver.expect_sent("!pair:test").await.starts_with("Room commands:"));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn plays_by_code_in_direct_messages() {
+    let setup = |homeserver: &mut MockHomeserver| {
+        homeserver.sync_direct(&[("@alice:test", "!alice:test"), ("@bob:test", "!bob:test")])
+    };
+    with_bot(setup, |mut homeserver, _| async move {
+        homeserver.sync(rooms(vec![("!alice:test", joined(vec![message("@alice:test", "!start")]))]));
+        match homeserver.next_request().await {
+            // Transaction ids come from the game's id generator too.
+            Request::Send { content, transaction, .. } => {
+                assert!(content.get("body").unwrap().as_str().unwrap().ends_with("game-1"));
+                assert_eq!(transaction, "game-2");
+            }
+            other => panic!("Expected the game code, got {:?}", other),
+        }
+        homeserver.sync(rooms(vec![("!bob:test", joined(vec![message("@bob:test", "!join game-1")]))]));
+        assert!(homeserver.expect_sent("!bob:test").await.starts_with("You joined the game!"));
+        assert!(homeserver.expect_sent("!alice:test").await.starts_with("bob joined your game!"));
+        homeserver.sync(rooms(vec![("!alice:test", joined(vec![message("@alice:test", "!start")]))]));
+        assert!(homeserver.expect_sent("!alice:test").await.starts_with("You are still in game game-1."));
+
+        homeserver.sync(rooms(vec![
+            ("!alice:test", joined(vec![message("@alice:test", "cat")])),
+            ("!bob:test", joined(vec![message("@bob:test", "cat")])),
+        ]));
+        homeserver.expect_sent("!alice:test").await;
+        homeserver.expect_sent("!bob:test").await;
+        // With no room hosting the game, both players hear how it went.
+        for room in ["!alice:test", "!bob:test"] {
+            assert_eq!(homeserver.expect_sent(room).await, r#"Round 1: alice said "cat" and bob said "cat"."#);
+            assert!(homeserver.expect_sent(room).await.starts_with("alice and bob, you won!!!"));
+        }
+    })
+    .await;
+}
+
+#[test]
+fn picks_up_its_rooms_again_after_a_restart() {
+    let game = new_game();
+    let direct = [
+        ("@alice:test", "!alice:test"),
+        ("@bob:test", "!bob:test"),
+        ("@carol:test", "!carol:test"),
+        ("@dave:test", "!dave:test"),
+    ];
+    let invites = direct
+        .iter()
+        .map(|(user, room)| {
+            let member = Json::object(vec![
+                ("type", "m.room.member".into()),
+                ("state_key", "@wordgame:test".into()),
+                ("sender", (*user).into()),
+                ("content", Json::object(vec![("membership", "invite".into()), ("is_direct", true.into())])),
+            ]);
+            (*room, Json::object(vec![("invite_state", Json::object(vec![("events", vec![member].into())]))]))
+        })
+        .collect();
+    let mut bot = MatrixBot::new("@wordgame:test");
+    let invites = Json::object(vec![("invite", Json::object(invites))]);
+    let mut actions = bot.handle_sync(&Json::object(vec![("rooms", invites)]), &game, false);
+    let timeline = rooms(vec![
+        ("!games:test", joined(vec![message("@alice:test", "!start"), message("@bob:test", "!join")])),
+        ("!carol:test", joined(vec![message("@carol:test", "!start")])),
+    ]);
+    actions.extend(bot.handle_sync(&Json::object(vec![("rooms", timeline)]), &game, false));
+    let join = rooms(vec![("!dave:test", joined(vec![message("@dave:test", "!join game-2")]))]);
+    actions.extend(bot.handle_sync(&Json::object(vec![("rooms", join)]), &game, false));
+
+    // What the homeserver would hand a fresh bot in its first sync.
+    let saved: Vec<Json> = ["m.direct", "org.wordgame.hosted"]
+        .iter()
+        .map(|kind| {
+            let content = actions
+                .iter()
+                .rev()
+                .find_map(|action| match action {
+                    Action::SetAccountData { kind: saved, content } if saved == kind => Some(content.clone()),
+                    _ => None,
+                })
+                .unwrap();
+            Json::object(vec![("type", (*kind).into()), ("content", content)])
+        })
+        .collect();
+
+    let mut bot = MatrixBot::new("@wordgame:test");
+    let guesses = direct.iter().map(|(user, room)| (*room, joined(vec![message(user, "cat")]))).collect();
+    let sync = Json::object(vec![
+        ("account_data", Json::object(vec![("events", saved.into())])),
+        ("rooms", rooms(guesses)),
+    ]);
+    let reveals: Vec<String> = bot
+        .handle_sync(&sync, &game, false)
+        .into_iter()
+        .filter_map(|action| match action {
+            Action::Send { room, text, .. } if text.starts_with("Round 1:") => Some(room),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(reveals, ["!games:test", "!carol:test", "!dave:test"]);
+}
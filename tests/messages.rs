@@ -0,0 +1,55 @@
+use std::collections::BTreeSet;
+
+use word_game::locale::{
+    messages::{self, Catalog},
+    Locale,
+};
+
+fn placeholders(text: &str) -> BTreeSet<&str> {
+    text.split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split('}').next())
+        .collect()
+}
+
+#[test]
+fn every_key_exists_in_every_locale() {
+    let english = Catalog::bundled(Locale::English);
+    for locale in Locale::ALL.iter() {
+        let catalog = Catalog::bundled(*locale);
+        for key in english.keys() {
+            assert!(catalog.get(key).is_some(), "{} is missing {}", locale.code(), key);
+        }
+        for key in catalog.keys() {
+            assert!(english.get(key).is_some(), "{} has unknown key {}", locale.code(), key);
+        }
+    }
+}
+
+#[test]
+fn translations_keep_placeholders() {
+    let english = Catalog::bundled(Locale::English);
+    for locale in Locale::ALL.iter() {
+        let catalog = Catalog::bundled(*locale);
+        for key in english.keys() {
+            let expected = placeholders(english.get(key).unwrap());
+            let actual = placeholders(catalog.get(key).unwrap_or_default());
+            assert_eq!(expected, actual, "{} changes the placeholders of {}", locale.code(), key);
+        }
+    }
+}
+
+#[test]
+fn fills_in_arguments() {
+    let text = messages::text(Locale::German, "guess.received", &[("round", &3)]);
+    assert!(text.contains('3'));
+    assert!(!text.contains("{round}"));
+}
+
+#[test]
+fn falls_back_to_english_then_key() {
+    let catalog = Catalog::parse(Locale::Turkish, "welcome = Hoş geldiniz!\n");
+    assert_eq!(catalog.text("welcome", &[]), "Hoş geldiniz!");
+    assert_eq!(catalog.text("round.mismatch", &[]), "Aww, shucks... Those didn't match.");
+    assert_eq!(catalog.text("no.such.key", &[]), "no.such.key");
+}
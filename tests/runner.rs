@@ -0,0 +1,26 @@
+use word_game::runner::{RunnerFactory, RunnerType};
+
+// The environment is shared by the whole test binary, so everything that
+// changes it lives in this one test.
+#[test]
+fn refuses_to_start_with_bad_settings() {
+    std::env::set_var("WORD_GAME_HTTP_ADDR", "not an address");
+    let error = RunnerFactory::create(RunnerType::Http).err().unwrap();
+    assert_eq!(error.code(), "config");
+    assert!(error.to_string().contains("WORD_GAME_HTTP_ADDR"));
+
+    std::env::remove_var("WORD_GAME_MATRIX_HOMESERVER");
+    let error = RunnerFactory::create(RunnerType::Matrix).err().unwrap();
+    assert_eq!(error.code(), "config");
+    assert!(error.to_string().contains("WORD_GAME_MATRIX_HOMESERVER"));
+
+    std::env::set_var("WORD_GAME_XMPP_JID", "bot@example.com");
+    std::env::set_var("WORD_GAME_XMPP_PASSWORD", "secret");
+    std::env::set_var("WORD_GAME_XMPP_MUC", "@muc.example.com");
+    let error = RunnerFactory::create(RunnerType::XMPP).err().unwrap();
+    assert_eq!(error.code(), "config");
+    assert!(error.to_string().contains("WORD_GAME_XMPP_MUC"));
+
+    std::env::set_var("WORD_GAME_HTTP_ADDR", "127.0.0.1:0");
+    assert!(RunnerFactory::create(RunnerType::Http).is_ok());
+}
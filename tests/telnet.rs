@@ -0,0 +1,183 @@
+use std::{net::SocketAddr, time::Duration};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::timeout,
+};
+use word_game::runner::telnet::{
+    protocol::{LineDecoder, DO, ECHO, ECHO_OFF, ECHO_ON, IAC, SB, SE, WILL},
+    session::Session,
+    TelnetRunner,
+};
+
+use common::new_game;
+
+mod common;
+
+/// A terminal stand-in that keeps everything the server sent it.
+struct Terminal {
+    stream: TcpStream,
+    received: Vec<u8>,
+}
+
+impl Terminal {
+    async fn connect(address: SocketAddr) -> Self {
+        let mut terminal = Self {
+            stream: TcpStream::connect(address).await.unwrap(),
+            received: vec![],
+        };
+        terminal.expect("What's your name?").await;
+        terminal
+    }
+
+    async fn send(&mut self, line: &str) {
+        self.stream.write_all(format!("{}\r\n", line).as_bytes()).await.unwrap();
+    }
+
+    /// Reads until the server has sent `text`, returning everything up to
+    /// and including it.
+    async fn expect<T: AsRef<[u8]> + ?Sized>(&mut self, text: &T) -> Vec<u8> {
+        let needle = text.as_ref();
+        loop {
+            if let Some(at) = self.received.windows(needle.len()).position(|window| window == needle) {
+                return self.received.drain(..at + needle.len()).collect();
+            }
+            let mut buffer = [0; 1024];
+            let read = timeout(Duration::from_secs(5), self.stream.read(&mut buffer))
+                .await
+                .unwrap_or_else(|_| panic!("Timed out waiting for {:?} in {:?}", String::from_utf8_lossy(needle), String::from_utf8_lossy(&self.received)))
+                .unwrap();
+            assert!(read > 0, "The server hung up before sending {:?}", String::from_utf8_lossy(needle));
+            self.received.extend_from_slice(&buffer[..read]);
+        }
+    }
+}
+
+async fn with_server<F, Fut>(script: F)
+where
+    F: FnOnce(SocketAddr) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let mut runner = TelnetRunner::with_address("127.0.0.1:0".parse().unwrap());
+    let address = runner.bind().await.unwrap();
+    common::serve_during(|shutdown| runner.run_until(new_game(), shutdown), script(address)).await;
+}
+
+#[tokio::test]
+async fn two_terminals_play_a_game() {
+    with_server(|address| async move {
+        let mut alice = Terminal::connect(address).await;
+        let mut bob = Terminal::connect(address).await;
+        alice.send("Alice").await;
+        alice.expect("Hello, Alice!").await;
+        bob.send("Bob").await;
+        bob.expect("Hello, Bob!").await;
+
+        alice.send("/new").await;
+        alice.expect("/join game-1").await;
+        bob.send("/join game-1").await;
+        // Once both seats are taken the terminals stop showing guesses.
+        bob.expect("You are playing with Alice").await;
+        bob.expect(&ECHO_OFF).await;
+        alice.expect("Bob joined your game!").await;
+        alice.expect(&ECHO_OFF).await;
+
+        alice.send("cat").await;
+        alice.expect("Got your guess for round 1!").await;
+        bob.expect("Alice has made a guess for round 1.").await;
+        bob.send("dog").await;
+        for terminal in [&mut alice, &mut bob] {
+            terminal.expect(r#"Round 1: Alice said "cat" and Bob said "dog"."#).await;
+            terminal.expect("Round 2: type your guess.").await;
+        }
+
+        alice.send("pet").await;
+        bob.send("pet").await;
+        for terminal in [&mut alice, &mut bob] {
+            terminal.expect("Alice and Bob, you won!!! Congrats!").await;
+            terminal.expect(&ECHO_ON).await;
+        }
+
+        alice.send("/quit").await;
+        alice.expect("Bye!").await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn refuses_names_in_use() {
+    with_server(|address| async move {
+        let mut first = Terminal::connect(address).await;
+        first.send("alice").await;
+        first.expect("Hello, alice!").await;
+        first.expect("Your secret is ").await;
+        let secret = String::from_utf8(first.expect(".").await).unwrap();
+        let secret = secret.trim_end_matches('.');
+
+        let mut second = Terminal::connect(address).await;
+        second.send("not a name").await;
+        second.expect("Names must be one word").await;
+        second.send("ALICE").await;
+        second.expect("ALICE is already playing from another terminal.").await;
+
+        first.send("/quit").await;
+        first.expect("Bye!").await;
+        // Once the first terminal is gone the name is free again, but only
+        // for whoever has its secret.
+        second.send("Alice").await;
+        second.expect("Alice already has a secret.").await;
+        second.send("guess").await;
+        second.expect("I couldn't tell who you are.").await;
+        second.expect("What's your name?").await;
+        second.send("Alice").await;
+        second.send(secret).await;
+        second.expect("Hello, alice!").await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn leaving_tells_the_partner() {
+    with_server(|address| async move {
+        let mut alice = Terminal::connect(address).await;
+        let mut bob = Terminal::connect(address).await;
+        alice.send("alice").await;
+        alice.send("/play").await;
+        alice.expect("/join game-1").await;
+        bob.send("bob").await;
+        bob.send("/play").await;
+        bob.expect("You are playing with alice").await;
+
+        bob.send("/new").await;
+        bob.expect("You are already playing in game game-1.").await;
+        bob.send("/leave").await;
+        bob.expect("You left the game.").await;
+        alice.expect("bob left the game. Someone else can take their seat with /join game-1.").await;
+    })
+    .await;
+}
+
+#[test]
+fn refuses_names_of_players_from_elsewhere() {
+    let game = new_game();
+    let mut word_game = game.lock().unwrap();
+    word_game.create_player("carol", "Carol").unwrap();
+
+    let mut session = Session::new();
+    let replies = session.handle_line("Carol", &mut word_game, |_| false);
+    assert!(replies[0].1.starts_with("Carol belongs to a player from elsewhere."));
+    assert_eq!(session.player(), None);
+    assert!(!session.hides_input(&word_game));
+}
+
+#[test]
+fn decodes_lines_without_telnet_commands() {
+    let mut decoder = LineDecoder::new();
+    assert_eq!(decoder.decode(&[IAC, DO, ECHO, b'c', b'a']), Vec::<String>::new());
+    assert_eq!(
+        decoder.decode(&[IAC, SB, 24, 0, b'x', IAC, SE, b't', b'x', 0x08, b'\r', 0, b'd', b'o', b'g', b'\n']),
+        vec!["cat", "dog"]
+    );
+    assert_eq!(decoder.decode(&[IAC, WILL, 3, b'o', b'k', b'\r', b'\n', b'\r', b'\n']), vec!["ok", ""]);
+}
@@ -0,0 +1,76 @@
+
+use ratatui::crossterm::event::KeyCode;
+use word_game::{
+    game::WordGame,
+    runner::tui::{Phase, TuiApp},
+};
+
+use common::word_game;
+
+mod common;
+
+fn type_line(app: &mut TuiApp, word_game: &mut WordGame, text: &str) {
+    for c in text.chars() {
+        app.handle_key(KeyCode::Char(c), word_game);
+    }
+    app.handle_key(KeyCode::Enter, word_game);
+}
+
+#[test]
+fn passes_the_keyboard_between_players() {
+    let mut word_game = word_game();
+    let mut app = TuiApp::new();
+
+    type_line(&mut app, &mut word_game, "Alice");
+    assert_eq!(app.phase(), Phase::EnterName(1));
+    type_line(&mut app, &mut word_game, "Bob");
+    assert_eq!(app.phase(), Phase::Handoff(0));
+    assert!(app.status().starts_with("Alice goes first"));
+
+    // Nobody types while the keyboard is being handed over.
+    app.handle_key(KeyCode::Char('x'), &mut word_game);
+    app.handle_key(KeyCode::Enter, &mut word_game);
+    assert_eq!(app.phase(), Phase::Guess(0));
+    type_line(&mut app, &mut word_game, "cat");
+    assert_eq!(app.phase(), Phase::Handoff(1));
+    app.handle_key(KeyCode::Enter, &mut word_game);
+    app.handle_key(KeyCode::Char('a'), &mut word_game);
+    app.handle_key(KeyCode::Backspace, &mut word_game);
+    type_line(&mut app, &mut word_game, "dog");
+    assert_eq!(app.phase(), Phase::Handoff(0));
+    assert!(app.status().contains("Round 1"));
+
+    app.handle_key(KeyCode::Enter, &mut word_game);
+    type_line(&mut app, &mut word_game, "pet");
+    app.handle_key(KeyCode::Enter, &mut word_game);
+    type_line(&mut app, &mut word_game, "pet");
+    assert_eq!(app.phase(), Phase::Won);
+    assert!(!app.has_quit());
+    app.handle_key(KeyCode::Enter, &mut word_game);
+    assert!(app.has_quit());
+}
+
+#[test]
+fn shows_errors_instead_of_quitting() {
+    let mut word_game = word_game();
+    let mut app = TuiApp::new();
+    type_line(&mut app, &mut word_game, "Alice");
+    type_line(&mut app, &mut word_game, "Bob");
+
+    app.handle_key(KeyCode::Enter, &mut word_game);
+    type_line(&mut app, &mut word_game, "   ");
+    assert_eq!(app.phase(), Phase::Guess(0));
+    assert_eq!(app.status(), "Type a word to guess. Try again!");
+
+    type_line(&mut app, &mut word_game, "cat");
+    // Player 2 leaves the game from elsewhere; the game rejects their
+    // next guess and the app keeps going.
+    word_game.leave_game("player2", &word_game.current_game_for("player2").unwrap().unwrap().id).unwrap();
+    app.handle_key(KeyCode::Enter, &mut word_game);
+    type_line(&mut app, &mut word_game, "dog");
+    assert_eq!(app.status(), "You are not in a game right now.");
+    assert!(!app.has_quit());
+
+    app.handle_key(KeyCode::Esc, &mut word_game);
+    assert!(app.has_quit());
+}
@@ -0,0 +1,253 @@
+use std::{net::SocketAddr, time::Duration as StdDuration};
+
+use chrono::Duration;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
+    time::timeout,
+};
+use word_game::{
+    json::Json,
+    runner::websocket::{
+        frame::{accept_key, write_frame, Frame, Message, MessageReader, Opcode},
+        WebSocketRunner,
+    },
+    time::clock::ManualClock,
+};
+
+use common::{new_game_at, start};
+
+mod common;
+
+const MASK: [u8; 4] = [0x12, 0x34, 0x56, 0x78];
+
+/// A browser stand-in that has finished the opening handshake.
+struct Client {
+    reader: MessageReader<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl Client {
+    async fn connect(address: SocketAddr) -> Self {
+        let (reader, mut writer) = TcpStream::connect(address).await.unwrap().into_split();
+        let key = "dGhlIHNhbXBsZSBub25jZQ==";
+        let request = format!(
+            "GET /ws HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: keep-alive, Upgrade\r\nSec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            key
+        );
+        writer.write_all(request.as_bytes()).await.unwrap();
+
+        let mut reader = BufReader::new(reader);
+        let mut head = vec![];
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            head.push(line.trim_end().to_string());
+        }
+        assert!(head[0].starts_with("HTTP/1.1 101"), "{:?}", head);
+        assert!(head.contains(&format!("Sec-WebSocket-Accept: {}", accept_key(key))));
+        Self { reader: MessageReader::new(reader, false), writer }
+    }
+
+    async fn send(&mut self, command: &str) {
+        write_frame(&mut self.writer, &Frame::text(command), Some(MASK)).await.unwrap();
+    }
+
+    async fn next_message(&mut self) -> Message {
+        timeout(StdDuration::from_secs(5), self.reader.read())
+            .await
+            .expect("Timed out waiting for the server")
+            .unwrap()
+    }
+
+    /// The next event of `kind`, skipping timer ticks and anything else
+    /// in between.
+    async fn expect(&mut self, kind: &str) -> Json {
+        loop {
+            if let Message::Text(text) = self.next_message().await {
+                let event: Json = text.parse().unwrap();
+                if event.get("type").and_then(Json::as_str) == Some(kind) {
+                    return event;
+                }
+                assert_ne!(event.get("type").and_then(Json::as_str), Some("error"), "{}", event);
+            }
+        }
+    }
+}
+
+async fn with_server<F, Fut>(clock: &ManualClock, script: F)
+where
+    F: FnOnce(SocketAddr) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+{
+    let mut runner = WebSocketRunner::with_address("127.0.0.1:0".parse().unwrap())
+        .with_tick_interval(StdDuration::from_millis(20));
+    let address = runner.bind().await.unwrap();
+    common::serve_during(|shutdown| runner.run_until(new_game_at(clock), shutdown), script(address)).await;
+}
+
+#[tokio::test]
+async fn serves_the_browser_client() {
+    with_server(&ManualClock::new(start()), |address| async move {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("text/html"));
+        assert!(response.contains("new WebSocket("));
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(b"GET /ws HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 400"));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn pushes_events_to_both_players() {
+    with_server(&ManualClock::new(start()), |address| async move {
+        let mut alice = Client::connect(address).await;
+        let mut bob = Client::connect(address).await;
+        alice.send(r#"{"type": "hello", "player": "alice", "display_name": "Alice"}"#).await;
+        alice.expect("welcome").await;
+        assert!(alice.expect("game").await.get("game").unwrap().is_null());
+        bob.send(r#"{"type": "hello", "player": "bob"}"#).await;
+        bob.expect("welcome").await;
+        bob.expect("game").await;
+
+        alice.send(r#"{"type": "new_game"}"#).await;
+        let game = alice.expect("game").await;
+        assert_eq!(game.get("game").unwrap().get("id").unwrap().as_str(), Some("game-1"));
+        alice.send(r#"{"type": "new_game"}"#).await;
+        assert_eq!(alice.expect("error").await.get("error").unwrap().as_str(), Some("in_another_game"));
+        bob.send(r#"{"type": "join", "game": "game-1"}"#).await;
+        bob.expect("game").await;
+        let joined = alice.expect("partner_joined").await;
+        assert_eq!(joined.get("player").unwrap().get("username").unwrap().as_str(), Some("bob"));
+
+        alice.send(r#"{"type": "guess", "guess": "cat"}"#).await;
+        alice.expect("guess_accepted").await;
+        assert_eq!(bob.expect("partner_guessed").await.get("round").unwrap().as_u64(), Some(1));
+
+        bob.send(r#"{"type": "guess", "guess": "dog"}"#).await;
+        for client in [&mut alice, &mut bob] {
+            let revealed = client.expect("round_revealed").await;
+            assert_eq!(revealed.get("player_1").unwrap().as_str(), Some("cat"));
+            assert_eq!(revealed.get("player_2").unwrap().as_str(), Some("dog"));
+            assert_eq!(revealed.get("matched").unwrap().as_bool(), Some(false));
+        }
+
+        alice.send(r#"{"type": "guess", "guess": "pet"}"#).await;
+        bob.send(r#"{"type": "guess", "guess": "pet"}"#).await;
+        for client in [&mut alice, &mut bob] {
+            assert_eq!(client.expect("round_revealed").await.get("matched").unwrap().as_bool(), Some(true));
+            assert_eq!(client.expect("game_won").await.get("round").unwrap().as_u64(), Some(2));
+        }
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn ticks_while_the_game_runs() {
+    let clock = ManualClock::new(start());
+    let ticking = clock.clone();
+    with_server(&clock, |address| async move {
+        let mut alice = Client::connect(address).await;
+        let mut bob = Client::connect(address).await;
+        for (client, player) in [(&mut alice, "alice"), (&mut bob, "bob")] {
+            client.send(&format!(r#"{{"type": "hello", "player": "{}"}}"#, player)).await;
+            client.expect("game").await;
+            client.send(r#"{"type": "matchmake"}"#).await;
+            client.expect("game").await;
+        }
+
+        ticking.advance(Duration::seconds(65));
+        // Ticks sent before the clock moved may still be on their way.
+        for _ in 0..50 {
+            let tick = bob.expect("tick").await;
+            if tick.get("elapsed_text").unwrap().as_str() == Some("1m 5s") {
+                assert_eq!(tick.get("elapsed").unwrap().as_str(), Some("PT1M5S"));
+                return;
+            }
+        }
+        panic!("The timer never showed the time the clock moved by");
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn answers_pings_and_refuses_commands_before_hello() {
+    with_server(&ManualClock::new(start()), |address| async move {
+        let mut client = Client::connect(address).await;
+        write_frame(&mut client.writer, &Frame::control(Opcode::Ping, b"hi"), Some(MASK)).await.unwrap();
+        assert_eq!(client.next_message().await, Message::Pong(b"hi".to_vec()));
+
+        client.send(r#"{"type": "new_game"}"#).await;
+        match client.next_message().await {
+            Message::Text(text) => {
+                let error: Json = text.parse().unwrap();
+                assert_eq!(error.get("error").unwrap().as_str(), Some("hello_required"));
+            }
+            other => panic!("Expected an error, got {:?}", other),
+        }
+
+        write_frame(&mut client.writer, &Frame::control(Opcode::Close, &[]), Some(MASK)).await.unwrap();
+        assert_eq!(client.next_message().await, Message::Close);
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn answers_pings_between_the_fragments_of_a_command() {
+    with_server(&ManualClock::new(start()), |address| async move {
+        let mut client = Client::connect(address).await;
+        let first = Frame { fin: false, opcode: Opcode::Text, payload: br#"{"type": "#.to_vec() };
+        write_frame(&mut client.writer, &first, Some(MASK)).await.unwrap();
+        write_frame(&mut client.writer, &Frame::control(Opcode::Ping, b"hi"), Some(MASK)).await.unwrap();
+        assert_eq!(client.next_message().await, Message::Pong(b"hi".to_vec()));
+
+        let rest = Frame { fin: true, opcode: Opcode::Continuation, payload: br#""new_game"}"#.to_vec() };
+        write_frame(&mut client.writer, &rest, Some(MASK)).await.unwrap();
+        assert_eq!(client.expect("error").await.get("error").unwrap().as_str(), Some("hello_required"));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn only_lets_the_secret_holder_back_in() {
+    with_server(&ManualClock::new(start()), |address| async move {
+        let mut alice = Client::connect(address).await;
+        alice.send(r#"{"type": "hello", "player": "alice"}"#).await;
+        let secret = alice.expect("welcome").await.get("secret").unwrap().as_str().unwrap().to_string();
+        let mut bob = Client::connect(address).await;
+        bob.send(r#"{"type": "hello", "player": "bob"}"#).await;
+        let bobs_secret = bob.expect("welcome").await.get("secret").unwrap().as_str().unwrap().to_string();
+
+        let mut impostor = Client::connect(address).await;
+        for hello in [
+            r#"{"type": "hello", "player": "alice"}"#.to_string(),
+            r#"{"type": "hello", "player": "alice", "secret": "guess"}"#.to_string(),
+            format!(r#"{{"type": "hello", "player": "alice", "secret": "{}"}}"#, bobs_secret),
+        ] {
+            impostor.send(&hello).await;
+            assert_eq!(impostor.expect("error").await.get("error").unwrap().as_str(), Some("unauthorized"));
+        }
+        impostor.send(r#"{"type": "new_game"}"#).await;
+        assert_eq!(impostor.expect("error").await.get("error").unwrap().as_str(), Some("hello_required"));
+
+        let mut again = Client::connect(address).await;
+        again.send(&format!(r#"{{"type": "hello", "player": "alice", "secret": "{}"}}"#, secret)).await;
+        let welcome = again.expect("welcome").await;
+        assert_eq!(welcome.get("secret").unwrap().as_str(), Some(secret.as_str()));
+    })
+    .await;
+}
@@ -0,0 +1,552 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{prelude::*, Duration};
+use word_game::{
+    database::{DatabaseFactory, DatabaseType},
+    game::WordGame,
+    locale::Locale,
+    runner::xmpp::{fake::FakeServer, muc::MucRoom, XmppRunner},
+    time::clock::{ClockEnum, ManualClock},
+};
+use xmpp_parsers::{
+    disco::{DiscoInfoResult, DiscoItemsResult},
+    iq::{Iq, IqType},
+    message::{Message, MessageType},
+    ns,
+    presence::Type as PresenceType,
+    receipts::Received,
+    stanza_error::{DefinedCondition, ErrorType},
+    version::VersionResult,
+    BareJid, Element, Jid,
+};
+use std::convert::TryFrom;
+
+use common::new_game;
+
+mod common;
+
+const BOT: &str = "bot@example.com/word-game";
+const ROOM: &str = "games@muc.example.com";
+
+fn stanza(xml: &str) -> Element {
+    xml.parse().unwrap()
+}
+
+fn body(message: &Message) -> &str {
+    message.bodies.get("").map_or("", |b| b.0.as_str())
+}
+
+#[tokio::test]
+async fn comes_online_with_presence() {
+    let (mut server, connector, shutdown) = FakeServer::new(BOT.parse().unwrap());
+    let mut runner = XmppRunner::with_connector(Box::new(connector), None);
+
+    let script = async move {
+        let presence = server.next_presence().await.unwrap();
+        assert_eq!(presence.type_, PresenceType::None);
+        server.shutdown();
+    };
+
+    let (result, _) = tokio::join!(runner.run_until(new_game(), shutdown), script);
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn answers_service_discovery() {
+    let (mut server, connector, shutdown) = FakeServer::new(BOT.parse().unwrap());
+    let mut runner = XmppRunner::with_connector(Box::new(connector), None);
+
+    let script = async move {
+        server.next_presence().await.unwrap();
+        server.inject(stanza(
+            "<iq xmlns='jabber:client' type='get' id='disco1' from='alice@example.com/phone' to='bot@example.com/word-game'>
+                <query xmlns='http://jabber.org/protocol/disco#info'/>
+            </iq>",
+        ));
+
+        let iq = server.next_iq().await.unwrap();
+        assert_eq!(iq.id, "disco1");
+        let info = match iq.payload {
+            IqType::Result(Some(payload)) => DiscoInfoResult::try_from(payload).unwrap(),
+            other => panic!("Expected a disco#info result, got {:?}", other),
+        };
+        assert_eq!(info.identities[0].category, "client");
+        assert_eq!(info.identities[0].type_, "bot");
+        let features: Vec<_> = info.features.iter().map(|f| f.var.as_str()).collect();
+        assert!(features.contains(&ns::RECEIPTS));
+        assert!(features.contains(&ns::DISCO_INFO));
+        assert!(features.contains(&ns::VERSION));
+        assert!(features.contains(&ns::PING));
+        server.shutdown();
+    };
+
+    let (result, _) = tokio::join!(runner.run_until(new_game(), shutdown), script);
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn names_ad_hoc_commands_in_the_players_language() {
+    let (mut server, connector, shutdown) = FakeServer::new(BOT.parse().unwrap());
+    let mut runner = XmppRunner::with_connector(Box::new(connector), None);
+    let game = new_game();
+    {
+        let mut word_game = game.lock().unwrap();
+        word_game.create_player("alice@example.com", "alice").unwrap();
+        word_game.set_player_language("alice@example.com", Locale::German).unwrap();
+    }
+
+    let script = async move {
+        server.next_presence().await.unwrap();
+        server.inject(stanza(
+            "<iq xmlns='jabber:client' type='get' id='items1' from='alice@example.com/phone' to='bot@example.com/word-game'>
+                <query xmlns='http://jabber.org/protocol/disco#items' node='http://jabber.org/protocol/commands'/>
+            </iq>",
+        ));
+        let items = match server.next_iq().await.unwrap().payload {
+            IqType::Result(Some(payload)) => DiscoItemsResult::try_from(payload).unwrap(),
+            other => panic!("Expected a disco#items result, got {:?}", other),
+        };
+        let start = items.items.iter().find(|item| item.node.as_deref() == Some("start-game")).unwrap();
+        assert_eq!(start.name.as_deref(), Some("Ein neues Spiel starten"));
+
+        server.inject(stanza(
+            "<iq xmlns='jabber:client' type='get' id='info1' from='alice@example.com/phone' to='bot@example.com/word-game'>
+                <query xmlns='http://jabber.org/protocol/disco#info' node='http://jabber.org/protocol/commands'/>
+            </iq>",
+        ));
+        let info = match server.next_iq().await.unwrap().payload {
+            IqType::Result(Some(payload)) => DiscoInfoResult::try_from(payload).unwrap(),
+            other => panic!("Expected a disco#info result, got {:?}", other),
+        };
+        assert_eq!(info.identities[0].lang.as_deref(), Some("de"));
+        assert_eq!(info.identities[0].name.as_deref(), Some("Befehle des Wortspiels"));
+        server.shutdown();
+    };
+
+    let (result, _) = tokio::join!(runner.run_until(game, shutdown), script);
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn answers_version_and_ping() {
+    let (mut server, connector, shutdown) = FakeServer::new(BOT.parse().unwrap());
+    let mut runner = XmppRunner::with_connector(Box::new(connector), None);
+
+    let script = async move {
+        server.next_presence().await.unwrap();
+        server.inject(stanza(
+            "<iq xmlns='jabber:client' type='get' id='v1' from='alice@example.com/phone' to='bot@example.com/word-game'>
+                <query xmlns='jabber:iq:version'/>
+            </iq>",
+        ));
+        let iq = server.next_iq().await.unwrap();
+        let version = match iq.payload {
+            IqType::Result(Some(payload)) => VersionResult::try_from(payload).unwrap(),
+            other => panic!("Expected a version result, got {:?}", other),
+        };
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+        server.inject(stanza(
+            "<iq xmlns='jabber:client' type='get' id='p1' from='example.com' to='bot@example.com/word-game'>
+                <ping xmlns='urn:xmpp:ping'/>
+            </iq>",
+        ));
+        let iq = server.next_iq().await.unwrap();
+        assert_eq!(iq.id, "p1");
+        assert!(matches!(iq.payload, IqType::Result(None)));
+        server.shutdown();
+    };
+
+    let (result, _) = tokio::join!(runner.run_until(new_game(), shutdown), script);
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn rejects_unsupported_iqs() {
+    let (mut server, connector, shutdown) = FakeServer::new(BOT.parse().unwrap());
+    let mut runner = XmppRunner::with_connector(Box::new(connector), None);
+
+    let script = async move {
+        server.next_presence().await.unwrap();
+        server.inject(stanza(
+            "<iq xmlns='jabber:client' type='get' id='t1' from='alice@example.com/phone' to='bot@example.com/word-game'>
+                <time xmlns='urn:xmpp:time'/>
+            </iq>",
+        ));
+        let iq = server.next_iq().await.unwrap();
+        assert_eq!(iq.id, "t1");
+        match iq.payload {
+            IqType::Error(error) => {
+                assert_eq!(error.type_, ErrorType::Cancel);
+                assert_eq!(error.defined_condition, DefinedCondition::ServiceUnavailable);
+            }
+            other => panic!("Expected an error, got {:?}", other),
+        }
+        server.shutdown();
+    };
+
+    let (result, _) = tokio::join!(runner.run_until(new_game(), shutdown), script);
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn acknowledges_receipts_and_replies() {
+    let (mut server, connector, shutdown) = FakeServer::new(BOT.parse().unwrap());
+    let mut runner = XmppRunner::with_connector(Box::new(connector), None);
+
+    let script = async move {
+        server.next_presence().await.unwrap();
+        server.inject(stanza(
+            "<message xmlns='jabber:client' type='chat' id='m1' from='alice@example.com/phone' to='bot@example.com'>
+                <body>hello</body>
+                <request xmlns='urn:xmpp:receipts'/>
+            </message>",
+        ));
+
+        let receipt = server.next_message().await.unwrap();
+        let received = receipt
+            .payloads
+            .into_iter()
+            .find_map(|p| Received::try_from(p).ok())
+            .unwrap();
+        assert_eq!(received.id, "m1");
+
+        let composing = server.next_message().await.unwrap();
+        assert!(composing.bodies.is_empty());
+        assert!(composing.payloads.iter().any(|p| p.is("composing", ns::CHATSTATES)));
+
+        let reply = next_reply(&mut server).await.unwrap();
+        assert_eq!(reply.to, Some("alice@example.com/phone".parse().unwrap()));
+        assert!(body(&reply).contains("Commands:"));
+        server.shutdown();
+    };
+
+    let (result, _) = tokio::join!(runner.run_until(new_game(), shutdown), script);
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn plays_a_game_in_a_room() {
+    let (mut server, connector, shutdown) = FakeServer::new(BOT.parse().unwrap());
+    let room = MucRoom::new(ROOM.parse::<BareJid>().unwrap(), "WordGame");
+    let mut runner = XmppRunner::with_connector(Box::new(connector), Some(room));
+
+    let script = async move {
+        server.next_presence().await.unwrap();
+        let join = server.next_presence().await.unwrap();
+        assert_eq!(join.to, Some("games@muc.example.com/WordGame".parse().unwrap()));
+
+        for (nick, jid) in &[("alice", "alice@example.com/phone"), ("bob", "bob@example.com/laptop")] {
+            server.inject(stanza(&format!(
+                "<presence xmlns='jabber:client' from='games@muc.example.com/{}'>
+                    <x xmlns='http://jabber.org/protocol/muc#user'>
+                        <item affiliation='none' role='participant' jid='{}'/>
+                    </x>
+                </presence>",
+                nick, jid
+            )));
+        }
+
+        server.inject(groupchat("alice", "/start"));
+        let started = next_reply(&mut server).await.unwrap();
+        assert_eq!(started.type_, MessageType::Groupchat);
+        assert!(body(&started).contains("alice started a new game"));
+
+        server.inject(groupchat("bob", "/join"));
+        let joined = next_reply(&mut server).await.unwrap();
+        assert!(body(&joined).contains("alice and bob are playing"));
+
+        server.inject(private("alice", "apple"));
+        let waiting = next_reply(&mut server).await.unwrap();
+        assert_eq!(waiting.to, Some("games@muc.example.com/alice".parse().unwrap()));
+        assert!(body(&waiting).contains("Waiting for your partner"));
+
+        server.inject(private("bob", "banana"));
+        next_reply(&mut server).await.unwrap();
+        let reveal = next_reply(&mut server).await.unwrap();
+        assert_eq!(reveal.type_, MessageType::Groupchat);
+        assert!(body(&reveal).contains("Round 1"));
+        assert!(body(&reveal).contains("apple"));
+        assert!(body(&reveal).contains("banana"));
+        let mismatch = next_reply(&mut server).await.unwrap();
+        assert!(body(&mismatch).contains("didn't match"));
+
+        server.inject(chat_state("bob", "composing"));
+        let thinking = next_reply(&mut server).await.unwrap();
+        assert_eq!(thinking.to, Some("games@muc.example.com/alice".parse().unwrap()));
+        assert!(body(&thinking).contains("partner is thinking"));
+
+        server.inject(private("alice", "fruit"));
+        next_reply(&mut server).await.unwrap();
+        server.inject(private("bob", "Fruit"));
+        next_reply(&mut server).await.unwrap();
+        next_reply(&mut server).await.unwrap();
+        let won = next_reply(&mut server).await.unwrap();
+        assert!(body(&won).contains("you won"));
+        let html = won.payloads.iter().find(|p| p.is("html", ns::XHTML_IM)).unwrap();
+        let strong: Vec<_> = html
+            .get_child("body", ns::XHTML)
+            .unwrap()
+            .children()
+            .flat_map(|p| p.children())
+            .flat_map(|code| code.children())
+            .filter(|e| e.name() == "strong")
+            .map(|e| e.text())
+            .collect();
+        assert!(strong.contains(&"fruit".to_string()));
+        server.shutdown();
+    };
+
+    let (result, _) = tokio::join!(runner.run_until(new_game(), shutdown), script);
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn corrects_a_pending_guess() {
+    let (mut server, connector, shutdown) = FakeServer::new(BOT.parse().unwrap());
+    let room = MucRoom::new(ROOM.parse::<BareJid>().unwrap(), "WordGame");
+    let mut runner = XmppRunner::with_connector(Box::new(connector), Some(room));
+
+    let script = async move {
+        server.next_presence().await.unwrap();
+        server.next_presence().await.unwrap();
+        for nick in &["alice", "bob"] {
+            server.inject(stanza(&format!(
+                "<presence xmlns='jabber:client' from='games@muc.example.com/{}'>
+                    <x xmlns='http://jabber.org/protocol/muc#user'>
+                        <item affiliation='none' role='participant'/>
+                    </x>
+                </presence>",
+                nick
+            )));
+        }
+        server.inject(groupchat("alice", "/start"));
+        next_reply(&mut server).await.unwrap();
+        server.inject(groupchat("bob", "/join"));
+        next_reply(&mut server).await.unwrap();
+
+        server.inject(private("alice", "aple"));
+        next_reply(&mut server).await.unwrap();
+        server.inject(correction("alice", "alice-aple", "apple"));
+        let updated = next_reply(&mut server).await.unwrap();
+        assert!(body(&updated).contains("Updated your guess for round 1"));
+
+        server.inject(private("bob", "pear"));
+        next_reply(&mut server).await.unwrap();
+        let reveal = next_reply(&mut server).await.unwrap();
+        assert!(body(&reveal).contains("\"apple\""));
+        next_reply(&mut server).await.unwrap();
+
+        server.inject(correction("alice", "alice-aple", "pear"));
+        let locked = next_reply(&mut server).await.unwrap();
+        assert!(body(&locked).contains("locked in"));
+
+        // Bob's guess sealed the round, so it can't be changed either.
+        server.inject(correction("bob", "bob-pear", "apple"));
+        let locked = next_reply(&mut server).await.unwrap();
+        assert!(body(&locked).contains("Your guess for round 1 is locked in"));
+        server.shutdown();
+    };
+
+    let (result, _) = tokio::join!(runner.run_until(new_game(), shutdown), script);
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn frees_the_room_when_its_game_is_left() {
+    let (mut server, connector, shutdown) = FakeServer::new(BOT.parse().unwrap());
+    let room = MucRoom::new(ROOM.parse::<BareJid>().unwrap(), "WordGame");
+    let mut runner = XmppRunner::with_connector(Box::new(connector), Some(room));
+
+    let script = async move {
+        server.next_presence().await.unwrap();
+        server.next_presence().await.unwrap();
+        for nick in &["alice", "bob"] {
+            server.inject(stanza(&format!(
+                "<presence xmlns='jabber:client' from='games@muc.example.com/{}'>
+                    <x xmlns='http://jabber.org/protocol/muc#user'>
+                        <item affiliation='none' role='participant'/>
+                    </x>
+                </presence>",
+                nick
+            )));
+        }
+        server.inject(groupchat("alice", "/start"));
+        next_reply(&mut server).await.unwrap();
+        server.inject(groupchat("bob", "/start"));
+        let busy = next_reply(&mut server).await.unwrap();
+        assert!(body(&busy).contains("already in progress"));
+
+        server.inject(groupchat("bob", "/leave"));
+        let refused = next_reply(&mut server).await.unwrap();
+        assert!(body(&refused).contains("bob, you are not playing"));
+        server.inject(groupchat("alice", "/leave"));
+        let left = next_reply(&mut server).await.unwrap();
+        assert!(body(&left).contains("alice left, so the game is over"));
+
+        server.inject(groupchat("bob", "/start"));
+        let started = next_reply(&mut server).await.unwrap();
+        assert!(body(&started).contains("bob started a new game"));
+        server.shutdown();
+    };
+
+    let (result, _) = tokio::join!(runner.run_until(new_game(), shutdown), script);
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn starts_a_game_with_an_ad_hoc_command() {
+    let (mut server, connector, shutdown) = FakeServer::new(BOT.parse().unwrap());
+    let mut runner = XmppRunner::with_connector(Box::new(connector), None);
+
+    let script = async move {
+        server.next_presence().await.unwrap();
+        server.inject(stanza(
+            "<iq xmlns='jabber:client' type='set' id='cmd1' from='alice@example.com/phone' to='bot@example.com/word-game'>
+                <command xmlns='http://jabber.org/protocol/commands' node='start-game' action='execute'/>
+            </iq>",
+        ));
+
+        let iq = server.next_iq().await.unwrap();
+        let command = match iq.payload {
+            IqType::Result(Some(payload)) => payload,
+            other => panic!("Expected a command result, got {:?}", other),
+        };
+        assert_eq!(command.attr("status"), Some("completed"));
+        let note = command.get_child("note", "http://jabber.org/protocol/commands").unwrap();
+        assert!(note.text().contains("Game created"));
+
+        // A second game is refused while the first is unfinished.
+        server.inject(stanza(
+            "<iq xmlns='jabber:client' type='set' id='cmd2' from='alice@example.com/phone' to='bot@example.com/word-game'>
+                <command xmlns='http://jabber.org/protocol/commands' node='start-game' action='execute'/>
+            </iq>",
+        ));
+        let iq = server.next_iq().await.unwrap();
+        let command = match iq.payload {
+            IqType::Result(Some(payload)) => payload,
+            other => panic!("Expected a command result, got {:?}", other),
+        };
+        let note = command.get_child("note", "http://jabber.org/protocol/commands").unwrap();
+        assert_eq!(note.attr("type"), Some("error"));
+        assert!(note.text().contains("You are still in game"));
+        server.shutdown();
+    };
+
+    let (result, _) = tokio::join!(runner.run_until(new_game(), shutdown), script);
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn forgets_abandoned_ad_hoc_sessions() {
+    let (mut server, connector, shutdown) = FakeServer::new(BOT.parse().unwrap());
+    let mut runner = XmppRunner::with_connector(Box::new(connector), None);
+    let clock = ManualClock::new(Utc.ymd(2024, 5, 1).and_hms(12, 0, 0));
+    let database = DatabaseFactory::create(DatabaseType::InMemory);
+    let game = WordGame::new(Arc::new(Mutex::new(database))).with_clock(ClockEnum::from(clock.clone()));
+
+    let script = async move {
+        server.next_presence().await.unwrap();
+        let execute = |id: &str, session: Option<&str>| {
+            let (session, form) = match session {
+                Some(session) => (
+                    format!("sessionid='{}'", session),
+                    "<x xmlns='jabber:x:data' type='submit'><field var='game_id'><value>nope</value></field></x>",
+                ),
+                None => (String::new(), ""),
+            };
+            stanza(&format!(
+                "<iq xmlns='jabber:client' type='set' id='{}' from='alice@example.com/phone' to='bot@example.com/word-game'>
+                    <command xmlns='http://jabber.org/protocol/commands' node='join-game' {}>{}</command>
+                </iq>",
+                id, session, form
+            ))
+        };
+        let command = |iq: Iq| match iq.payload {
+            IqType::Result(Some(payload)) => payload,
+            other => panic!("Expected a command result, got {:?}", other),
+        };
+
+        server.inject(execute("cmd1", None));
+        let form = command(server.next_iq().await.unwrap());
+        assert_eq!(form.attr("status"), Some("executing"));
+        let session = form.attr("sessionid").unwrap().to_string();
+
+        clock.advance(Duration::minutes(11));
+        server.inject(execute("cmd2", Some(&session)));
+        let again = command(server.next_iq().await.unwrap());
+        assert_eq!(again.attr("status"), Some("executing"), "the expired session is not continued");
+        assert_ne!(again.attr("sessionid"), Some(session.as_str()));
+        server.shutdown();
+    };
+
+    let (result, _) = tokio::join!(runner.run_until(Arc::new(Mutex::new(game)), shutdown), script);
+    result.unwrap();
+}
+
+#[tokio::test]
+async fn reconnects_after_a_disconnect() {
+    let (mut server, connector, shutdown) = FakeServer::new(BOT.parse().unwrap());
+    let room = MucRoom::new(ROOM.parse::<BareJid>().unwrap(), "WordGame");
+    let mut runner = XmppRunner::with_connector(Box::new(connector), Some(room));
+
+    let script = async move {
+        server.next_presence().await.unwrap();
+        server.next_presence().await.unwrap();
+        server.disconnect();
+
+        let presence = server.next_presence().await.unwrap();
+        assert_eq!(presence.type_, PresenceType::None);
+        let rejoin = server.next_presence().await.unwrap();
+        assert_eq!(rejoin.to, Some("games@muc.example.com/WordGame".parse().unwrap()));
+        server.shutdown();
+    };
+
+    let (result, _) = tokio::join!(runner.run_until(new_game(), shutdown), script);
+    result.unwrap();
+}
+
+/// Skips chat state notifications and receipts to the next message with a body.
+async fn next_reply(server: &mut FakeServer) -> Option<Message> {
+    loop {
+        let message = server.next_message().await?;
+        if !message.bodies.is_empty() {
+            return Some(message);
+        }
+    }
+}
+
+fn groupchat(nick: &str, text: &str) -> Message {
+    let mut message = Message::new(Some(Jid::Bare(ROOM.parse().unwrap())));
+    message.from = Some(format!("{}/{}", ROOM, nick).parse().unwrap());
+    message.type_ = MessageType::Groupchat;
+    message.bodies.insert(String::new(), xmpp_parsers::message::Body(text.into()));
+    message
+}
+
+fn chat_state(nick: &str, state: &str) -> Element {
+    stanza(&format!(
+        "<message xmlns='jabber:client' type='chat' from='{}/{}' to='{}'>
+            <{} xmlns='http://jabber.org/protocol/chatstates'/>
+        </message>",
+        ROOM, nick, BOT, state
+    ))
+}
+
+fn correction(nick: &str, replaces: &str, text: &str) -> Element {
+    stanza(&format!(
+        "<message xmlns='jabber:client' type='chat' id='{0}-fix-{3}' from='{1}/{0}' to='{2}'>
+            <body>{3}</body>
+            <replace xmlns='urn:xmpp:message-correct:0' id='{4}'/>
+        </message>",
+        nick, ROOM, BOT, text, replaces
+    ))
+}
+
+fn private(nick: &str, text: &str) -> Message {
+    let mut message = Message::new(Some(BOT.parse().unwrap()));
+    message.from = Some(format!("{}/{}", ROOM, nick).parse().unwrap());
+    message.id = Some(format!("{}-{}", nick, text));
+    message.bodies.insert(String::new(), xmpp_parsers::message::Body(text.into()));
+    message
+}